@@ -0,0 +1,55 @@
+//! Full scans of logs with growing record counts. Walking the store by position keeps the
+//! time per record flat, where an index lookup per record grows with the segment size.
+//!
+//! ```sh
+//! cargo bench --bench scan
+//! ```
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use proglog_rs::storage::log::{Durability, Log, LogConfig};
+use std::hint::black_box;
+use tempfile::TempDir;
+
+fn log_with_records(records: u64) -> (TempDir, Log) {
+    let temp_dir = TempDir::new().unwrap();
+    let config = LogConfig::builder()
+        .max_index_entries(10_000)
+        .max_store_bytes(16 * 1024 * 1024)
+        .durability(Durability::Os)
+        .log_dir(temp_dir.path())
+        .build()
+        .unwrap();
+
+    let mut log = Log::new(config).unwrap();
+    for offset in 0..records {
+        log.append(format!("record-{offset:08}").as_bytes())
+            .unwrap();
+    }
+    (temp_dir, log)
+}
+
+fn bench_scan(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scan_full");
+    for records in [1_000u64, 10_000, 50_000] {
+        let (_temp_dir, log) = log_with_records(records);
+
+        group.throughput(Throughput::Elements(records));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(records),
+            &records,
+            |b, &records| {
+                b.iter(|| {
+                    let mut scanned = 0;
+                    for result in log.scan_from(0) {
+                        black_box(result.unwrap());
+                        scanned += 1;
+                    }
+                    assert_eq!(scanned, records);
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_scan);
+criterion_main!(benches);
@@ -0,0 +1,77 @@
+//! Reads at offsets spread over logs with growing segment counts. With a lookup by base
+//! offset the time per read stays flat as the number of segments grows.
+//!
+//! ```sh
+//! cargo bench --bench segment_lookup
+//! ```
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use proglog_rs::storage::log::{Durability, Log, LogConfig};
+use std::hint::black_box;
+use tempfile::TempDir;
+
+/// Segments read from in every run, few enough to all stay open in the segment cache
+const HOT_SEGMENTS: u64 = 32;
+
+/// A log with one record per segment, so offset `n` lives in the `n`th segment
+fn log_with_segments(segments: u64) -> (TempDir, Log) {
+    let temp_dir = TempDir::new().unwrap();
+    let config = LogConfig::builder()
+        .max_record_bytes(64)
+        .max_store_bytes(72)
+        .max_index_entries(1)
+        .max_open_segments(HOT_SEGMENTS as usize)
+        .durability(Durability::Os)
+        .log_dir(temp_dir.path())
+        .build()
+        .unwrap();
+
+    let mut log = Log::new(config).unwrap();
+    for offset in 0..segments {
+        log.append(format!("record-{offset}").as_bytes()).unwrap();
+    }
+    assert_eq!(log.segment_count() as u64, segments);
+    (temp_dir, log)
+}
+
+fn bench_read(c: &mut Criterion) {
+    let mut group = c.benchmark_group("read_spread");
+    for segments in [10u64, 100, 1_000, 4_000] {
+        let (_temp_dir, log) = log_with_segments(segments);
+        let stride = segments.div_ceil(HOT_SEGMENTS);
+        let offsets: Vec<u64> = (0..segments).step_by(stride as usize).collect();
+        assert!(offsets.len() as u64 <= HOT_SEGMENTS);
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(segments),
+            &offsets,
+            |b, offsets| {
+                b.iter(|| {
+                    for &offset in offsets {
+                        black_box(log.read(black_box(offset)).unwrap());
+                    }
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_scan_start(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scan_start");
+    for segments in [10u64, 100, 1_000, 4_000] {
+        let (_temp_dir, log) = log_with_segments(segments);
+        let middle = segments / 2;
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(segments),
+            &middle,
+            |b, &middle| {
+                b.iter(|| black_box(log.scan_from(black_box(middle)).next().unwrap().unwrap()))
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_read, bench_scan_start);
+criterion_main!(benches);
@@ -9,7 +9,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     ];
     // tonic_prost_build::compile_protos(protos, &["proto"])?;
 
+    // descriptor set for gRPC server reflection
+    let out_dir = std::path::PathBuf::from(std::env::var("OUT_DIR")?);
+
     tonic_prost_build::configure()
+        .file_descriptor_set_path(out_dir.join("log_descriptor.bin"))
         .build_server(true)
         .build_client(true)
         .compile_protos(protos, &["proto"])?;
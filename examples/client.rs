@@ -1,10 +1,28 @@
-use proglog_rs::server::grpc::proto::{self, log_client::LogClient};
+use proglog_rs::client::{ClientConfig, LogClient};
+use proglog_rs::server::tls::ClientTls;
+use std::env;
+use std::path::PathBuf;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let mut client = LogClient::connect("http://[::1]:50051").await?;
+    // PROGLOG_TLS_CA enables TLS, PROGLOG_CLIENT_CERT/PROGLOG_CLIENT_KEY add a client certificate for mTLS
+    let tls = env::var_os("PROGLOG_TLS_CA").map(|ca_cert_path| ClientTls {
+        ca_cert_path: ca_cert_path.into(),
+        domain_name: Some(env::var("PROGLOG_TLS_DOMAIN").unwrap_or_else(|_| "localhost".into())),
+        cert_path: env::var_os("PROGLOG_CLIENT_CERT").map(PathBuf::from),
+        key_path: env::var_os("PROGLOG_CLIENT_KEY").map(PathBuf::from),
+    });
 
-    println!("🔌 Connected to server");
+    let scheme = if tls.is_some() { "https" } else { "http" };
+    let config = ClientConfig {
+        servers: vec![format!("{scheme}://[::1]:50051")],
+        tls,
+        ..Default::default()
+    };
+
+    let mut client = LogClient::connect(config).await?;
+
+    println!("🔌 Connected to server, leader is {}", client.leader());
 
     // produce some records
     println!("\n📝 Producing records...");
@@ -18,12 +36,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let mut offsets = Vec::new();
     for record in &records {
-        let request = tonic::Request::new(proto::ProduceRequest {
-            record: record.as_bytes().to_vec(),
-        });
-
-        let response = client.produce(request).await?;
-        let offset = response.into_inner().offset;
+        let offset = client.produce(record.as_bytes()).await?;
         offsets.push(offset);
 
         println!("  ✅ Produced: '{}' → offset {}", record, offset);
@@ -33,30 +46,19 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Read in reverse order to demonstrate random access
     for &offset in offsets.iter().rev() {
-        let request = tonic::Request::new(proto::ConsumeRequest { offset });
-        let response = client.consume(request).await?;
-        let inner = response.into_inner();
-        let record = String::from_utf8_lossy(&inner.record);
-        println!("  🔍 Offset {} → '{}'", inner.offset, record);
+        let data = client.consume(offset).await?;
+        let record = String::from_utf8_lossy(&data);
+        println!("  🔍 Offset {} → '{}'", offset, record);
     }
 
     println!("\n📜 Sequential scan from offset 0...");
 
     // Demonstrate sequential scanning
     let mut offset = 0;
-    loop {
-        let request = tonic::Request::new(proto::ConsumeRequest { offset });
-        match client.consume(request).await {
-            Ok(response) => {
-                let inner = response.into_inner();
-                let record = String::from_utf8_lossy(&inner.record);
-                println!("  📄 Offset {} → '{}'", inner.offset, record);
-                offset += 1;
-            }
-            Err(_) => {
-                break;
-            }
-        }
+    while let Ok(data) = client.consume(offset).await {
+        let record = String::from_utf8_lossy(&data);
+        println!("  📄 Offset {} → '{}'", offset, record);
+        offset += 1;
     }
 
     println!("\n✨ All operations completed successfully!");
@@ -0,0 +1,330 @@
+//! Command line client for proglog servers.
+//!
+//! ```text
+//! seq 3 | proglog-cli produce              # one record per line
+//! proglog-cli produce --whole-file a.bin   # one record per file
+//! proglog-cli consume --from 0 --to 10 --format json
+//! proglog-cli tail -n 5 -f
+//! proglog-cli offsets
+//! ```
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use clap::{Parser, Subcommand, ValueEnum};
+use proglog_rs::client::{ClientConfig, LogClient, RecordStream};
+use proglog_rs::server::tls::ClientTls;
+use serde_json::json;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "proglog-cli",
+    version,
+    about = "Command line client for proglog servers"
+)]
+struct Cli {
+    /// Server addresses, the first one is assumed to be the leader
+    #[arg(
+        short,
+        long = "server",
+        env = "PROGLOG_SERVERS",
+        value_delimiter = ',',
+        default_value = "http://[::1]:50051"
+    )]
+    servers: Vec<String>,
+
+    /// Bearer token sent with every request
+    #[arg(long, env = "PROGLOG_TOKEN")]
+    token: Option<String>,
+
+    /// PEM CA the server certificate is verified against, enables TLS
+    #[arg(long, env = "PROGLOG_TLS_CA")]
+    tls_ca: Option<PathBuf>,
+
+    /// Name expected in the server certificate
+    #[arg(long, env = "PROGLOG_TLS_DOMAIN")]
+    tls_domain: Option<String>,
+
+    /// PEM client certificate for mutual TLS
+    #[arg(long, env = "PROGLOG_CLIENT_CERT", requires = "tls_key")]
+    tls_cert: Option<PathBuf>,
+
+    /// PEM private key for `tls_cert`
+    #[arg(long, env = "PROGLOG_CLIENT_KEY", requires = "tls_cert")]
+    tls_key: Option<PathBuf>,
+
+    /// How records and offsets are printed
+    #[arg(short = 'o', long, value_enum, default_value_t = Format::Raw, global = true)]
+    format: Format,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Append records read from stdin or files, one per line, and print their offsets
+    Produce {
+        /// Files to read, stdin when none are given
+        files: Vec<PathBuf>,
+
+        /// Append every file as a single record instead of one record per line
+        #[arg(long, requires = "files")]
+        whole_file: bool,
+    },
+    /// Print the records in `from..to`
+    Consume {
+        /// First offset to print, defaults to the oldest record
+        #[arg(long)]
+        from: Option<u64>,
+
+        /// Offset to stop before, defaults to the end of the log
+        #[arg(long)]
+        to: Option<u64>,
+
+        /// Print the newest record first
+        #[arg(long)]
+        reverse: bool,
+    },
+    /// Print the last records of the log
+    Tail {
+        /// Number of records to print
+        #[arg(short = 'n', long, default_value_t = 10)]
+        lines: u64,
+
+        /// Keep printing records as they are appended
+        #[arg(short, long)]
+        follow: bool,
+    },
+    /// Print the oldest stored offset and the offset of the next record
+    Offsets,
+}
+
+/// Output formats. `raw` and `hex` print one record per line, `json` prints one object per line
+/// with the record base64 encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Raw,
+    Hex,
+    Json,
+}
+
+impl Format {
+    fn write_record(self, out: &mut impl Write, offset: u64, record: &[u8]) -> io::Result<()> {
+        match self {
+            Format::Raw => out.write_all(record)?,
+            Format::Hex => out.write_all(hex(record).as_bytes())?,
+            Format::Json => {
+                let line = json!({ "offset": offset, "record": BASE64.encode(record) });
+                out.write_all(line.to_string().as_bytes())?;
+            }
+        }
+        out.write_all(b"\n")
+    }
+
+    fn write_offset(self, out: &mut impl Write, offset: u64) -> io::Result<()> {
+        match self {
+            Format::Raw | Format::Hex => writeln!(out, "{offset}"),
+            Format::Json => writeln!(out, "{}", json!({ "offset": offset })),
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+impl Cli {
+    fn client_config(&self) -> ClientConfig {
+        let tls = self.tls_ca.as_ref().map(|ca_cert_path| ClientTls {
+            ca_cert_path: ca_cert_path.clone(),
+            domain_name: self.tls_domain.clone(),
+            cert_path: self.tls_cert.clone(),
+            key_path: self.tls_key.clone(),
+        });
+
+        ClientConfig {
+            servers: self.servers.clone(),
+            tls,
+            token: self.token.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("proglog-cli: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = LogClient::connect(cli.client_config()).await?;
+    let format = cli.format;
+    let mut out = io::stdout().lock();
+
+    let result = match cli.command {
+        Command::Produce { files, whole_file } => {
+            produce(&mut client, &mut out, format, &files, whole_file).await
+        }
+        Command::Consume { from, to, reverse } => {
+            let (base_offset, next_offset) = client.offsets().await?;
+            let from = from.unwrap_or(base_offset);
+            let to = to.unwrap_or(next_offset);
+            if to <= from {
+                Ok(())
+            } else {
+                let records = client.consume_range(from..to, reverse).await?;
+                print_records(records, &mut out, format, false).await
+            }
+        }
+        Command::Tail { lines, follow } => {
+            let (base_offset, next_offset) = client.offsets().await?;
+            let from = next_offset.saturating_sub(lines).max(base_offset);
+            let records = client.consume_stream(from, follow).await?;
+            print_records(records, &mut out, format, follow).await
+        }
+        Command::Offsets => {
+            let (base_offset, next_offset) = client.offsets().await?;
+            match format {
+                Format::Raw | Format::Hex => writeln!(
+                    out,
+                    "base_offset: {base_offset}\nnext_offset: {next_offset}"
+                )?,
+                Format::Json => writeln!(
+                    out,
+                    "{}",
+                    json!({ "base_offset": base_offset, "next_offset": next_offset })
+                )?,
+            }
+            Ok(())
+        }
+    };
+
+    match result {
+        // the reader went away, e.g. `proglog-cli tail -f | head`
+        Err(e)
+            if e.downcast_ref::<io::Error>()
+                .is_some_and(|e| e.kind() == io::ErrorKind::BrokenPipe) =>
+        {
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+async fn produce(
+    client: &mut LogClient,
+    out: &mut impl Write,
+    format: Format,
+    files: &[PathBuf],
+    whole_file: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if whole_file {
+        for path in files {
+            let record = std::fs::read(path)?;
+            format.write_offset(out, client.produce(&record).await?)?;
+        }
+        return Ok(());
+    }
+
+    if files.is_empty() {
+        return produce_lines(client, out, format, io::stdin().lock()).await;
+    }
+    for path in files {
+        produce_lines(client, out, format, BufReader::new(File::open(path)?)).await?;
+    }
+    Ok(())
+}
+
+/// Appends every line as a record, without its line ending
+async fn produce_lines(
+    client: &mut LogClient,
+    out: &mut impl Write,
+    format: Format,
+    mut reader: impl BufRead,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut line = Vec::new();
+    while reader.read_until(b'\n', &mut line)? > 0 {
+        if line.ends_with(b"\n") {
+            line.pop();
+            if line.ends_with(b"\r") {
+                line.pop();
+            }
+        }
+
+        format.write_offset(out, client.produce(&line).await?)?;
+        line.clear();
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Prints records until the server ends the stream; a following stream never ends
+async fn print_records(
+    mut records: RecordStream,
+    out: &mut impl Write,
+    format: Format,
+    follow: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    while let Some((offset, record)) = records.next().await? {
+        format.write_record(out, offset, &record)?;
+        if follow {
+            out.flush()?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(format: Format, record: &[u8]) -> String {
+        let mut out = Vec::new();
+        format.write_record(&mut out, 7, record).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_cli_definition() {
+        use clap::CommandFactory;
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_client_certificate_env_is_not_the_server_one() {
+        use clap::CommandFactory;
+        let command = Cli::command();
+        let env = |id: &str| {
+            command
+                .get_arguments()
+                .find(|arg| arg.get_id() == id)
+                .and_then(|arg| arg.get_env())
+                .map(|env| env.to_string_lossy().to_string())
+        };
+        // PROGLOG_TLS_CERT/PROGLOG_TLS_KEY hold the server's own certificate
+        assert_eq!(env("tls_cert").as_deref(), Some("PROGLOG_CLIENT_CERT"));
+        assert_eq!(env("tls_key").as_deref(), Some("PROGLOG_CLIENT_KEY"));
+    }
+
+    #[test]
+    fn test_record_formats() {
+        assert_eq!(render(Format::Raw, b"hi"), "hi\n");
+        assert_eq!(render(Format::Hex, b"hi\x00"), "686900\n");
+        assert_eq!(
+            render(Format::Json, b"hi"),
+            "{\"offset\":7,\"record\":\"aGk=\"}\n"
+        );
+    }
+}
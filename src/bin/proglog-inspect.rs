@@ -0,0 +1,325 @@
+//! Offline inspector for log directories. Files are only ever read, so it is safe to point at
+//! a log a server is still writing to, or at a damaged one before recovery repairs it.
+//!
+//! ```text
+//! proglog-inspect segments data/
+//! proglog-inspect records data/ --from 10 --to 20
+//! proglog-inspect index data/ --segment 0 -o json
+//! proglog-inspect verify data/ --repair
+//! ```
+//!
+//! `verify --repair` is the only command that writes, and must not be run against a log
+//! that is open elsewhere.
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use clap::{Parser, Subcommand, ValueEnum};
+use proglog_rs::storage::inspect::{SegmentFiles, segment_base_offsets};
+use proglog_rs::storage::log::{Log, LogConfig};
+use serde_json::json;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Bytes of a record shown in text output
+const PREVIEW_BYTES: usize = 64;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "proglog-inspect",
+    version,
+    about = "Read-only inspector for proglog log directories"
+)]
+struct Cli {
+    /// `text` for people, `json` for one object per line
+    #[arg(short = 'o', long, value_enum, default_value_t = Format::Text, global = true)]
+    format: Format,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// List the segments with their offsets, sizes and fill ratio
+    Segments {
+        /// Log directory
+        dir: PathBuf,
+
+        /// Segment store limit the fill ratio is computed against
+        #[arg(long, default_value_t = LogConfig::default().max_store_bytes)]
+        max_store_bytes: u64,
+
+        /// Segment index limit the fill ratio is computed against
+        #[arg(long, default_value_t = LogConfig::default().max_index_entries)]
+        max_index_entries: u64,
+    },
+    /// Dump records with their store positions
+    Records {
+        /// Log directory
+        dir: PathBuf,
+
+        /// Only dump the segment with this base offset
+        #[arg(long)]
+        segment: Option<u64>,
+
+        /// First offset to dump
+        #[arg(long)]
+        from: Option<u64>,
+
+        /// Offset to stop before
+        #[arg(long)]
+        to: Option<u64>,
+    },
+    /// Dump the raw entries of a segment index
+    Index {
+        /// Log directory
+        dir: PathBuf,
+
+        /// Base offset of the segment
+        #[arg(long)]
+        segment: u64,
+    },
+    /// Check for torn records, bad index entries, offset gaps and orphan files
+    Verify {
+        /// Log directory
+        dir: PathBuf,
+
+        /// Repair what can be repaired instead of only reporting it
+        #[arg(long)]
+        repair: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let mut out = io::stdout().lock();
+
+    match run(cli, &mut out) {
+        Ok(true) => ExitCode::SUCCESS,
+        // some records could not be read, they have been reported inline
+        Ok(false) => ExitCode::FAILURE,
+        Err(e)
+            if e.downcast_ref::<io::Error>()
+                .is_some_and(|e| e.kind() == io::ErrorKind::BrokenPipe) =>
+        {
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("proglog-inspect: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Returns false if any record could not be read, or verification left issues unrepaired
+fn run(cli: Cli, out: &mut impl Write) -> Result<bool, Box<dyn std::error::Error>> {
+    let format = cli.format;
+
+    match cli.command {
+        Command::Segments {
+            dir,
+            max_store_bytes,
+            max_index_entries,
+        } => {
+            if format == Format::Text {
+                writeln!(
+                    out,
+                    "{:>20}  {:>20}  {:>8}  {:>10}  {:>10}  {:>8}  {:>6}",
+                    "BASE_OFFSET", "NEXT_OFFSET", "ENTRIES", "USED", "FILE", "IDX_FILE", "FILL"
+                )?;
+            }
+
+            for base_offset in segment_base_offsets(&dir)? {
+                let summary = SegmentFiles::open(&dir, base_offset)?.summary();
+                let fill = summary.fill_ratio(max_store_bytes, max_index_entries);
+                match format {
+                    Format::Text => writeln!(
+                        out,
+                        "{:>20}  {:>20}  {:>8}  {:>10}  {:>10}  {:>8}  {:>5.1}%",
+                        summary.base_offset,
+                        summary.next_offset,
+                        summary.index_entries,
+                        summary.store_used_bytes,
+                        summary.store_file_bytes,
+                        summary.index_file_bytes,
+                        fill * 100.0
+                    )?,
+                    Format::Json => writeln!(
+                        out,
+                        "{}",
+                        json!({
+                            "base_offset": summary.base_offset,
+                            "next_offset": summary.next_offset,
+                            "index_entries": summary.index_entries,
+                            "store_used_bytes": summary.store_used_bytes,
+                            "store_file_bytes": summary.store_file_bytes,
+                            "index_file_bytes": summary.index_file_bytes,
+                            "fill_ratio": fill,
+                        })
+                    )?,
+                }
+            }
+            Ok(true)
+        }
+        Command::Records {
+            dir,
+            segment,
+            from,
+            to,
+        } => {
+            let base_offsets = match segment {
+                Some(base_offset) => vec![base_offset],
+                None => segment_base_offsets(&dir)?,
+            };
+            let wanted = |offset: u64| {
+                from.is_none_or(|from| offset >= from) && to.is_none_or(|to| offset < to)
+            };
+
+            let mut intact = true;
+            for base_offset in base_offsets {
+                let segment = SegmentFiles::open(&dir, base_offset)?;
+                for entry in segment.index_entries().filter(|e| wanted(e.offset)) {
+                    match segment.record(&entry) {
+                        Ok(record) => {
+                            write_record(out, format, record.offset, record.position, &record.data)?
+                        }
+                        Err(e) => {
+                            intact = false;
+                            write_unreadable(out, format, entry.offset, entry.position, &e)?;
+                        }
+                    }
+                }
+            }
+            Ok(intact)
+        }
+        Command::Index { dir, segment } => {
+            let segment = SegmentFiles::open(&dir, segment)?;
+            for entry in segment.index_entries() {
+                match format {
+                    Format::Text => {
+                        writeln!(out, "{}\t{}\t{}", entry.slot, entry.offset, entry.position)?
+                    }
+                    Format::Json => writeln!(
+                        out,
+                        "{}",
+                        json!({
+                            "slot": entry.slot,
+                            "offset": entry.offset,
+                            "position": entry.position,
+                        })
+                    )?,
+                }
+            }
+            Ok(true)
+        }
+        Command::Verify { dir, repair } => {
+            let report = if repair {
+                Log::repair(&dir)?
+            } else {
+                Log::verify(&dir)?
+            };
+
+            for finding in &report.findings {
+                match format {
+                    Format::Text => {
+                        let state = if finding.repaired {
+                            "repaired"
+                        } else {
+                            "unrepaired"
+                        };
+                        writeln!(out, "{}\t{state}", finding.issue)?
+                    }
+                    Format::Json => writeln!(out, "{}", serde_json::to_string(finding)?)?,
+                }
+            }
+            if format == Format::Text {
+                writeln!(
+                    out,
+                    "{} segments, {} records, {} issues",
+                    report.segments,
+                    report.records,
+                    report.findings.len()
+                )?;
+            }
+            Ok(report.is_consistent())
+        }
+    }
+}
+
+fn write_record(
+    out: &mut impl Write,
+    format: Format,
+    offset: u64,
+    position: u64,
+    data: &[u8],
+) -> io::Result<()> {
+    match format {
+        Format::Text => writeln!(
+            out,
+            "{offset}\t{position}\t{}\t{}",
+            data.len(),
+            preview(data)
+        ),
+        Format::Json => writeln!(
+            out,
+            "{}",
+            json!({
+                "offset": offset,
+                "position": position,
+                "len": data.len(),
+                "record": BASE64.encode(data),
+            })
+        ),
+    }
+}
+
+fn write_unreadable(
+    out: &mut impl Write,
+    format: Format,
+    offset: u64,
+    position: u64,
+    err: &dyn std::error::Error,
+) -> io::Result<()> {
+    match format {
+        Format::Text => writeln!(out, "{offset}\t{position}\t-\t<unreadable: {err}>"),
+        Format::Json => writeln!(
+            out,
+            "{}",
+            json!({ "offset": offset, "position": position, "error": err.to_string() })
+        ),
+    }
+}
+
+/// Escapes non-printable bytes and cuts long records short
+fn preview(data: &[u8]) -> String {
+    let shown = &data[..data.len().min(PREVIEW_BYTES)];
+    let mut preview = shown.escape_ascii().to_string();
+    if shown.len() < data.len() {
+        preview.push_str("...");
+    }
+    preview
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli_definition() {
+        use clap::CommandFactory;
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_preview() {
+        assert_eq!(preview(b"plain"), "plain");
+        assert_eq!(preview(b"tab\tnul\0"), "tab\\tnul\\x00");
+        assert_eq!(preview(&[b'a'; 100]), format!("{}...", "a".repeat(64)));
+    }
+}
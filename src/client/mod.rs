@@ -0,0 +1,649 @@
+//! Client wrapper around the generated gRPC stub that is aware of the cluster.
+//! Produce requests go to the leader (following `NotLeader` redirects), consume requests are
+//! spread over the followers, and recoverable failures are retried with exponential backoff.
+//! The cluster topology is resolved through the `GetServers` RPC.
+use crate::ProglogResult;
+use crate::errors::{ConsensusError, NetworkError, ProglogError};
+use crate::server::auth::AUTHORIZATION_METADATA_KEY;
+use crate::server::grpc::LEADER_ID_METADATA_KEY;
+use crate::server::grpc::proto::{self, log_client::LogClient as GrpcLogClient};
+use crate::server::status::NOT_LEADER_REASON;
+use crate::server::tls::ClientTls;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::ops::Range;
+use std::time::Duration;
+use tonic::metadata::{Ascii, MetadataValue};
+use tonic::transport::{Channel, ClientTlsConfig, Endpoint, Uri};
+use tonic::{Code, Request, Status, Streaming};
+use tonic_types::StatusExt;
+use tracing::{debug, info, instrument, warn};
+
+/// Configuration for the [`LogClient`]
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    /// Addresses of the known servers, e.g. `http://[::1]:50051`. The first one is assumed to be the leader
+    pub servers: Vec<String>,
+    /// Maximum number of retries for a single request
+    pub max_retries: u32,
+    /// Backoff before the first retry, doubled after every failed attempt
+    pub initial_backoff: Duration,
+    /// Upper bound for the backoff between two attempts
+    pub max_backoff: Duration,
+    /// Timeout applied to every request and connection attempt
+    pub request_timeout: Duration,
+    /// TLS settings; server addresses must use the `https` scheme when set
+    pub tls: Option<ClientTls>,
+    /// Bearer token sent with every request for authentication
+    pub token: Option<String>,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            servers: vec!["http://[::1]:50051".to_string()],
+            max_retries: 5,
+            initial_backoff: Duration::from_millis(50),
+            max_backoff: Duration::from_secs(2),
+            request_timeout: Duration::from_secs(5),
+            tls: None,
+            token: None,
+        }
+    }
+}
+
+struct Server {
+    id: String,
+    addr: String,
+    client: GrpcLogClient<Channel>,
+}
+
+/// LogClient talks to a cluster of log servers.
+/// Writes always go to the current leader, reads are load-balanced round-robin across followers.
+pub struct LogClient {
+    config: ClientConfig,
+    tls: Option<ClientTlsConfig>,
+    authorization: Option<MetadataValue<Ascii>>,
+    servers: Vec<Server>,
+    leader: usize,
+    next_follower: usize,
+}
+
+impl LogClient {
+    /// Creates a client and resolves the cluster topology from the configured servers.
+    /// If none of them can be reached the configured list is used as is.
+    pub async fn connect(config: ClientConfig) -> ProglogResult<Self> {
+        let mut client = Self::new(config)?;
+        if let Err(e) = client.discover().await {
+            warn!(error = %e, "Could not resolve cluster topology, using configured servers");
+        }
+        Ok(client)
+    }
+
+    /// Creates a client for the configured servers. Connections are established lazily on first use.
+    pub fn new(config: ClientConfig) -> ProglogResult<Self> {
+        if config.servers.is_empty() {
+            return Err(ProglogError::Config {
+                message: "at least one server address is required".to_string(),
+            });
+        }
+
+        let tls = config.tls.as_ref().map(ClientTls::load).transpose()?;
+        let authorization = config
+            .token
+            .as_ref()
+            .map(|token| {
+                format!("Bearer {token}")
+                    .parse()
+                    .map_err(|_| ProglogError::Config {
+                        message: "token contains invalid characters".to_string(),
+                    })
+            })
+            .transpose()?;
+
+        let servers = config
+            .servers
+            .iter()
+            .map(|addr| Self::connect_lazy(addr, addr, config.request_timeout, tls.as_ref()))
+            .collect::<ProglogResult<Vec<_>>>()?;
+
+        Ok(Self {
+            config,
+            tls,
+            authorization,
+            servers,
+            leader: 0,
+            next_follower: 0,
+        })
+    }
+
+    /// Returns the address of the server currently believed to be the leader
+    pub fn leader(&self) -> &str {
+        &self.servers[self.leader].addr
+    }
+
+    /// Asks the known servers for the cluster topology via `GetServers` and switches to it.
+    /// Existing connections are reused for servers that are still part of the cluster.
+    #[instrument(skip(self))]
+    pub async fn discover(&mut self) -> ProglogResult<()> {
+        let mut last_err = None;
+
+        for idx in 0..self.servers.len() {
+            let mut client = self.servers[idx].client.clone();
+            let servers = match client
+                .get_servers(self.request(proto::GetServersRequest {}))
+                .await
+            {
+                Ok(response) => response.into_inner().servers,
+                Err(status) => {
+                    last_err = Some(self.classify(status));
+                    continue;
+                }
+            };
+
+            // a standalone server doesn't know about any cluster, keep what we have
+            if servers.is_empty() {
+                return Ok(());
+            }
+
+            let mut resolved = Vec::with_capacity(servers.len());
+            let mut leader = 0;
+            for server in servers {
+                let mut addr = endpoint_uri(&server.rpc_addr, self.tls.is_some());
+                // a server advertising its wildcard bind address can only be reached through
+                // the endpoint that answered
+                if is_unspecified(&addr) {
+                    warn!(server = %server.id, rpc_addr = %server.rpc_addr, "Server advertised an unspecified address, keeping the configured endpoint");
+                    addr = self.servers[idx].addr.clone();
+                }
+                if server.is_leader {
+                    leader = resolved.len();
+                }
+
+                // channels are cheap to clone; `self.servers` stays intact until every
+                // address has resolved, so a bad one leaves the old topology usable
+                let existing = self.servers.iter().find(|s| s.addr == addr);
+                let resolved_server = match existing {
+                    Some(existing) => Server {
+                        id: server.id,
+                        addr,
+                        client: existing.client.clone(),
+                    },
+                    None => Self::connect_lazy(
+                        &server.id,
+                        &addr,
+                        self.config.request_timeout,
+                        self.tls.as_ref(),
+                    )?,
+                };
+                resolved.push(resolved_server);
+            }
+
+            self.servers = resolved;
+            self.leader = leader;
+            self.next_follower = 0;
+
+            info!(
+                servers = self.servers.len(),
+                leader = self.leader(),
+                "Resolved cluster topology"
+            );
+            return Ok(());
+        }
+
+        Err(last_err.unwrap_or_else(|| NetworkError::ServerUnavailable.into()))
+    }
+
+    /// Appends a record on the leader and returns its offset
+    #[instrument(skip(self, record), fields(record_len = record.len()))]
+    pub async fn produce(&mut self, record: &[u8]) -> ProglogResult<u64> {
+        let mut attempt = 0;
+        loop {
+            let mut client = self.servers[self.leader].client.clone();
+            let request = self.request(proto::ProduceRequest {
+                record: record.to_vec(),
+            });
+
+            let status = match client.produce(request).await {
+                Ok(response) => return Ok(response.into_inner().offset),
+                Err(status) => status,
+            };
+
+            let err = self.classify(status);
+            match &err {
+                ProglogError::Consensus(ConsensusError::NotLeader { leader_id }) => {
+                    let leader_id = leader_id.clone();
+                    // discovery marks the leader itself; only a leader ID it now knows overrides that
+                    if !self.redirect(leader_id.as_deref())
+                        && self.discover().await.is_ok()
+                        && let Some(idx) = self.known_server(leader_id.as_deref())
+                    {
+                        self.leader = idx;
+                    }
+                }
+                ProglogError::Network(NetworkError::ServerUnavailable) => {
+                    // the leader may be gone, try the next known server
+                    self.leader = (self.leader + 1) % self.servers.len();
+                }
+                _ => {}
+            }
+
+            self.retry_or_fail(&mut attempt, err).await?;
+        }
+    }
+
+    /// Reads the record at the given offset from one of the followers.
+    /// Failed reads are retried on the other servers.
+    #[instrument(skip(self))]
+    pub async fn consume(&mut self, offset: u64) -> ProglogResult<Vec<u8>> {
+        let mut attempt = 0;
+        let mut target = self.pick_reader();
+        loop {
+            let mut client = self.servers[target].client.clone();
+
+            let status = match client
+                .consume(self.request(proto::ConsumeRequest { offset }))
+                .await
+            {
+                Ok(response) => return Ok(response.into_inner().record),
+                Err(status) => status,
+            };
+
+            let err = self.classify(status);
+            self.retry_or_fail(&mut attempt, err).await?;
+
+            // retries walk through every server, including the leader
+            target = (target + 1) % self.servers.len();
+        }
+    }
+
+    /// Returns the lowest offset still stored and the offset the next record will get
+    #[instrument(skip(self))]
+    pub async fn offsets(&mut self) -> ProglogResult<(u64, u64)> {
+        let mut attempt = 0;
+        loop {
+            // only the leader is guaranteed to know about the latest append
+            let mut client = self.servers[self.leader].client.clone();
+
+            let status = match client
+                .get_offsets(self.request(proto::GetOffsetsRequest {}))
+                .await
+            {
+                Ok(response) => {
+                    let response = response.into_inner();
+                    return Ok((response.base_offset, response.next_offset));
+                }
+                Err(status) => status,
+            };
+
+            let err = self.classify(status);
+            self.retry_or_fail(&mut attempt, err).await?;
+        }
+    }
+
+    /// Streams records starting at `offset` from one of the followers. The stream ends once it
+    /// has caught up with the log, unless `follow` keeps it open for new appends.
+    /// Only opening the stream is retried.
+    #[instrument(skip(self))]
+    pub async fn consume_stream(
+        &mut self,
+        offset: u64,
+        follow: bool,
+    ) -> ProglogResult<RecordStream> {
+        self.open_stream(proto::ConsumeStreamRequest {
+            offset,
+            follow,
+            end_offset: None,
+            reverse: false,
+        })
+        .await
+    }
+
+    /// Streams the records in `range`, clamped to the end of the log, oldest first or,
+    /// with `reverse`, newest first. Only opening the stream is retried.
+    #[instrument(skip(self))]
+    pub async fn consume_range(
+        &mut self,
+        range: Range<u64>,
+        reverse: bool,
+    ) -> ProglogResult<RecordStream> {
+        self.open_stream(proto::ConsumeStreamRequest {
+            offset: range.start,
+            follow: false,
+            end_offset: Some(range.end),
+            reverse,
+        })
+        .await
+    }
+
+    /// Opens a stream on one of the followers, retrying on the next one
+    async fn open_stream(
+        &mut self,
+        request: proto::ConsumeStreamRequest,
+    ) -> ProglogResult<RecordStream> {
+        let mut attempt = 0;
+        let mut target = self.pick_reader();
+        loop {
+            let mut client = self.servers[target].client.clone();
+
+            let status = match client.consume_stream(self.request(request)).await {
+                Ok(response) => {
+                    return Ok(RecordStream {
+                        inner: response.into_inner(),
+                        request_timeout: self.config.request_timeout,
+                    });
+                }
+                Err(status) => status,
+            };
+
+            let err = self.classify(status);
+            self.retry_or_fail(&mut attempt, err).await?;
+
+            target = (target + 1) % self.servers.len();
+        }
+    }
+
+    /// Wraps a message in a request carrying the client's credentials
+    fn request<T>(&self, message: T) -> Request<T> {
+        let mut request = Request::new(message);
+        if let Some(authorization) = &self.authorization {
+            request
+                .metadata_mut()
+                .insert(AUTHORIZATION_METADATA_KEY, authorization.clone());
+        }
+        request
+    }
+
+    fn connect_lazy(
+        id: &str,
+        addr: &str,
+        timeout: Duration,
+        tls: Option<&ClientTlsConfig>,
+    ) -> ProglogResult<Server> {
+        let invalid_address = |e: &dyn std::fmt::Display| ProglogError::Config {
+            message: format!("invalid server address {addr}: {e}"),
+        };
+
+        let mut endpoint = Endpoint::from_shared(addr.to_string())
+            .map_err(|e| invalid_address(&e))?
+            .timeout(timeout)
+            .connect_timeout(timeout);
+
+        if let Some(tls) = tls {
+            endpoint = endpoint
+                .tls_config(tls.clone())
+                .map_err(|e| invalid_address(&e))?;
+        }
+
+        Ok(Server {
+            id: id.to_string(),
+            addr: addr.to_string(),
+            client: GrpcLogClient::new(endpoint.connect_lazy()),
+        })
+    }
+
+    /// Points the client at the leader named in a `NotLeader` response, matching it by server ID
+    /// or address. Returns false if the leader is unknown, in which case the next server is tried.
+    fn redirect(&mut self, leader_id: Option<&str>) -> bool {
+        match self.known_server(leader_id) {
+            Some(idx) => {
+                self.leader = idx;
+                info!(leader = self.leader(), "Redirected to leader");
+                true
+            }
+            None => {
+                self.leader = (self.leader + 1) % self.servers.len();
+                false
+            }
+        }
+    }
+
+    /// Returns the index of the server with the given ID or address
+    fn known_server(&self, leader_id: Option<&str>) -> Option<usize> {
+        let leader_id = leader_id?;
+        self.servers
+            .iter()
+            .position(|s| s.id == leader_id || s.addr == leader_id)
+    }
+
+    /// Picks the next follower round-robin, or the leader in a single-node cluster
+    fn pick_reader(&mut self) -> usize {
+        if self.servers.len() == 1 {
+            return self.leader;
+        }
+
+        let followers = self.servers.len() - 1;
+        let nth = self.next_follower % followers;
+        self.next_follower = self.next_follower.wrapping_add(1);
+
+        // skip over the leader's slot
+        if nth >= self.leader { nth + 1 } else { nth }
+    }
+
+    /// Sleeps before the next attempt if the error can be retried, otherwise returns it
+    async fn retry_or_fail(&self, attempt: &mut u32, err: ProglogError) -> ProglogResult<()> {
+        if !(err.is_recoverable() || err.is_temporary()) || *attempt >= self.config.max_retries {
+            return Err(err);
+        }
+
+        let delay = self.backoff(*attempt);
+        *attempt += 1;
+
+        debug!(
+            attempt = *attempt,
+            delay_ms = delay.as_millis() as u64,
+            error = %err,
+            "Retrying request"
+        );
+        tokio::time::sleep(delay).await;
+        Ok(())
+    }
+
+    /// Exponential backoff for the given attempt, capped at `max_backoff`
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.config
+            .initial_backoff
+            .saturating_mul(1 << attempt.min(16))
+            .min(self.config.max_backoff)
+    }
+
+    /// Maps a gRPC status to the crate's error type so retries can be decided on
+    fn classify(&self, status: Status) -> ProglogError {
+        classify(status, self.config.request_timeout)
+    }
+}
+
+/// Records streamed by [`LogClient::consume_stream`]
+pub struct RecordStream {
+    inner: Streaming<proto::ConsumeResponse>,
+    request_timeout: Duration,
+}
+
+impl RecordStream {
+    /// Returns the next `(offset, record)`, or `None` once the server has ended the stream
+    pub async fn next(&mut self) -> ProglogResult<Option<(u64, Vec<u8>)>> {
+        match self.inner.message().await {
+            Ok(response) => Ok(response.map(|response| (response.offset, response.record))),
+            Err(status) => Err(classify(status, self.request_timeout)),
+        }
+    }
+}
+
+/// Status classification shared by [`LogClient`] and [`RecordStream`]
+fn classify(status: Status, request_timeout: Duration) -> ProglogError {
+    let error_info = status.get_details_error_info();
+    match status.code() {
+        // a follower that does not know the leader yet sends the reason without a leader ID
+        Code::FailedPrecondition
+            if status.metadata().contains_key(LEADER_ID_METADATA_KEY)
+                || error_info
+                    .as_ref()
+                    .is_some_and(|info| info.reason == NOT_LEADER_REASON) =>
+        {
+            let leader_id = status
+                .metadata()
+                .get(LEADER_ID_METADATA_KEY)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+                .or_else(|| error_info?.metadata.remove("leader_id"));
+            ConsensusError::NotLeader { leader_id }.into()
+        }
+        Code::Unavailable => NetworkError::ServerUnavailable.into(),
+        Code::DeadlineExceeded => NetworkError::Timeout {
+            timeout_ms: request_timeout.as_millis() as u64,
+        }
+        .into(),
+        code => {
+            let (reason, metadata) = match error_info {
+                Some(info) => (Some(info.reason), info.metadata),
+                None => (None, HashMap::new()),
+            };
+            NetworkError::Rpc {
+                code,
+                message: status.message().to_string(),
+                reason,
+                metadata,
+            }
+            .into()
+        }
+    }
+}
+
+/// Turns a `host:port` RPC address into a URI, leaving full URIs untouched
+fn endpoint_uri(rpc_addr: &str, tls: bool) -> String {
+    if rpc_addr.contains("://") {
+        rpc_addr.to_string()
+    } else if tls {
+        format!("https://{rpc_addr}")
+    } else {
+        format!("http://{rpc_addr}")
+    }
+}
+
+fn is_unspecified(uri: &str) -> bool {
+    uri.parse::<Uri>().is_ok_and(|uri| {
+        uri.host()
+            .map(|host| host.trim_start_matches('[').trim_end_matches(']'))
+            .and_then(|host| host.parse::<IpAddr>().ok())
+            .is_some_and(|ip| ip.is_unspecified())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::status::IntoStatus;
+
+    fn test_client(servers: &[&str]) -> LogClient {
+        LogClient::new(ClientConfig {
+            servers: servers.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_backoff_grows_and_caps() {
+        let client = test_client(&["http://127.0.0.1:1"]);
+
+        assert_eq!(client.backoff(0), Duration::from_millis(50));
+        assert_eq!(client.backoff(1), Duration::from_millis(100));
+        assert_eq!(client.backoff(3), Duration::from_millis(400));
+        assert_eq!(client.backoff(10), Duration::from_secs(2));
+        assert_eq!(client.backoff(u32::MAX), Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_classify_status() {
+        let client = test_client(&["http://127.0.0.1:1"]);
+
+        let mut not_leader = Status::failed_precondition("not the leader");
+        not_leader.metadata_mut().insert(
+            LEADER_ID_METADATA_KEY,
+            "http://127.0.0.1:2".parse().unwrap(),
+        );
+        let err = client.classify(not_leader);
+        assert!(matches!(
+            &err,
+            ProglogError::Consensus(ConsensusError::NotLeader { leader_id: Some(id) })
+                if id == "http://127.0.0.1:2"
+        ));
+        assert!(err.is_recoverable());
+
+        // a follower that does not know the leader only sends the ErrorInfo reason
+        let err = client.classify(ConsensusError::NotLeader { leader_id: None }.into_status());
+        assert!(matches!(
+            &err,
+            ProglogError::Consensus(ConsensusError::NotLeader { leader_id: None })
+        ));
+        assert!(err.is_recoverable());
+
+        let mut not_leader = ConsensusError::NotLeader {
+            leader_id: Some("node-2".to_string()),
+        }
+        .into_status();
+        not_leader.metadata_mut().remove(LEADER_ID_METADATA_KEY);
+        assert!(matches!(
+            client.classify(not_leader),
+            ProglogError::Consensus(ConsensusError::NotLeader { leader_id: Some(id) })
+                if id == "node-2"
+        ));
+
+        let err = client.classify(Status::unavailable("down"));
+        assert!(err.is_recoverable() && err.is_temporary());
+
+        let err = client.classify(Status::not_found("Offset 9 not found"));
+        assert!(matches!(
+            err,
+            ProglogError::Network(NetworkError::Rpc {
+                code: Code::NotFound,
+                ..
+            })
+        ));
+        assert!(!err.is_recoverable() && !err.is_temporary());
+    }
+
+    #[tokio::test]
+    async fn test_reads_round_robin_over_followers() {
+        let mut client = test_client(&[
+            "http://127.0.0.1:1",
+            "http://127.0.0.1:2",
+            "http://127.0.0.1:3",
+        ]);
+
+        let picks: Vec<_> = (0..4).map(|_| client.pick_reader()).collect();
+        assert_eq!(picks, vec![1, 2, 1, 2]);
+
+        client.redirect(Some("http://127.0.0.1:2"));
+        assert_eq!(client.leader(), "http://127.0.0.1:2");
+        let picks: Vec<_> = (0..2).map(|_| client.pick_reader()).collect();
+        assert_eq!(picks, vec![0, 2]);
+    }
+
+    #[test]
+    fn test_endpoint_uri() {
+        assert_eq!(endpoint_uri("[::1]:50051", false), "http://[::1]:50051");
+        assert_eq!(endpoint_uri("[::1]:50051", true), "https://[::1]:50051");
+        assert_eq!(
+            endpoint_uri("http://[::1]:50051", true),
+            "http://[::1]:50051"
+        );
+    }
+
+    #[test]
+    fn test_is_unspecified() {
+        assert!(is_unspecified("http://0.0.0.0:50051"));
+        assert!(is_unspecified("https://[::]:50051"));
+        assert!(!is_unspecified("http://[::1]:50051"));
+        assert!(!is_unspecified("http://proglog-0.internal:50051"));
+    }
+
+    #[test]
+    fn test_requires_servers() {
+        let result = LogClient::new(ClientConfig {
+            servers: Vec::new(),
+            ..Default::default()
+        });
+        assert!(matches!(result, Err(ProglogError::Config { .. })));
+    }
+}
@@ -0,0 +1,432 @@
+//! Configuration for the `proglog` server binary.
+//!
+//! Settings are layered, later sources overriding earlier ones:
+//! built-in defaults, the TOML file passed with `--config`, `PROGLOG_*` environment
+//! variables and finally command line flags.
+//!
+//! ```toml
+//! bind_addr = "0.0.0.0:50051"
+//! advertise_addr = "proglog-0.internal:50051"
+//! data_dir = "/var/lib/proglog"
+//! max_store_bytes = 1048576
+//! max_index_entries = 1000
+//! max_record_bytes = 65536
+//! retention_bytes = 1073741824
+//! durability = "os"
+//! max_open_segments = 64
+//! log_level = "info,proglog_rs::storage=debug"
+//! metrics_addr = "127.0.0.1:9464"
+//! http_addr = "127.0.0.1:8080"
+//! otlp_endpoint = "http://localhost:4317"
+//! ```
+
+use crate::ProglogResult;
+use crate::errors::ProglogError;
+use crate::server::tls::ServerTls;
+use crate::storage::log::{Durability, LogConfig};
+use clap::Parser;
+use serde::Deserialize;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use tonic::transport::Uri;
+use tracing_subscriber::EnvFilter;
+
+pub const DEFAULT_BIND_ADDR: &str = "[::1]:50051";
+pub const DEFAULT_DATA_DIR: &str = "data";
+pub const DEFAULT_LOG_LEVEL: &str = "info";
+pub const DEFAULT_NODE_ID: &str = "proglog-0";
+
+/// Raw settings from a single source. Every field is optional so sources can be layered.
+#[derive(Debug, Default, Clone, Parser, Deserialize)]
+#[command(name = "proglog", version, about = "Distributed commit log server")]
+#[serde(deny_unknown_fields)]
+pub struct ServerArgs {
+    /// Path to a TOML configuration file
+    #[arg(short, long, env = "PROGLOG_CONFIG")]
+    #[serde(skip)]
+    pub config: Option<PathBuf>,
+
+    /// Address the gRPC server listens on
+    #[arg(long, env = "PROGLOG_BIND_ADDR")]
+    pub bind_addr: Option<String>,
+
+    /// `host:port` clients and other servers reach the gRPC server at, advertised through
+    /// `GetServers`; defaults to `bind_addr`, which then has to name a specific IP
+    #[arg(long, env = "PROGLOG_ADVERTISE_ADDR")]
+    pub advertise_addr: Option<String>,
+
+    /// Directory holding the log segments
+    #[arg(long, env = "PROGLOG_DATA_DIR")]
+    pub data_dir: Option<PathBuf>,
+
+    /// Maximum size of a segment's store in bytes
+    #[arg(long, env = "PROGLOG_MAX_STORE_BYTES")]
+    pub max_store_bytes: Option<u64>,
+
+    /// Maximum number of index entries per segment
+    #[arg(long, env = "PROGLOG_MAX_INDEX_ENTRIES")]
+    pub max_index_entries: Option<u64>,
+
+    /// Largest record the server accepts
+    #[arg(long, env = "PROGLOG_MAX_RECORD_BYTES")]
+    pub max_record_bytes: Option<u64>,
+
+    /// Delete the oldest segments once the log grows beyond this many bytes
+    #[arg(long, env = "PROGLOG_RETENTION_BYTES")]
+    pub retention_bytes: Option<u64>,
+
+    /// When appends are flushed to disk: `always` or `os`
+    #[arg(long, env = "PROGLOG_DURABILITY")]
+    pub durability: Option<Durability>,
+
+    /// Most sealed segments kept open (mapped) at once
+    #[arg(long, env = "PROGLOG_MAX_OPEN_SEGMENTS")]
+    pub max_open_segments: Option<usize>,
+
+    /// Tracing filter, e.g. `info` or `info,proglog_rs::storage=debug`
+    #[arg(long, env = "PROGLOG_LOG_LEVEL")]
+    pub log_level: Option<String>,
+
+    /// Address of the HTTP `/metrics` endpoint for Prometheus, disabled when unset
+    #[arg(long, env = "PROGLOG_METRICS_ADDR")]
+    pub metrics_addr: Option<String>,
+
+    /// Address of the HTTP/JSON gateway, disabled when unset. The gateway serves plain HTTP,
+    /// so it cannot be combined with TLS.
+    #[arg(long, env = "PROGLOG_HTTP_ADDR")]
+    pub http_addr: Option<String>,
+
+    /// OTLP/gRPC collector that spans are exported to, disabled when unset
+    #[arg(long, env = "PROGLOG_OTLP_ENDPOINT")]
+    pub otlp_endpoint: Option<String>,
+
+    /// ID this server advertises through `GetServers`
+    #[arg(long, env = "PROGLOG_NODE_ID")]
+    pub node_id: Option<String>,
+
+    /// PEM certificate presented by the server, enables TLS
+    #[arg(long, env = "PROGLOG_TLS_CERT")]
+    pub tls_cert: Option<PathBuf>,
+
+    /// PEM private key for `tls_cert`
+    #[arg(long, env = "PROGLOG_TLS_KEY")]
+    pub tls_key: Option<PathBuf>,
+
+    /// PEM CA used to verify client certificates, enables mutual TLS
+    #[arg(long, env = "PROGLOG_TLS_CLIENT_CA")]
+    pub tls_client_ca: Option<PathBuf>,
+
+    /// ACL policy file, enables authorization
+    #[arg(long, env = "PROGLOG_ACL_POLICY")]
+    pub acl_policy: Option<PathBuf>,
+
+    /// Bearer token file with `subject token` lines
+    #[arg(long, env = "PROGLOG_AUTH_TOKENS")]
+    pub auth_tokens: Option<PathBuf>,
+}
+
+impl ServerArgs {
+    /// Reads settings from a TOML file
+    pub fn from_file(path: impl AsRef<Path>) -> ProglogResult<Self> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)
+            .map_err(|e| config_error(format!("failed to read {}: {e}", path.display())))?;
+
+        toml::from_str(&contents)
+            .map_err(|e| config_error(format!("invalid config file {}: {e}", path.display())))
+    }
+
+    /// Fills every unset field from `other`
+    fn or(self, other: Self) -> Self {
+        Self {
+            config: self.config.or(other.config),
+            bind_addr: self.bind_addr.or(other.bind_addr),
+            advertise_addr: self.advertise_addr.or(other.advertise_addr),
+            data_dir: self.data_dir.or(other.data_dir),
+            max_store_bytes: self.max_store_bytes.or(other.max_store_bytes),
+            max_index_entries: self.max_index_entries.or(other.max_index_entries),
+            max_record_bytes: self.max_record_bytes.or(other.max_record_bytes),
+            retention_bytes: self.retention_bytes.or(other.retention_bytes),
+            durability: self.durability.or(other.durability),
+            max_open_segments: self.max_open_segments.or(other.max_open_segments),
+            log_level: self.log_level.or(other.log_level),
+            metrics_addr: self.metrics_addr.or(other.metrics_addr),
+            http_addr: self.http_addr.or(other.http_addr),
+            otlp_endpoint: self.otlp_endpoint.or(other.otlp_endpoint),
+            node_id: self.node_id.or(other.node_id),
+            tls_cert: self.tls_cert.or(other.tls_cert),
+            tls_key: self.tls_key.or(other.tls_key),
+            tls_client_ca: self.tls_client_ca.or(other.tls_client_ca),
+            acl_policy: self.acl_policy.or(other.acl_policy),
+            auth_tokens: self.auth_tokens.or(other.auth_tokens),
+        }
+    }
+}
+
+/// Fully resolved and validated server settings
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub bind_addr: SocketAddr,
+    pub advertise_addr: String,
+    pub log: LogConfig,
+    pub log_level: String,
+    pub metrics_addr: Option<SocketAddr>,
+    pub http_addr: Option<SocketAddr>,
+    pub otlp_endpoint: Option<String>,
+    pub node_id: String,
+    pub tls: Option<ServerTls>,
+    pub acl_policy: Option<PathBuf>,
+    pub auth_tokens: Option<PathBuf>,
+}
+
+impl ServerConfig {
+    /// Loads the configuration from the process arguments, environment and config file
+    pub fn load() -> ProglogResult<Self> {
+        Self::from_args(ServerArgs::parse())
+    }
+
+    /// Resolves command line/environment settings on top of the config file they point to
+    pub fn from_args(args: ServerArgs) -> ProglogResult<Self> {
+        let file = match &args.config {
+            Some(path) => ServerArgs::from_file(path)?,
+            None => ServerArgs::default(),
+        };
+
+        Self::resolve(args.or(file))
+    }
+
+    fn resolve(args: ServerArgs) -> ProglogResult<Self> {
+        let bind_addr = args.bind_addr.as_deref().unwrap_or(DEFAULT_BIND_ADDR);
+        let bind_addr: SocketAddr = bind_addr
+            .parse()
+            .map_err(|e| config_error(format!("invalid bind_addr `{bind_addr}`: {e}")))?;
+
+        let advertise_addr = match args.advertise_addr {
+            Some(addr) => {
+                let valid = format!("http://{addr}").parse::<Uri>().is_ok_and(|uri| {
+                    uri.port().is_some() && uri.authority().is_some_and(|a| a.as_str() == addr)
+                });
+                if !valid {
+                    return Err(config_error(format!(
+                        "invalid advertise_addr `{addr}`, expected host:port"
+                    )));
+                }
+                addr
+            }
+            // peers cannot dial a wildcard address
+            None if bind_addr.ip().is_unspecified() => {
+                return Err(config_error(
+                    "advertise_addr is required when bind_addr listens on every interface",
+                ));
+            }
+            None => bind_addr.to_string(),
+        };
+
+        let defaults = LogConfig::default();
+        let log = LogConfig::builder()
+            .log_dir(
+                args.data_dir
+                    .unwrap_or_else(|| PathBuf::from(DEFAULT_DATA_DIR)),
+            )
+            .max_store_bytes(args.max_store_bytes.unwrap_or(defaults.max_store_bytes))
+            .max_index_entries(args.max_index_entries.unwrap_or(defaults.max_index_entries))
+            .max_record_bytes(args.max_record_bytes.unwrap_or(defaults.max_record_bytes))
+            .retention_bytes(args.retention_bytes)
+            .durability(args.durability.unwrap_or_default())
+            .max_open_segments(args.max_open_segments.unwrap_or(defaults.max_open_segments))
+            .build()
+            .map_err(|e| config_error(e.to_string()))?;
+
+        let log_level = args
+            .log_level
+            .unwrap_or_else(|| DEFAULT_LOG_LEVEL.to_string());
+        EnvFilter::try_new(&log_level)
+            .map_err(|e| config_error(format!("invalid log_level `{log_level}`: {e}")))?;
+
+        let metrics_addr = args
+            .metrics_addr
+            .map(|addr| {
+                addr.parse()
+                    .map_err(|e| config_error(format!("invalid metrics_addr `{addr}`: {e}")))
+            })
+            .transpose()?;
+
+        let http_addr = args
+            .http_addr
+            .map(|addr| {
+                addr.parse()
+                    .map_err(|e| config_error(format!("invalid http_addr `{addr}`: {e}")))
+            })
+            .transpose()?;
+
+        if let Some(endpoint) = &args.otlp_endpoint {
+            let valid = endpoint
+                .parse::<Uri>()
+                .is_ok_and(|uri| matches!(uri.scheme_str(), Some("http" | "https")));
+            if !valid {
+                return Err(config_error(format!(
+                    "invalid otlp_endpoint `{endpoint}`, expected an http(s) URL"
+                )));
+            }
+        }
+
+        let node_id = args.node_id.unwrap_or_else(|| DEFAULT_NODE_ID.to_string());
+        if node_id.is_empty() {
+            return Err(config_error("node_id must not be empty"));
+        }
+
+        let tls = match (args.tls_cert, args.tls_key) {
+            (Some(cert_path), Some(key_path)) => Some(ServerTls {
+                cert_path,
+                key_path,
+                client_ca_path: args.tls_client_ca,
+            }),
+            (None, None) if args.tls_client_ca.is_some() => {
+                return Err(config_error("tls_client_ca requires tls_cert and tls_key"));
+            }
+            (None, None) => None,
+            _ => {
+                return Err(config_error(
+                    "tls_cert and tls_key must be configured together",
+                ));
+            }
+        };
+
+        // the gateway has no TLS, it would send bearer tokens in the clear and skip mTLS
+        if http_addr.is_some() && tls.is_some() {
+            return Err(config_error(
+                "http_addr cannot be used with TLS, the HTTP gateway only serves plain HTTP",
+            ));
+        }
+
+        Ok(Self {
+            bind_addr,
+            advertise_addr,
+            log,
+            log_level,
+            metrics_addr,
+            http_addr,
+            otlp_endpoint: args.otlp_endpoint,
+            node_id,
+            tls,
+            acl_policy: args.acl_policy,
+            auth_tokens: args.auth_tokens,
+        })
+    }
+}
+
+fn config_error(message: impl Into<String>) -> ProglogError {
+    ProglogError::Config {
+        message: message.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn parse(args: &[&str]) -> ProglogResult<ServerConfig> {
+        let args =
+            ServerArgs::try_parse_from(std::iter::once("proglog").chain(args.iter().copied()))
+                .expect("valid arguments");
+        ServerConfig::from_args(args)
+    }
+
+    #[test]
+    fn test_defaults() {
+        let config = ServerConfig::resolve(ServerArgs::default()).unwrap();
+
+        assert_eq!(config.bind_addr, DEFAULT_BIND_ADDR.parse().unwrap());
+        assert_eq!(config.advertise_addr, "[::1]:50051");
+        assert_eq!(config.log.log_dir, PathBuf::from(DEFAULT_DATA_DIR));
+        assert_eq!(
+            config.log.max_store_bytes,
+            LogConfig::default().max_store_bytes
+        );
+        assert_eq!(config.log.durability, Durability::Always);
+        assert!(config.tls.is_none());
+    }
+
+    #[test]
+    fn test_flags_override_config_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("proglog.toml");
+        fs::write(
+            &path,
+            "bind_addr = \"127.0.0.1:6000\"\nmax_store_bytes = 4096\nmax_record_bytes = 1024\ndurability = \"os\"\n",
+        )
+        .unwrap();
+
+        let config = parse(&[
+            "--config",
+            path.to_str().unwrap(),
+            "--bind-addr",
+            "127.0.0.1:7000",
+        ])
+        .unwrap();
+
+        assert_eq!(config.bind_addr, "127.0.0.1:7000".parse().unwrap());
+        assert_eq!(config.log.max_store_bytes, 4096);
+        assert_eq!(config.log.durability, Durability::Os);
+    }
+
+    #[test]
+    fn test_advertise_addr() {
+        let config = parse(&[
+            "--bind-addr",
+            "0.0.0.0:50051",
+            "--advertise-addr",
+            "proglog-0.internal:50051",
+        ])
+        .unwrap();
+
+        assert_eq!(config.bind_addr, "0.0.0.0:50051".parse().unwrap());
+        assert_eq!(config.advertise_addr, "proglog-0.internal:50051");
+    }
+
+    #[test]
+    fn test_rejects_unknown_file_keys() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("proglog.toml");
+        fs::write(&path, "max_store_byte = 4096\n").unwrap();
+
+        let err = parse(&["--config", path.to_str().unwrap()]).unwrap_err();
+        assert!(matches!(err, ProglogError::Config { .. }));
+    }
+
+    #[test]
+    fn test_validation() {
+        let invalid: [&[&str]; 13] = [
+            &["--bind-addr", "not-an-address"],
+            &["--bind-addr", "0.0.0.0:50051"],
+            &["--advertise-addr", "proglog-0.internal"],
+            &["--advertise-addr", "http://proglog-0.internal:50051"],
+            &["--max-store-bytes", "0"],
+            &["--max-index-entries", "0"],
+            &["--max-record-bytes", "4096", "--max-store-bytes", "4096"],
+            &["--log-level", "info,[=bad"],
+            &["--metrics-addr", "9464"],
+            &["--http-addr", "localhost"],
+            &["--otlp-endpoint", "localhost:4317"],
+            &["--tls-cert", "server.pem"],
+            &[
+                "--tls-cert",
+                "server.pem",
+                "--tls-key",
+                "server-key.pem",
+                "--http-addr",
+                "127.0.0.1:8080",
+            ],
+        ];
+
+        for args in invalid {
+            let err = parse(args).unwrap_err();
+            assert!(
+                matches!(err, ProglogError::Config { .. }),
+                "{args:?} should be rejected, got {err:?}"
+            );
+        }
+    }
+}
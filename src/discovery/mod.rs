@@ -0,0 +1,162 @@
+//! Discovery keeps track of which servers make up the cluster and which of them is the leader.
+//! For now membership is maintained explicitly (static configuration or an external agent calling
+//! `join`/`leave`); a gossip/Raft backed implementation can drive the same API later.
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+use tracing::{debug, info};
+
+/// Information about a single server in the cluster
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerInfo {
+    /// Unique ID of the server
+    pub id: String,
+    /// Address clients use to reach the server's gRPC API, e.g. `[::1]:50051`
+    pub rpc_addr: String,
+    /// Whether the server is the current leader
+    pub is_leader: bool,
+}
+
+#[derive(Debug, Default)]
+struct MembershipState {
+    servers: BTreeMap<String, String>, // id -> rpc_addr
+    leader_id: Option<String>,
+}
+
+/// Membership is this node's view of the cluster topology.
+#[derive(Debug, Default)]
+pub struct Membership {
+    local_id: Option<String>,
+    state: RwLock<MembershipState>,
+}
+
+impl Membership {
+    /// Creates a membership view for the local server, which is the only known member
+    pub fn new(local_id: impl Into<String>, rpc_addr: impl Into<String>) -> Self {
+        let local_id = local_id.into();
+        let mut servers = BTreeMap::new();
+        servers.insert(local_id.clone(), rpc_addr.into());
+
+        Self {
+            local_id: Some(local_id),
+            state: RwLock::new(MembershipState {
+                servers,
+                leader_id: None,
+            }),
+        }
+    }
+
+    /// Creates a membership for a single node cluster where the local server is the leader
+    pub fn single_node(local_id: impl Into<String>, rpc_addr: impl Into<String>) -> Self {
+        let membership = Self::new(local_id, rpc_addr);
+        let local_id = membership.local_id.clone();
+        membership.set_leader(local_id);
+        membership
+    }
+
+    /// Adds (or updates) a server in the cluster
+    pub fn join(&self, id: impl Into<String>, rpc_addr: impl Into<String>) {
+        let id = id.into();
+        let rpc_addr = rpc_addr.into();
+        info!(id, rpc_addr, "Server joined the cluster");
+
+        self.write_state().servers.insert(id, rpc_addr);
+    }
+
+    /// Removes a server from the cluster. If it was the leader, the cluster has no leader until one is set.
+    pub fn leave(&self, id: &str) {
+        info!(id, "Server left the cluster");
+
+        let mut state = self.write_state();
+        state.servers.remove(id);
+        if state.leader_id.as_deref() == Some(id) {
+            state.leader_id = None;
+        }
+    }
+
+    /// Records which server is the current leader
+    pub fn set_leader(&self, leader_id: Option<String>) {
+        debug!(leader_id = ?leader_id, "Leader changed");
+        self.write_state().leader_id = leader_id;
+    }
+
+    /// Returns the current leader, if one is known
+    pub fn leader(&self) -> Option<ServerInfo> {
+        self.servers().into_iter().find(|s| s.is_leader)
+    }
+
+    /// Returns true if this node is the leader, or if no leader is known at all
+    /// (standalone servers accept writes)
+    pub fn accepts_writes(&self) -> bool {
+        let state = self.read_state();
+        match &state.leader_id {
+            Some(leader_id) => self.local_id.as_ref() == Some(leader_id),
+            None => true,
+        }
+    }
+
+    /// Returns all known servers ordered by ID
+    pub fn servers(&self) -> Vec<ServerInfo> {
+        let state = self.read_state();
+        state
+            .servers
+            .iter()
+            .map(|(id, rpc_addr)| ServerInfo {
+                id: id.clone(),
+                rpc_addr: rpc_addr.clone(),
+                is_leader: state.leader_id.as_ref() == Some(id),
+            })
+            .collect()
+    }
+
+    fn read_state(&self) -> std::sync::RwLockReadGuard<'_, MembershipState> {
+        // the state is only ever replaced wholesale, so a poisoned lock still holds consistent data
+        self.state.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write_state(&self) -> std::sync::RwLockWriteGuard<'_, MembershipState> {
+        self.state.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_node_is_leader() {
+        let membership = Membership::single_node("node-0", "[::1]:50051");
+
+        assert!(membership.accepts_writes());
+        assert_eq!(
+            membership.leader(),
+            Some(ServerInfo {
+                id: "node-0".to_string(),
+                rpc_addr: "[::1]:50051".to_string(),
+                is_leader: true,
+            })
+        );
+    }
+
+    #[test]
+    fn test_join_leave_and_leadership() {
+        let membership = Membership::new("node-0", "127.0.0.1:5000");
+        membership.join("node-1", "127.0.0.1:5001");
+        membership.join("node-2", "127.0.0.1:5002");
+
+        // no leader known yet, so writes are accepted locally
+        assert!(membership.accepts_writes());
+        assert_eq!(membership.servers().len(), 3);
+
+        membership.set_leader(Some("node-1".to_string()));
+        assert!(!membership.accepts_writes());
+        assert_eq!(
+            membership.leader().map(|s| s.id),
+            Some("node-1".to_string())
+        );
+
+        membership.leave("node-1");
+        assert_eq!(membership.leader(), None);
+        let ids: Vec<_> = membership.servers().into_iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec!["node-0", "node-2"]);
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::io;
 use thiserror::Error;
 
@@ -117,6 +118,9 @@ pub enum IndexError {
 
     #[error("Invalid offset {offset}, must be >= {min_offset}")]
     InvalidOffset { offset: u64, min_offset: u64 },
+
+    #[error("Index is in read-only mode")]
+    ReadOnly,
 }
 
 #[derive(Debug, Error)]
@@ -162,6 +166,32 @@ pub enum LogError {
         base_offset: u64,
         next_offset: u64,
     },
+    #[error("Invalid log config: {field} {reason}")]
+    InvalidConfig { field: &'static str, reason: String },
+    #[error("Record of {size} bytes exceeds the maximum of {max_record_bytes} bytes")]
+    RecordTooLarge { size: u64, max_record_bytes: u64 },
+    #[error("Log directory {path} is locked by another process")]
+    Locked { path: String },
+    #[error("Failed to write manifest {path}")]
+    ManifestError {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("Log {path} is not empty, next offset is {next_offset}")]
+    NotEmpty { path: String, next_offset: u64 },
+    #[error("Failed to restore log from stream at byte {position}")]
+    RestoreFailed {
+        position: u64,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("Snapshot failed on {path}")]
+    SnapshotFailed {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
     #[error("Segment error: {0}")]
     Segment(#[from] SegmentError), //converts SegmentError to LogError via From trait implementation. Convienence macro
 }
@@ -181,6 +211,13 @@ pub enum NetworkError {
     #[error("Authentication failed")]
     AuthenticationFailed,
 
+    #[error("{subject} is not permitted to {action} on {object}")]
+    PermissionDenied {
+        subject: String,
+        object: String,
+        action: String,
+    },
+
     #[error("Server unavailable")]
     ServerUnavailable,
 
@@ -189,6 +226,16 @@ pub enum NetworkError {
 
     #[error("Task execution failed: {0}")]
     TaskFailed(String),
+
+    /// A call was rejected by the server. `reason` and `metadata` come from the
+    /// status' `google.rpc.ErrorInfo` details, if the server sent any.
+    #[error("RPC failed with {code}: {message}")]
+    Rpc {
+        code: tonic::Code,
+        message: String,
+        reason: Option<String>,
+        metadata: HashMap<String, String>,
+    },
 }
 
 /// Consensus-related errors
@@ -210,6 +257,39 @@ pub enum ConsensusError {
     LogDivergence { index: u64 },
 }
 
+impl LogError {
+    /// Check if the error means the log itself can no longer be trusted, as opposed to
+    /// a bad request (unknown offset, oversized record) or a transient I/O failure
+    pub fn is_fatal(&self) -> bool {
+        match self {
+            LogError::CleanupError { .. }
+            | LogError::DirectoryError { .. }
+            | LogError::ManifestError { .. } => true,
+            LogError::InvalidConfig { .. }
+            | LogError::Locked { .. }
+            | LogError::NotEmpty { .. }
+            | LogError::RestoreFailed { .. }
+            | LogError::SnapshotFailed { .. }
+            | LogError::OffsetNotFound { .. }
+            | LogError::RecordTooLarge { .. } => false,
+            LogError::Segment(SegmentError::Storage(e)) => matches!(
+                e,
+                StorageError::OpenFailed { .. }
+                    | StorageError::CorruptedRecord { .. }
+                    | StorageError::MmapFailed { .. }
+            ),
+            LogError::Segment(SegmentError::Index(e)) => matches!(
+                e,
+                IndexError::OpenFailed { .. }
+                    | IndexError::CorruptedEntry { .. }
+                    | IndexError::CorruptedFile { .. }
+                    | IndexError::MmapFailed { .. }
+            ),
+            LogError::Segment(_) => false,
+        }
+    }
+}
+
 impl ProglogError {
     /// Check if this error is recoverable (e.g., can retry)
     pub fn is_recoverable(&self) -> bool {
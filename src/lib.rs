@@ -1,9 +1,12 @@
-// pub mod discovery;
 // pub mod proto;
 // pub mod server;
+pub mod client;
+pub mod config;
+pub mod discovery;
 pub mod errors;
 pub mod server;
 pub mod storage;
+pub mod telemetry;
 
 use crate::errors::*;
 
@@ -4,40 +4,123 @@
 // use tempfile::TempDir;
 
 use log::info;
+use proglog_rs::config::ServerConfig;
+use proglog_rs::discovery::Membership;
+use proglog_rs::server::auth::{AuthInterceptor, Authorizer};
 use proglog_rs::server::grpc::{LogService, proto};
-use proglog_rs::storage::log::{Log, LogConfig};
+use proglog_rs::server::health::{LogHealth, reflection_service};
+use proglog_rs::server::http::HttpGateway;
+use proglog_rs::server::shutdown::{Shutdown, os_signal, reclaim_log};
+use proglog_rs::storage::log::Log;
+use proglog_rs::telemetry::{metrics, trace};
 use proto::log_server::LogServer;
 use std::fs::create_dir_all;
-use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tonic::service::interceptor::InterceptedService;
 use tonic::transport::Server;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    tracing_subscriber::fmt().with_env_filter("info").init();
+    let config = ServerConfig::load()?;
+
+    let provider = config
+        .otlp_endpoint
+        .as_deref()
+        .map(|endpoint| trace::otlp_provider(endpoint, &config.node_id))
+        .transpose()?;
+    // flushes buffered spans when main returns
+    let _tracing = trace::init(&config.log_level, provider)?;
 
     info!("starting proglog-rs gRPC server");
 
-    let log_dir = PathBuf::from("data");
-    create_dir_all(&log_dir)?;
+    if let Some(metrics_addr) = config.metrics_addr {
+        metrics::install(metrics_addr)?;
+        info!("Metrics served on http://{metrics_addr}/metrics");
+    }
+    if let Some(endpoint) = &config.otlp_endpoint {
+        info!("Exporting spans to {endpoint}");
+    }
 
-    let config = LogConfig {
-        max_store_bytes: 1024 * 1024,
-        max_index_entries: 1000,
-        log_dir: log_dir.clone(),
-    };
+    create_dir_all(&config.log.log_dir)?;
+    let prog_log = Log::new(config.log.clone())?;
+
+    info!("Log initialized in {:?}", config.log.log_dir);
 
-    let prog_log = Log::new(config)?;
+    let addr = config.bind_addr;
+    let membership = Arc::new(Membership::single_node(
+        config.node_id.clone(),
+        config.advertise_addr.clone(),
+    ));
 
-    info!("Log initialized in ./data directory");
+    let shutdown = Shutdown::new();
+    let (health, health_service) = LogHealth::new().await;
+    health.watch_shutdown(shutdown.listener());
 
-    let log_service = LogService::new(prog_log);
+    let mut log_service = LogService::new(prog_log)
+        .with_membership(membership)
+        .with_shutdown(shutdown.listener())
+        .with_health(health);
+    let log = log_service.log();
+
+    // an ACL policy turns on authorization, a tokens file adds bearer token identities
+    if let Some(policy_path) = &config.acl_policy {
+        info!("Authorization enabled with policy {policy_path:?}");
+        log_service = log_service.with_authorizer(Arc::new(Authorizer::from_file(policy_path)?));
+    }
+    let interceptor = match &config.auth_tokens {
+        Some(tokens_path) => AuthInterceptor::load_tokens(tokens_path)?,
+        None => AuthInterceptor::default(),
+    };
 
-    let addr = "[::1]:50051".parse()?;
     info!("Server listening on {addr}");
 
-    Server::builder()
-        .add_service(LogServer::new(log_service))
-        .serve(addr)
+    let mut server = Server::builder();
+    if let Some(tls) = &config.tls {
+        let mutual_tls = tls.client_ca_path.is_some();
+        info!("TLS enabled (mutual TLS: {mutual_tls})");
+        server = server.tls_config(tls.load()?)?;
+    }
+
+    // gRPC and HTTP share one service so both see the same log and shutdown state
+    let log_service = Arc::new(log_service);
+
+    if let Some(http_addr) = config.http_addr {
+        let router = HttpGateway::new(Arc::clone(&log_service))
+            .with_interceptor(interceptor.clone())
+            .router();
+        let listener = TcpListener::bind(http_addr).await?;
+        let http_shutdown = shutdown.listener();
+        info!("HTTP gateway listening on http://{http_addr}");
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, router)
+                .with_graceful_shutdown(http_shutdown.wait())
+                .await
+            {
+                log::error!("HTTP gateway failed: {e}");
+            }
+        });
+    }
+
+    let server_shutdown = shutdown.listener();
+    tokio::spawn(async move {
+        os_signal().await;
+        info!("Shutting down, draining in-flight calls");
+        shutdown.trigger();
+    });
+
+    server
+        .add_service(health_service)
+        .add_service(reflection_service()?)
+        .add_service(InterceptedService::new(
+            LogServer::from_arc(log_service),
+            interceptor,
+        ))
+        .serve_with_shutdown(addr, server_shutdown.wait())
         .await?;
+
+    reclaim_log(log, Duration::from_secs(5)).await?.close()?;
+    info!("Log closed, bye");
     Ok(())
 }
@@ -0,0 +1,349 @@
+//! Authentication and authorization for the gRPC API.
+//! The [`AuthInterceptor`] resolves the caller's identity from its mTLS client certificate or a
+//! bearer token, and the [`Authorizer`] checks that identity against an ACL policy file.
+//!
+//! Policies use Casbin-style `subject, object, action` rules, one per line:
+//!
+//! ```text
+//! # subject, object (topic), action
+//! p, alice, orders, produce
+//! p, alice, orders, consume
+//! p, *, audit, consume
+//! ```
+use crate::ProglogResult;
+use crate::errors::{NetworkError, ProglogError};
+use crate::server::status::IntoStatus;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use tonic::service::Interceptor;
+use tonic::{Request, Status};
+use tracing::{debug, warn};
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+/// Metadata key carrying bearer tokens
+pub const AUTHORIZATION_METADATA_KEY: &str = "authorization";
+
+/// Matches any subject, object or action in a policy rule
+const WILDCARD: &str = "*";
+
+/// Identity of an authenticated caller, stored in the request extensions by the [`AuthInterceptor`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subject(pub String);
+
+/// Operations that are subject to authorization
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Produce,
+    Consume,
+}
+
+impl Action {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Action::Produce => "produce",
+            Action::Consume => "consume",
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Interceptor that authenticates callers.
+/// Client certificates take precedence: their subject common name becomes the identity.
+/// Otherwise an `authorization: Bearer <token>` header is looked up in the configured tokens.
+/// Requests without credentials pass through without a [`Subject`], invalid tokens are rejected.
+#[derive(Debug, Clone, Default)]
+pub struct AuthInterceptor {
+    tokens: Arc<HashMap<String, String>>, // token -> subject
+}
+
+impl AuthInterceptor {
+    /// Creates an interceptor that accepts the given bearer tokens, mapping each token to its subject
+    pub fn with_tokens(tokens: HashMap<String, String>) -> Self {
+        Self {
+            tokens: Arc::new(tokens),
+        }
+    }
+
+    /// Loads bearer tokens from a file with one `subject token` pair per line
+    pub fn load_tokens(path: impl AsRef<Path>) -> ProglogResult<Self> {
+        let path = path.as_ref();
+        let contents = read_config_file(path)?;
+
+        let mut tokens = HashMap::new();
+        for (line_no, line) in policy_lines(&contents) {
+            let mut parts = line.split_whitespace();
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some(subject), Some(token), None) => {
+                    tokens.insert(token.to_string(), subject.to_string());
+                }
+                _ => {
+                    return Err(ProglogError::Config {
+                        message: format!("{}:{line_no}: expected `subject token`", path.display()),
+                    });
+                }
+            }
+        }
+
+        Ok(Self::with_tokens(tokens))
+    }
+
+    fn authenticate<T>(&self, request: &Request<T>) -> Result<Option<Subject>, NetworkError> {
+        if let Some(certs) = request.peer_certs()
+            && let Some(cert) = certs.first()
+        {
+            let (_, cert) = X509Certificate::from_der(cert.as_ref())
+                .map_err(|_| NetworkError::AuthenticationFailed)?;
+            let common_name = cert
+                .subject()
+                .iter_common_name()
+                .next()
+                .and_then(|cn| cn.as_str().ok())
+                .ok_or(NetworkError::AuthenticationFailed)?;
+
+            return Ok(Some(Subject(common_name.to_string())));
+        }
+
+        let Some(header) = request.metadata().get(AUTHORIZATION_METADATA_KEY) else {
+            return Ok(None);
+        };
+
+        let token = header
+            .to_str()
+            .ok()
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or(NetworkError::AuthenticationFailed)?;
+
+        match self.tokens.get(token) {
+            Some(subject) => Ok(Some(Subject(subject.clone()))),
+            None => {
+                warn!("Rejected request with unknown bearer token");
+                Err(NetworkError::AuthenticationFailed)
+            }
+        }
+    }
+}
+
+impl Interceptor for AuthInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        if let Some(subject) = self.authenticate(&request).map_err(|e| e.into_status())? {
+            debug!(subject = subject.0, "Authenticated request");
+            request.extensions_mut().insert(subject);
+        }
+        Ok(request)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rule {
+    subject: String,
+    object: String,
+    action: String,
+}
+
+impl Rule {
+    fn matches(&self, subject: &str, object: &str, action: Action) -> bool {
+        let matches = |pattern: &str, value: &str| pattern == WILDCARD || pattern == value;
+
+        matches(&self.subject, subject)
+            && matches(&self.object, object)
+            && matches(&self.action, action.as_str())
+    }
+}
+
+/// ACL based authorizer. Everything that is not explicitly permitted by a rule is denied.
+#[derive(Debug, Clone, Default)]
+pub struct Authorizer {
+    rules: Vec<Rule>,
+}
+
+impl Authorizer {
+    /// Loads a policy file
+    pub fn from_file(path: impl AsRef<Path>) -> ProglogResult<Self> {
+        let path = path.as_ref();
+        Self::parse(&read_config_file(path)?).map_err(|e| ProglogError::Config {
+            message: format!("{}: {e}", path.display()),
+        })
+    }
+
+    /// Parses a policy made of `p, subject, object, action` lines
+    pub fn parse(policy: &str) -> ProglogResult<Self> {
+        let mut rules = Vec::new();
+
+        for (line_no, line) in policy_lines(policy) {
+            let fields: Vec<_> = line.split(',').map(str::trim).collect();
+            let [kind, subject, object, action] = fields[..] else {
+                return Err(ProglogError::Config {
+                    message: format!("line {line_no}: expected `p, subject, object, action`"),
+                });
+            };
+
+            if kind != "p" {
+                return Err(ProglogError::Config {
+                    message: format!("line {line_no}: unsupported rule type `{kind}`"),
+                });
+            }
+
+            if ![WILDCARD, Action::Produce.as_str(), Action::Consume.as_str()].contains(&action) {
+                return Err(ProglogError::Config {
+                    message: format!("line {line_no}: unknown action `{action}`"),
+                });
+            }
+
+            rules.push(Rule {
+                subject: subject.to_string(),
+                object: object.to_string(),
+                action: action.to_string(),
+            });
+        }
+
+        Ok(Self { rules })
+    }
+
+    /// Checks whether the subject may perform the action on the object (topic)
+    pub fn authorize(
+        &self,
+        subject: &str,
+        object: &str,
+        action: Action,
+    ) -> Result<(), NetworkError> {
+        if self
+            .rules
+            .iter()
+            .any(|rule| rule.matches(subject, object, action))
+        {
+            return Ok(());
+        }
+
+        warn!(subject, object, %action, "Permission denied");
+        Err(NetworkError::PermissionDenied {
+            subject: subject.to_string(),
+            object: object.to_string(),
+            action: action.to_string(),
+        })
+    }
+}
+
+fn read_config_file(path: &Path) -> ProglogResult<String> {
+    fs::read_to_string(path).map_err(|e| ProglogError::Config {
+        message: format!("failed to read {}: {e}", path.display()),
+    })
+}
+
+/// Yields non-empty, non-comment lines with their 1-based line numbers
+fn policy_lines(contents: &str) -> impl Iterator<Item = (usize, &str)> {
+    contents
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| (idx + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLICY: &str = "
+        # producers
+        p, alice, orders, produce
+        p, alice, orders, consume
+
+        p, *, audit, consume
+        p, admin, *, *
+    ";
+
+    #[test]
+    fn test_authorize_rules() {
+        let authorizer = Authorizer::parse(POLICY).unwrap();
+
+        assert!(
+            authorizer
+                .authorize("alice", "orders", Action::Produce)
+                .is_ok()
+        );
+        assert!(
+            authorizer
+                .authorize("alice", "orders", Action::Consume)
+                .is_ok()
+        );
+        assert!(
+            authorizer
+                .authorize("bob", "audit", Action::Consume)
+                .is_ok()
+        );
+        assert!(
+            authorizer
+                .authorize("admin", "payments", Action::Produce)
+                .is_ok()
+        );
+
+        assert!(matches!(
+            authorizer.authorize("bob", "orders", Action::Produce),
+            Err(NetworkError::PermissionDenied { subject, .. }) if subject == "bob"
+        ));
+        assert!(
+            authorizer
+                .authorize("alice", "audit", Action::Produce)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_empty_policy_denies_everything() {
+        let authorizer = Authorizer::default();
+        assert!(
+            authorizer
+                .authorize("alice", "orders", Action::Consume)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_invalid_policies() {
+        for policy in [
+            "p, alice, orders",
+            "g, alice, admins",
+            "p, alice, orders, delete",
+        ] {
+            assert!(matches!(
+                Authorizer::parse(policy),
+                Err(ProglogError::Config { message }) if message.starts_with("line 1")
+            ));
+        }
+    }
+
+    #[test]
+    fn test_bearer_token_authentication() {
+        let interceptor = AuthInterceptor::with_tokens(HashMap::from([(
+            "secret".to_string(),
+            "alice".to_string(),
+        )]));
+
+        let mut request = Request::new(());
+        request
+            .metadata_mut()
+            .insert(AUTHORIZATION_METADATA_KEY, "Bearer secret".parse().unwrap());
+        assert_eq!(
+            interceptor.authenticate(&request).unwrap(),
+            Some(Subject("alice".to_string()))
+        );
+
+        let mut request = Request::new(());
+        request
+            .metadata_mut()
+            .insert(AUTHORIZATION_METADATA_KEY, "Bearer wrong".parse().unwrap());
+        assert!(matches!(
+            interceptor.authenticate(&request),
+            Err(NetworkError::AuthenticationFailed)
+        ));
+
+        assert_eq!(interceptor.authenticate(&Request::new(())).unwrap(), None);
+    }
+}
@@ -1,50 +1,325 @@
 use crate::{
-    errors::{LogError, NetworkError},
+    discovery::Membership,
+    errors::{ConsensusError, LogError, NetworkError},
+    server::auth::{Action, Authorizer, Subject},
+    server::health::LogHealth,
+    server::shutdown::ShutdownListener,
+    server::status::IntoStatus,
     storage::log::Log,
+    telemetry::{metrics::record_rpc, trace::set_remote_parent},
 };
-use proto::{ConsumeRequest, ConsumeResponse, ProduceRequest, ProduceResponse};
+use proto::log_server::SERVICE_NAME;
+use proto::{
+    ConsumeRequest, ConsumeResponse, ConsumeStreamRequest, GetOffsetsRequest, GetOffsetsResponse,
+    GetServersRequest, GetServersResponse, ProduceRequest, ProduceResponse,
+};
+use std::ops::Range;
+use std::pin::Pin;
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::sync::{mpsc, watch};
+use tokio_stream::Stream;
+use tokio_stream::wrappers::ReceiverStream;
 use tonic::{Request, Response, Status};
+use tracing::{Instrument, Span, debug, error, info_span};
 
 pub mod proto {
     tonic::include_proto!("log.v1");
-}
 
-trait IntoStatus {
-    fn into_status(self) -> Status;
+    /// Encoded descriptors of `log.v1`, served through gRPC reflection
+    pub const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("log_descriptor");
 }
 
-impl IntoStatus for LogError {
-    fn into_status(self) -> Status {
-        match &self {
-            LogError::OffsetNotFound { offset, .. } => {
-                Status::not_found(format!("Offset {offset} not found"))
-            }
-            LogError::Segment(e) => Status::internal(format!("Segment error: {e}")),
-            _ => Status::internal("Log error: {self}"),
-        }
-    }
-}
+/// Topic name used when none is configured
+pub const DEFAULT_TOPIC: &str = "default";
 
-impl IntoStatus for NetworkError {
-    fn into_status(self) -> Status {
-        match &self {
-            NetworkError::LockPoisoned => Status::internal("Lock poisoned"),
-            NetworkError::TaskFailed(msg) => Status::internal(format!("Task failed: {msg}")),
-            _ => Status::internal(format!("Network error: {self}")),
-        }
-    }
-}
+/// Metadata key carrying the current leader's ID when a request hits a follower.
+/// Clients use it to redirect writes to the leader.
+pub const LEADER_ID_METADATA_KEY: &str = "x-leader-id";
+
+/// Records read under one lock while streaming, which also bounds the stream's send buffer
+pub const STREAM_BATCH_SIZE: u64 = 64;
+
+type ConsumeStream = Pin<Box<dyn Stream<Item = Result<ConsumeResponse, Status>> + Send>>;
+
+#[derive(Clone)]
 pub struct LogService {
     log: Arc<Mutex<Log>>,
+    membership: Arc<Membership>,
+    authorizer: Option<Arc<Authorizer>>,
+    shutdown: Option<ShutdownListener>,
+    health: Option<LogHealth>,
+    topic: String,
+    /// Next offset after the latest append, wakes up following streams
+    appended: Arc<watch::Sender<u64>>,
 }
 
 impl LogService {
+    /// Creates a service for a standalone server without cluster membership or authorization
     pub fn new(log: Log) -> Self {
+        let (appended, _) = watch::channel(log.next_offset());
         Self {
             log: Arc::new(Mutex::new(log)),
+            membership: Arc::new(Membership::default()),
+            authorizer: None,
+            shutdown: None,
+            health: None,
+            topic: DEFAULT_TOPIC.to_string(),
+            appended: Arc::new(appended),
+        }
+    }
+
+    /// Rejects writes unless this node is the leader and reports the cluster topology through `GetServers`
+    pub fn with_membership(mut self, membership: Arc<Membership>) -> Self {
+        self.membership = membership;
+        self
+    }
+
+    /// Requires every produce/consume call to be permitted by the authorizer.
+    /// The caller identity is taken from the [`Subject`] set by the `AuthInterceptor`.
+    pub fn with_authorizer(mut self, authorizer: Arc<Authorizer>) -> Self {
+        self.authorizer = Some(authorizer);
+        self
+    }
+
+    /// Rejects new calls with `UNAVAILABLE` once shutdown starts so the server can drain
+    pub fn with_shutdown(mut self, shutdown: ShutdownListener) -> Self {
+        self.shutdown = Some(shutdown);
+        self
+    }
+
+    /// Reports `NOT_SERVING` through the health service once the log fails in a way it cannot recover from
+    pub fn with_health(mut self, health: LogHealth) -> Self {
+        self.health = Some(health);
+        self
+    }
+
+    /// Returns the shared log, e.g. to reclaim and close it once the server has stopped
+    pub fn log(&self) -> Arc<Mutex<Log>> {
+        Arc::clone(&self.log)
+    }
+
+    /// Sets the topic name the log is served under, which is the object ACL rules refer to
+    pub fn with_topic(mut self, topic: impl Into<String>) -> Self {
+        self.topic = topic.into();
+        self
+    }
+
+    fn ensure_running(&self) -> Result<(), Status> {
+        match &self.shutdown {
+            Some(shutdown) if shutdown.is_shutdown() => {
+                Err(NetworkError::ServerUnavailable.into_status())
+            }
+            _ => Ok(()),
         }
     }
+
+    /// Converts a storage result into a response, flagging the server unhealthy on fatal errors
+    async fn check_storage<T>(&self, result: Result<T, LogError>) -> Result<T, Status> {
+        let err = match result {
+            Ok(value) => return Ok(value),
+            Err(err) => err,
+        };
+
+        if err.is_fatal() {
+            error!(error = %err, "Unrecoverable storage error");
+            if let Some(health) = &self.health {
+                health.set_not_serving("unrecoverable storage error").await;
+            }
+        }
+
+        Err(err.into_status())
+    }
+
+    async fn lock_poisoned(&self) -> Status {
+        // a panic while holding the lock may have left the log half-written
+        if let Some(health) = &self.health {
+            health.set_not_serving("log lock poisoned").await;
+        }
+        NetworkError::LockPoisoned.into_status()
+    }
+
+    /// Runs `f` against the locked log on the blocking thread pool
+    async fn with_log<R, F>(&self, f: F) -> Result<R, Status>
+    where
+        F: FnOnce(&mut Log) -> Result<R, LogError> + Send + 'static,
+        R: Send + 'static,
+    {
+        let log = Arc::clone(&self.log);
+
+        // carry the RPC span onto the blocking thread so storage spans nest under it
+        let span = Span::current();
+        let result = tokio::task::spawn_blocking(move || {
+            let _entered = span.enter();
+            let mut log = log.lock().ok()?;
+            Some(f(&mut log))
+        })
+        .await
+        .map_err(|e| NetworkError::TaskFailed(e.to_string()).into_status())?;
+
+        match result {
+            Some(result) => self.check_storage(result).await,
+            None => Err(self.lock_poisoned().await),
+        }
+    }
+
+    /// Returns the base offset and the next offset to be assigned
+    pub(crate) async fn offsets<T>(&self, request: &Request<T>) -> Result<(u64, u64), Status> {
+        self.ensure_running()?;
+        self.authorize(request, Action::Consume)?;
+
+        self.with_log(|log| Ok((log.base_offset(), log.next_offset())))
+            .await
+    }
+
+    /// Reads the records in `range` under a single lock, see [`read_clamped`]
+    pub(crate) async fn read_range<T>(
+        &self,
+        request: &Request<T>,
+        range: Range<u64>,
+    ) -> Result<Vec<(u64, Vec<u8>)>, Status> {
+        self.ensure_running()?;
+        self.authorize(request, Action::Consume)?;
+
+        self.with_log(move |log| read_clamped(log, range)).await
+    }
+
+    /// Sends records from `offset` on until the stream reaches `end_offset` or catches up
+    /// with the log, or, when following, until the client goes away or the server shuts down
+    async fn stream_records(
+        self,
+        mut offset: u64,
+        end_offset: u64,
+        follow: bool,
+        tx: mpsc::Sender<Result<ConsumeResponse, Status>>,
+    ) {
+        // subscribe before the first read so no append is missed
+        let mut appended = self.appended.subscribe();
+
+        while offset < end_offset {
+            if let Err(status) = self.ensure_running() {
+                let _ = tx.send(Err(status)).await;
+                return;
+            }
+
+            let range = offset..offset.saturating_add(STREAM_BATCH_SIZE).min(end_offset);
+            let records = match self.with_log(move |log| read_clamped(log, range)).await {
+                Ok(records) => records,
+                Err(status) => {
+                    let _ = tx.send(Err(status)).await;
+                    return;
+                }
+            };
+
+            if records.is_empty() {
+                if !follow {
+                    return;
+                }
+                if !self.wait_for_append(&mut appended).await {
+                    let _ = tx
+                        .send(Err(NetworkError::ServerUnavailable.into_status()))
+                        .await;
+                    return;
+                }
+                continue;
+            }
+
+            for (record_offset, record) in records {
+                let response = ConsumeResponse {
+                    record,
+                    offset: record_offset,
+                };
+                if tx.send(Ok(response)).await.is_err() {
+                    debug!(offset = record_offset, "Client closed the stream");
+                    return;
+                }
+                offset = record_offset + 1;
+            }
+        }
+    }
+
+    /// Sends the records in `offset..end_offset` newest first. The range ends at the log as
+    /// it is when the stream starts; later appends are not part of it.
+    async fn stream_records_rev(
+        self,
+        offset: u64,
+        end_offset: u64,
+        tx: mpsc::Sender<Result<ConsumeResponse, Status>>,
+    ) {
+        let next_offset = self
+            .with_log(move |log| {
+                check_start(log, offset)?;
+                Ok(log.next_offset())
+            })
+            .await;
+        let mut end_offset = match next_offset {
+            Ok(next_offset) => end_offset.min(next_offset),
+            Err(status) => {
+                let _ = tx.send(Err(status)).await;
+                return;
+            }
+        };
+
+        while end_offset > offset {
+            if let Err(status) = self.ensure_running() {
+                let _ = tx.send(Err(status)).await;
+                return;
+            }
+
+            let range = end_offset.saturating_sub(STREAM_BATCH_SIZE).max(offset)..end_offset;
+            let start = range.start;
+            let records = match self.with_log(move |log| read_clamped_rev(log, range)).await {
+                Ok(records) => records,
+                Err(status) => {
+                    let _ = tx.send(Err(status)).await;
+                    return;
+                }
+            };
+
+            for (record_offset, record) in records {
+                let response = ConsumeResponse {
+                    record,
+                    offset: record_offset,
+                };
+                if tx.send(Ok(response)).await.is_err() {
+                    debug!(offset = record_offset, "Client closed the stream");
+                    return;
+                }
+            }
+            end_offset = start;
+        }
+    }
+
+    /// Waits for the next append, returning false once the server shuts down
+    async fn wait_for_append(&self, appended: &mut watch::Receiver<u64>) -> bool {
+        let shutdown = self.shutdown.clone();
+        let shutdown = async move {
+            match shutdown {
+                Some(shutdown) => shutdown.wait().await,
+                None => std::future::pending().await,
+            }
+        };
+
+        tokio::select! {
+            changed = appended.changed() => changed.is_ok(),
+            _ = shutdown => false,
+        }
+    }
+
+    fn authorize<T>(&self, request: &Request<T>, action: Action) -> Result<(), Status> {
+        let Some(authorizer) = &self.authorizer else {
+            return Ok(());
+        };
+
+        let subject = request
+            .extensions()
+            .get::<Subject>()
+            .ok_or_else(|| NetworkError::AuthenticationFailed.into_status())?;
+
+        authorizer
+            .authorize(&subject.0, &self.topic, action)
+            .map_err(|e| e.into_status())
+    }
 }
 
 #[tonic::async_trait]
@@ -53,40 +328,172 @@ impl proto::log_server::Log for LogService {
         &self,
         request: Request<ProduceRequest>,
     ) -> Result<Response<ProduceResponse>, Status> {
-        let record = request.into_inner().record;
-        let log = Arc::clone(&self.log);
+        traced("Produce", request, |request| async move {
+            self.ensure_running()?;
+            self.authorize(&request, Action::Produce)?;
 
-        // Run blocking op on thread-pool
-        let offset = tokio::task::spawn_blocking(move || {
-            let mut log = log
-                .lock()
-                .map_err(|_| NetworkError::LockPoisoned.into_status())?;
+            if !self.membership.accepts_writes() {
+                let leader_id = self.membership.leader().map(|leader| leader.id);
+                return Err(ConsensusError::NotLeader { leader_id }.into_status());
+            }
+
+            let record = request.into_inner().record;
+            // Run blocking op on thread-pool
+            let offset = self.with_log(move |log| log.append(&record)).await?;
+            self.appended.send_replace(offset + 1);
 
-            log.append(&record).map_err(|e| e.into_status())
+            Ok(Response::new(ProduceResponse { offset }))
         })
         .await
-        .map_err(|e| NetworkError::TaskFailed(e.to_string()).into_status())??;
-
-        Ok(Response::new(ProduceResponse { offset }))
     }
 
     async fn consume(
         &self,
         request: Request<ConsumeRequest>,
     ) -> Result<Response<ConsumeResponse>, Status> {
-        let offset = request.into_inner().offset;
-        let log = Arc::clone(&self.log);
+        traced("Consume", request, |request| async move {
+            self.ensure_running()?;
+            self.authorize(&request, Action::Consume)?;
+
+            let offset = request.into_inner().offset;
+            let record = self.with_log(move |log| log.read(offset)).await?;
+
+            Ok(Response::new(ConsumeResponse { record, offset }))
+        })
+        .await
+    }
 
-        let record = tokio::task::spawn_blocking(move || {
-            let log = log
-                .lock()
-                .map_err(|_| NetworkError::LockPoisoned.into_status())?;
+    async fn get_servers(
+        &self,
+        request: Request<GetServersRequest>,
+    ) -> Result<Response<GetServersResponse>, Status> {
+        traced("GetServers", request, |_| async move {
+            let servers = self
+                .membership
+                .servers()
+                .into_iter()
+                .map(|server| proto::Server {
+                    id: server.id,
+                    rpc_addr: server.rpc_addr,
+                    is_leader: server.is_leader,
+                })
+                .collect();
 
-            log.read(offset).map_err(|e| e.into_status())
+            Ok(Response::new(GetServersResponse { servers }))
         })
         .await
-        .map_err(|e| NetworkError::TaskFailed(e.to_string()).into_status())??;
+    }
+
+    type ConsumeStreamStream = ConsumeStream;
+
+    async fn consume_stream(
+        &self,
+        request: Request<ConsumeStreamRequest>,
+    ) -> Result<Response<Self::ConsumeStreamStream>, Status> {
+        traced("ConsumeStream", request, |request| async move {
+            self.ensure_running()?;
+            self.authorize(&request, Action::Consume)?;
+
+            let ConsumeStreamRequest {
+                offset,
+                follow,
+                end_offset,
+                reverse,
+            } = request.into_inner();
+            if reverse && follow {
+                return Err(NetworkError::InvalidRequest {
+                    reason: "a reverse stream cannot follow the log".to_string(),
+                }
+                .into_status());
+            }
+
+            let end_offset = end_offset.unwrap_or(u64::MAX);
+            let (tx, rx) = mpsc::channel(STREAM_BATCH_SIZE as usize);
+            if reverse {
+                tokio::spawn(
+                    self.clone()
+                        .stream_records_rev(offset, end_offset, tx)
+                        .instrument(Span::current()),
+                );
+            } else {
+                tokio::spawn(
+                    self.clone()
+                        .stream_records(offset, end_offset, follow, tx)
+                        .instrument(Span::current()),
+                );
+            }
+
+            let stream: ConsumeStream = Box::pin(ReceiverStream::new(rx));
+            Ok(Response::new(stream))
+        })
+        .await
+    }
+
+    async fn get_offsets(
+        &self,
+        request: Request<GetOffsetsRequest>,
+    ) -> Result<Response<GetOffsetsResponse>, Status> {
+        traced("GetOffsets", request, |request| async move {
+            let (base_offset, next_offset) = self.offsets(&request).await?;
+
+            Ok(Response::new(GetOffsetsResponse {
+                base_offset,
+                next_offset,
+            }))
+        })
+        .await
+    }
+}
+
+/// Runs an RPC handler inside a server span that continues the caller's trace, and records
+/// its outcome in the RPC metrics. The span is exported under the method's name.
+async fn traced<R, T, F, Fut>(
+    method: &'static str,
+    request: Request<R>,
+    handler: F,
+) -> Result<Response<T>, Status>
+where
+    F: FnOnce(Request<R>) -> Fut,
+    Fut: Future<Output = Result<Response<T>, Status>>,
+{
+    let start = Instant::now();
+    let span = info_span!(
+        "rpc",
+        otel.name = method,
+        otel.kind = "server",
+        rpc.system = "grpc",
+        rpc.service = SERVICE_NAME,
+        rpc.method = method
+    );
+    set_remote_parent(&span, request.metadata());
+
+    let result = handler(request).instrument(span).await;
+    record_rpc(method, &result, start.elapsed());
+    result
+}
+
+/// Reads the records in `range`, clamping its end to the next offset.
+/// Starting exactly at the next offset yields no records, starting outside the log is an error.
+fn read_clamped(log: &Log, range: Range<u64>) -> Result<Vec<(u64, Vec<u8>)>, LogError> {
+    check_start(log, range.start)?;
+    log.scan_range(range).collect()
+}
+
+/// Like [`read_clamped`], newest record first
+fn read_clamped_rev(log: &Log, range: Range<u64>) -> Result<Vec<(u64, Vec<u8>)>, LogError> {
+    check_start(log, range.start)?;
+    log.scan_range(range).rev().collect()
+}
 
-        Ok(Response::new(ConsumeResponse { record, offset }))
+/// Checks that a read may start at `offset`: inside the log, or exactly at its end
+fn check_start(log: &Log, offset: u64) -> Result<(), LogError> {
+    let (base_offset, next_offset) = (log.base_offset(), log.next_offset());
+    if offset < base_offset || offset > next_offset {
+        return Err(LogError::OffsetNotFound {
+            offset,
+            base_offset,
+            next_offset,
+        });
     }
+    Ok(())
 }
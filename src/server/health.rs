@@ -0,0 +1,61 @@
+//! Standard `grpc.health.v1.Health` checking and gRPC server reflection.
+//!
+//! The overall server status (service `""`) and `log.v1.Log` flip to `NOT_SERVING` together,
+//! either when the log hits a fatal storage error or when shutdown starts.
+
+use crate::ProglogResult;
+use crate::errors::ProglogError;
+use crate::server::grpc::proto::{self, log_server::SERVICE_NAME};
+use crate::server::shutdown::ShutdownListener;
+use tonic_health::ServingStatus;
+use tonic_health::pb::health_server::{Health, HealthServer};
+use tonic_health::server::{HealthReporter, health_reporter};
+use tonic_reflection::server::v1::{ServerReflection, ServerReflectionServer};
+use tracing::warn;
+
+/// Reports the serving status of the log service
+#[derive(Debug, Clone)]
+pub struct LogHealth {
+    reporter: HealthReporter,
+}
+
+impl LogHealth {
+    /// Creates the reporter together with the `Health` service to register on the server.
+    /// Both the server and `log.v1.Log` start out as `SERVING`.
+    pub async fn new() -> (Self, HealthServer<impl Health>) {
+        let (reporter, server) = health_reporter();
+        let health = Self { reporter };
+        health.set(ServingStatus::Serving).await;
+        (health, server)
+    }
+
+    pub async fn set_not_serving(&self, reason: &str) {
+        warn!(reason, "Reporting NOT_SERVING");
+        self.set(ServingStatus::NotServing).await;
+    }
+
+    /// Flips to `NOT_SERVING` as soon as shutdown is triggered
+    pub fn watch_shutdown(&self, shutdown: ShutdownListener) {
+        let health = self.clone();
+        tokio::spawn(async move {
+            shutdown.wait().await;
+            health.set_not_serving("shutting down").await;
+        });
+    }
+
+    async fn set(&self, status: ServingStatus) {
+        self.reporter.set_service_status("", status).await;
+        self.reporter.set_service_status(SERVICE_NAME, status).await;
+    }
+}
+
+/// Builds the gRPC reflection service describing `log.v1` and `grpc.health.v1`
+pub fn reflection_service() -> ProglogResult<ServerReflectionServer<impl ServerReflection>> {
+    tonic_reflection::server::Builder::configure()
+        .register_encoded_file_descriptor_set(proto::FILE_DESCRIPTOR_SET)
+        .register_encoded_file_descriptor_set(tonic_health::pb::FILE_DESCRIPTOR_SET)
+        .build_v1()
+        .map_err(|e| ProglogError::Internal {
+            message: format!("failed to build reflection service: {e}"),
+        })
+}
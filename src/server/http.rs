@@ -0,0 +1,317 @@
+//! HTTP/JSON gateway serving the log next to the gRPC API.
+//!
+//! Requests are handed to the same [`LogService`] as gRPC calls, so authentication,
+//! authorization, leader checks and error reasons are shared. gRPC codes map to HTTP statuses
+//! and the `ErrorInfo` details become the JSON error body:
+//!
+//! ```text
+//! POST /records                    produce, JSON {"record": "<base64>"} or a raw body
+//! GET  /records/{offset}           consume, JSON or raw bytes with `Accept: application/octet-stream`
+//! GET  /records?from=N&to=M        records N..M, at most `limit` (default 100) per response
+//! GET  /records?tail=N             the last N records
+//! ```
+use crate::errors::NetworkError;
+use crate::server::auth::AuthInterceptor;
+use crate::server::grpc::LEADER_ID_METADATA_KEY;
+use crate::server::grpc::LogService;
+use crate::server::grpc::proto::log_server::Log as _;
+use crate::server::grpc::proto::{ConsumeRequest, ProduceRequest};
+use crate::server::status::{IntoStatus, code_name};
+use axum::body::Bytes;
+use axum::extract::rejection::QueryRejection;
+use axum::extract::{Path, Query, State};
+use axum::http::header::{ACCEPT, CONTENT_TYPE, LOCATION};
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tonic::metadata::MetadataMap;
+use tonic::service::Interceptor;
+use tonic::{Code, Extensions, Request, Status};
+use tonic_types::StatusExt;
+
+/// Records returned by a range read when no `limit` is given
+pub const DEFAULT_RANGE_LIMIT: u64 = 100;
+
+/// Upper bound for the `limit` of a range read
+pub const MAX_RANGE_LIMIT: u64 = 1000;
+
+const OCTET_STREAM: &str = "application/octet-stream";
+const JSON: &str = "application/json";
+
+pub struct HttpGateway {
+    service: Arc<LogService>,
+    interceptor: AuthInterceptor,
+}
+
+impl HttpGateway {
+    /// Creates a gateway in front of `service`, usually the one also served over gRPC
+    pub fn new(service: Arc<LogService>) -> Self {
+        Self {
+            service,
+            interceptor: AuthInterceptor::default(),
+        }
+    }
+
+    /// Authenticates callers by their `authorization: Bearer <token>` header
+    pub fn with_interceptor(mut self, interceptor: AuthInterceptor) -> Self {
+        self.interceptor = interceptor;
+        self
+    }
+
+    pub fn router(self) -> Router {
+        Router::new()
+            .route("/records", post(produce).get(read_range))
+            .route("/records/{offset}", get(consume))
+            .with_state(Arc::new(self))
+    }
+
+    /// Wraps `message` into a gRPC request carrying the HTTP headers as metadata,
+    /// authenticated like a gRPC call
+    fn request<T>(&self, headers: &HeaderMap, message: T) -> Result<Request<T>, Status> {
+        let metadata = MetadataMap::from_headers(headers.clone());
+        let request = self.interceptor.clone().call(Request::from_parts(
+            metadata,
+            Extensions::default(),
+            (),
+        ))?;
+
+        let (metadata, extensions, ()) = request.into_parts();
+        Ok(Request::from_parts(metadata, extensions, message))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ProduceBody {
+    record: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ProduceResponseBody {
+    offset: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct RecordBody {
+    offset: u64,
+    record: String,
+}
+
+impl RecordBody {
+    fn new(offset: u64, record: &[u8]) -> Self {
+        Self {
+            offset,
+            record: BASE64.encode(record),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RangeParams {
+    from: Option<u64>,
+    to: Option<u64>,
+    tail: Option<u64>,
+    limit: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct RangeResponseBody {
+    records: Vec<RecordBody>,
+    /// Offset to continue reading from
+    next_offset: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorBody {
+    code: &'static str,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    metadata: HashMap<String, String>,
+}
+
+/// gRPC status rendered as an HTTP error response
+#[derive(Debug)]
+struct HttpError(Status);
+
+impl From<Status> for HttpError {
+    fn from(status: Status) -> Self {
+        Self(status)
+    }
+}
+
+impl IntoResponse for HttpError {
+    fn into_response(self) -> Response {
+        let status = self.0;
+        let (reason, metadata) = match status.get_details_error_info() {
+            Some(info) => (Some(info.reason), info.metadata),
+            None => (None, HashMap::new()),
+        };
+        let body = ErrorBody {
+            code: code_name(status.code()),
+            message: status.message().to_string(),
+            reason,
+            metadata,
+        };
+
+        let mut response = (http_status(status.code()), Json(body)).into_response();
+        if let Some(leader_id) = status.metadata().get(LEADER_ID_METADATA_KEY)
+            && let Ok(value) = HeaderValue::from_bytes(leader_id.as_bytes())
+        {
+            response.headers_mut().insert(LEADER_ID_METADATA_KEY, value);
+        }
+        response
+    }
+}
+
+/// Maps gRPC codes the way grpc-gateway does
+fn http_status(code: Code) -> StatusCode {
+    match code {
+        Code::Ok => StatusCode::OK,
+        Code::InvalidArgument | Code::FailedPrecondition | Code::OutOfRange => {
+            StatusCode::BAD_REQUEST
+        }
+        Code::Unauthenticated => StatusCode::UNAUTHORIZED,
+        Code::PermissionDenied => StatusCode::FORBIDDEN,
+        Code::NotFound => StatusCode::NOT_FOUND,
+        Code::AlreadyExists | Code::Aborted => StatusCode::CONFLICT,
+        Code::ResourceExhausted => StatusCode::TOO_MANY_REQUESTS,
+        // nginx's "client closed request"
+        Code::Cancelled => StatusCode::from_u16(499).expect("499 is a valid status"),
+        Code::Unimplemented => StatusCode::NOT_IMPLEMENTED,
+        Code::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+        Code::DeadlineExceeded => StatusCode::GATEWAY_TIMEOUT,
+        Code::Unknown | Code::Internal | Code::DataLoss => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+fn invalid_request(reason: impl Into<String>) -> HttpError {
+    HttpError(
+        NetworkError::InvalidRequest {
+            reason: reason.into(),
+        }
+        .into_status(),
+    )
+}
+
+fn header_is(headers: &HeaderMap, name: axum::http::HeaderName, mime: &str) -> bool {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.contains(mime))
+}
+
+async fn produce(
+    State(gateway): State<Arc<HttpGateway>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, HttpError> {
+    let record = if header_is(&headers, CONTENT_TYPE, JSON) {
+        let body: ProduceBody = serde_json::from_slice(&body)
+            .map_err(|e| invalid_request(format!("invalid JSON body: {e}")))?;
+        BASE64
+            .decode(body.record)
+            .map_err(|e| invalid_request(format!("record is not valid base64: {e}")))?
+    } else {
+        body.to_vec()
+    };
+
+    let request = gateway.request(&headers, ProduceRequest { record })?;
+    let offset = gateway.service.produce(request).await?.into_inner().offset;
+
+    Ok((
+        StatusCode::CREATED,
+        [(LOCATION, format!("/records/{offset}"))],
+        Json(ProduceResponseBody { offset }),
+    )
+        .into_response())
+}
+
+async fn consume(
+    State(gateway): State<Arc<HttpGateway>>,
+    Path(offset): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, HttpError> {
+    let offset = offset
+        .parse()
+        .map_err(|_| invalid_request(format!("invalid offset `{offset}`")))?;
+
+    let request = gateway.request(&headers, ConsumeRequest { offset })?;
+    let response = gateway.service.consume(request).await?.into_inner();
+
+    if header_is(&headers, ACCEPT, OCTET_STREAM) {
+        return Ok(([(CONTENT_TYPE, OCTET_STREAM)], response.record).into_response());
+    }
+    Ok(Json(RecordBody::new(response.offset, &response.record)).into_response())
+}
+
+async fn read_range(
+    State(gateway): State<Arc<HttpGateway>>,
+    params: Result<Query<RangeParams>, QueryRejection>,
+    headers: HeaderMap,
+) -> Result<Response, HttpError> {
+    let Query(params) = params.map_err(|e| invalid_request(e.body_text()))?;
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_RANGE_LIMIT)
+        .min(MAX_RANGE_LIMIT);
+
+    let request = gateway.request(&headers, ())?;
+    let start = match (params.from, params.tail) {
+        (Some(_), Some(_)) => return Err(invalid_request("`from` and `tail` are exclusive")),
+        (Some(from), None) => from,
+        (None, tail) => {
+            let (base_offset, next_offset) = gateway.service.offsets(&request).await?;
+            match tail {
+                Some(tail) => next_offset.saturating_sub(tail).max(base_offset),
+                None => base_offset,
+            }
+        }
+    };
+    let end = params
+        .to
+        .unwrap_or(u64::MAX)
+        .min(start.saturating_add(limit));
+    if end < start {
+        return Err(invalid_request("`to` must not be below the start offset"));
+    }
+
+    let records = gateway.service.read_range(&request, start..end).await?;
+    let next_offset = records.last().map_or(start, |(offset, _)| offset + 1);
+
+    Ok(Json(RangeResponseBody {
+        records: records
+            .iter()
+            .map(|(offset, record)| RecordBody::new(*offset, record))
+            .collect(),
+        next_offset,
+    })
+    .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_http_status_mapping() {
+        assert_eq!(http_status(Code::OutOfRange), StatusCode::BAD_REQUEST);
+        assert_eq!(http_status(Code::Unauthenticated), StatusCode::UNAUTHORIZED);
+        assert_eq!(http_status(Code::PermissionDenied), StatusCode::FORBIDDEN);
+        assert_eq!(
+            http_status(Code::Unavailable),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        assert_eq!(http_status(Code::Cancelled).as_u16(), 499);
+        assert_eq!(
+            http_status(Code::DataLoss),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+}
@@ -1 +1,7 @@
+pub mod auth;
 pub mod grpc;
+pub mod health;
+pub mod http;
+pub mod shutdown;
+pub mod status;
+pub mod tls;
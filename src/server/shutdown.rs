@@ -0,0 +1,154 @@
+//! Graceful shutdown: a [`Shutdown`] trigger fans out to [`ShutdownListener`]s held by the
+//! server (to stop accepting connections) and the service (to reject new calls and end streams).
+//! Once the server has drained, [`reclaim_log`] takes the log back so it can be closed explicitly.
+
+use crate::ProglogResult;
+use crate::errors::NetworkError;
+use crate::storage::log::Log;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+/// Triggers shutdown for every listener handed out by [`Shutdown::listener`]
+#[derive(Debug)]
+pub struct Shutdown {
+    sender: watch::Sender<bool>,
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        let (sender, _) = watch::channel(false);
+        Self { sender }
+    }
+
+    pub fn listener(&self) -> ShutdownListener {
+        ShutdownListener {
+            receiver: self.sender.subscribe(),
+        }
+    }
+
+    /// Signals every listener; calling it more than once is harmless
+    pub fn trigger(&self) {
+        self.sender.send_replace(true);
+    }
+}
+
+/// Observes a [`Shutdown`]
+#[derive(Debug, Clone)]
+pub struct ShutdownListener {
+    receiver: watch::Receiver<bool>,
+}
+
+impl ShutdownListener {
+    pub fn is_shutdown(&self) -> bool {
+        *self.receiver.borrow()
+    }
+
+    /// Completes once shutdown has been triggered (or the trigger was dropped)
+    pub async fn wait(mut self) {
+        let _ = self.receiver.wait_for(|shutdown| *shutdown).await;
+    }
+}
+
+/// Completes on SIGINT (Ctrl-C) or, on Unix, SIGTERM
+pub async fn os_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!(error = %e, "Failed to listen for Ctrl-C");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{SignalKind, signal};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                warn!(error = %e, "Failed to listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("Received SIGINT"),
+        _ = terminate => info!("Received SIGTERM"),
+    }
+}
+
+/// Takes sole ownership of the log shared with the service once the server has stopped.
+/// Blocking tasks of cancelled calls may still hold a reference briefly, so this waits up to `timeout`.
+pub async fn reclaim_log(mut log: Arc<Mutex<Log>>, timeout: Duration) -> ProglogResult<Log> {
+    let deadline = tokio::time::Instant::now() + timeout;
+
+    loop {
+        match Arc::try_unwrap(log) {
+            // a panic during an append poisons the lock, but the log is still worth flushing
+            Ok(mutex) => return Ok(mutex.into_inner().unwrap_or_else(PoisonError::into_inner)),
+            Err(shared) if tokio::time::Instant::now() < deadline => {
+                log = shared;
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            Err(_) => {
+                return Err(NetworkError::Timeout {
+                    timeout_ms: timeout.as_millis() as u64,
+                }
+                .into());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::log::LogConfig;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_listeners_observe_trigger() {
+        let shutdown = Shutdown::new();
+        let listener = shutdown.listener();
+        assert!(!listener.is_shutdown());
+
+        let waiter = tokio::spawn(listener.clone().wait());
+        shutdown.trigger();
+        waiter.await.unwrap();
+
+        assert!(listener.is_shutdown());
+        assert!(shutdown.listener().is_shutdown());
+    }
+
+    #[tokio::test]
+    async fn test_reclaim_log_waits_for_other_owners() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = LogConfig::builder()
+            .log_dir(temp_dir.path())
+            .build()
+            .unwrap();
+        let log = Arc::new(Mutex::new(Log::new(config).unwrap()));
+
+        let held = Arc::clone(&log);
+        let result = reclaim_log(Arc::clone(&log), Duration::from_millis(20)).await;
+        assert!(result.is_err_and(|e| e.is_recoverable()));
+
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            drop(held);
+        });
+        let log = reclaim_log(log, Duration::from_secs(5)).await.unwrap();
+        log.close().unwrap();
+    }
+}
@@ -0,0 +1,486 @@
+//! Maps the crate's errors to gRPC statuses.
+//! Every status carries a `google.rpc.ErrorInfo` detail with a stable `reason` and structured metadata
+//! (offsets, valid ranges, leader IDs...) so clients can react without parsing messages.
+//! Filesystem paths stay in the server log; clients only see the segment they belong to.
+use crate::errors::{
+    ConsensusError, IndexError, LogError, NetworkError, SegmentError, StorageError,
+};
+use crate::server::grpc::LEADER_ID_METADATA_KEY;
+use std::collections::HashMap;
+use std::path::Path;
+use tonic::{Code, Status};
+use tonic_types::{ErrorDetails, StatusExt};
+use tracing::{error, warn};
+
+/// Domain reported in the `ErrorInfo` details of every status
+pub const ERROR_DOMAIN: &str = "proglog.rs";
+
+/// `ErrorInfo` reason of a write sent to a server that is not the leader
+pub const NOT_LEADER_REASON: &str = "NOT_LEADER";
+
+pub(crate) trait IntoStatus {
+    fn into_status(self) -> Status;
+}
+
+/// Code, reason and metadata describing an error on the wire
+struct Details {
+    code: Code,
+    reason: &'static str,
+    metadata: Vec<(&'static str, String)>,
+    /// Path the error happened on and the message sent in place of the error's own
+    redacted: Option<(String, String)>,
+}
+
+impl Details {
+    fn new(code: Code, reason: &'static str) -> Self {
+        Self {
+            code,
+            reason,
+            metadata: Vec::new(),
+            redacted: None,
+        }
+    }
+
+    fn with(mut self, key: &'static str, value: impl ToString) -> Self {
+        self.metadata.push((key, value.to_string()));
+        self
+    }
+
+    /// Keeps `path` out of the status, reporting the segment it names (if any) and `message`
+    fn redact(mut self, path: &str, message: impl Into<String>) -> Self {
+        let base_offset = Path::new(path)
+            .file_stem()
+            .and_then(|stem| stem.to_str()?.parse::<u64>().ok());
+        if let Some(base_offset) = base_offset {
+            self = self.with("base_offset", base_offset);
+        }
+        self.redacted = Some((path.to_string(), message.into()));
+        self
+    }
+
+    fn into_status(self, mut message: String) -> Status {
+        if let Some((path, redacted)) = self.redacted {
+            if self.code == Code::Internal {
+                error!(reason = self.reason, path, error = %message, "Request failed");
+            } else {
+                warn!(reason = self.reason, path, error = %message, "Request failed");
+            }
+            message = redacted;
+        }
+
+        let metadata: HashMap<String, String> = self
+            .metadata
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect();
+
+        Status::with_error_details(
+            self.code,
+            message,
+            ErrorDetails::with_error_info(self.reason, ERROR_DOMAIN, metadata),
+        )
+    }
+}
+
+fn storage_details(err: &StorageError) -> Details {
+    match err {
+        StorageError::OpenFailed { path, .. } => Details::new(Code::Internal, "STORE_OPEN_FAILED")
+            .redact(path, "Failed to open store file"),
+        StorageError::WriteFailed { position, .. } => {
+            Details::new(Code::Unavailable, "STORE_WRITE_FAILED").with("position", position)
+        }
+        StorageError::ReadFailed { position, .. } => {
+            Details::new(Code::Unavailable, "STORE_READ_FAILED").with("position", position)
+        }
+        StorageError::ReadBeyondEnd { position, size } => {
+            Details::new(Code::OutOfRange, "STORE_READ_BEYOND_END")
+                .with("position", position)
+                .with("size", size)
+        }
+        StorageError::CorruptedRecord { position, .. } => {
+            Details::new(Code::DataLoss, "STORE_CORRUPTED_RECORD").with("position", position)
+        }
+        StorageError::GrowFailed {
+            current_size,
+            target_size,
+            ..
+        } => Details::new(Code::ResourceExhausted, "STORE_GROW_FAILED")
+            .with("current_size", current_size)
+            .with("target_size", target_size),
+        StorageError::MmapFailed { size, .. } => {
+            Details::new(Code::ResourceExhausted, "STORE_MMAP_FAILED").with("size", size)
+        }
+        StorageError::ReadOnly => Details::new(Code::FailedPrecondition, "STORE_READ_ONLY"),
+    }
+}
+
+fn index_details(err: &IndexError) -> Details {
+    match err {
+        IndexError::OpenFailed { path, .. } => Details::new(Code::Internal, "INDEX_OPEN_FAILED")
+            .redact(path, "Failed to open index file"),
+        IndexError::WriteFailed { position, .. } => {
+            Details::new(Code::Unavailable, "INDEX_WRITE_FAILED").with("position", position)
+        }
+        IndexError::OffsetNotFound { offset } => {
+            Details::new(Code::NotFound, "INDEX_OFFSET_NOT_FOUND").with("offset", offset)
+        }
+        IndexError::CorruptedEntry { position } => {
+            Details::new(Code::DataLoss, "INDEX_CORRUPTED_ENTRY").with("position", position)
+        }
+        IndexError::CorruptedFile { .. } => Details::new(Code::DataLoss, "INDEX_CORRUPTED_FILE"),
+        IndexError::GrowFailed {
+            current_size,
+            target_size,
+            ..
+        } => Details::new(Code::ResourceExhausted, "INDEX_GROW_FAILED")
+            .with("current_size", current_size)
+            .with("target_size", target_size),
+        IndexError::MmapFailed { size, .. } => {
+            Details::new(Code::ResourceExhausted, "INDEX_MMAP_FAILED").with("size", size)
+        }
+        IndexError::IndexFull => Details::new(Code::ResourceExhausted, "INDEX_FULL"),
+        IndexError::InvalidOffset { offset, min_offset } => {
+            Details::new(Code::InvalidArgument, "INDEX_INVALID_OFFSET")
+                .with("offset", offset)
+                .with("min_offset", min_offset)
+        }
+        IndexError::ReadOnly => Details::new(Code::FailedPrecondition, "INDEX_READ_ONLY"),
+    }
+}
+
+fn segment_details(err: &SegmentError) -> Details {
+    match err {
+        SegmentError::SegmentFull {
+            base_offset,
+            max_size,
+            current_size,
+        } => Details::new(Code::ResourceExhausted, "SEGMENT_FULL")
+            .with("base_offset", base_offset)
+            .with("max_size", max_size)
+            .with("current_size", current_size),
+        SegmentError::OffsetOutOfRange {
+            offset,
+            base_offset,
+            next_offset,
+        } => Details::new(Code::OutOfRange, "SEGMENT_OFFSET_OUT_OF_RANGE")
+            .with("offset", offset)
+            .with("base_offset", base_offset)
+            .with("next_offset", next_offset),
+        SegmentError::Storage(e) => storage_details(e),
+        SegmentError::Index(e) => index_details(e),
+    }
+}
+
+fn log_details(err: &LogError) -> Details {
+    match err {
+        LogError::CleanupError { base_offset, .. } => {
+            Details::new(Code::Internal, "LOG_CLEANUP_FAILED").with("base_offset", base_offset)
+        }
+        LogError::DirectoryError { path, .. } => {
+            Details::new(Code::Internal, "LOG_DIRECTORY_ERROR").redact(path, "Log directory error")
+        }
+        LogError::OffsetNotFound {
+            offset,
+            base_offset,
+            next_offset,
+        } => Details::new(Code::OutOfRange, "OFFSET_OUT_OF_RANGE")
+            .with("offset", offset)
+            .with("base_offset", base_offset)
+            .with("next_offset", next_offset),
+        LogError::InvalidConfig { field, .. } => {
+            Details::new(Code::FailedPrecondition, "INVALID_LOG_CONFIG").with("field", field)
+        }
+        LogError::RecordTooLarge {
+            size,
+            max_record_bytes,
+        } => Details::new(Code::InvalidArgument, "RECORD_TOO_LARGE")
+            .with("size", size)
+            .with("max_record_bytes", max_record_bytes),
+        LogError::ManifestError { path, .. } => Details::new(Code::Internal, "LOG_MANIFEST_FAILED")
+            .redact(path, "Failed to write manifest"),
+        LogError::Locked { path } => Details::new(Code::FailedPrecondition, "LOG_DIRECTORY_LOCKED")
+            .redact(path, "Log directory is locked by another process"),
+        LogError::NotEmpty { path, next_offset } => {
+            Details::new(Code::FailedPrecondition, "LOG_NOT_EMPTY")
+                .with("next_offset", next_offset)
+                .redact(
+                    path,
+                    format!("Log is not empty, next offset is {next_offset}"),
+                )
+        }
+        LogError::RestoreFailed { position, .. } => {
+            Details::new(Code::InvalidArgument, "LOG_RESTORE_FAILED").with("position", position)
+        }
+        LogError::SnapshotFailed { path, .. } => {
+            Details::new(Code::Internal, "LOG_SNAPSHOT_FAILED").redact(path, "Snapshot failed")
+        }
+        LogError::Segment(e) => segment_details(e),
+    }
+}
+
+fn consensus_details(err: &ConsensusError) -> Details {
+    match err {
+        ConsensusError::NotLeader { leader_id } => {
+            let details = Details::new(Code::FailedPrecondition, NOT_LEADER_REASON);
+            match leader_id {
+                Some(leader_id) => details.with("leader_id", leader_id),
+                None => details,
+            }
+        }
+        ConsensusError::NoLeader => Details::new(Code::Unavailable, "NO_LEADER"),
+        ConsensusError::Timeout => Details::new(Code::Unavailable, "CONSENSUS_TIMEOUT"),
+        ConsensusError::InsufficientReplicas {
+            required,
+            available,
+        } => Details::new(Code::Unavailable, "INSUFFICIENT_REPLICAS")
+            .with("required", required)
+            .with("available", available),
+        ConsensusError::LogDivergence { index } => {
+            Details::new(Code::Aborted, "LOG_DIVERGENCE").with("index", index)
+        }
+    }
+}
+
+fn network_details(err: &NetworkError) -> Details {
+    match err {
+        NetworkError::ConnectionFailed { address } => {
+            Details::new(Code::Unavailable, "CONNECTION_FAILED").with("address", address)
+        }
+        NetworkError::Timeout { timeout_ms } => {
+            Details::new(Code::DeadlineExceeded, "TIMEOUT").with("timeout_ms", timeout_ms)
+        }
+        NetworkError::InvalidRequest { .. } => {
+            Details::new(Code::InvalidArgument, "INVALID_REQUEST")
+        }
+        NetworkError::AuthenticationFailed => {
+            Details::new(Code::Unauthenticated, "AUTHENTICATION_FAILED")
+        }
+        NetworkError::PermissionDenied {
+            subject,
+            object,
+            action,
+        } => Details::new(Code::PermissionDenied, "PERMISSION_DENIED")
+            .with("subject", subject)
+            .with("object", object)
+            .with("action", action),
+        NetworkError::ServerUnavailable => Details::new(Code::Unavailable, "SERVER_UNAVAILABLE"),
+        NetworkError::LockPoisoned => Details::new(Code::Internal, "LOCK_POISONED"),
+        NetworkError::TaskFailed(_) => Details::new(Code::Internal, "TASK_FAILED"),
+        NetworkError::Rpc { code, .. } => Details::new(*code, "RPC_FAILED"),
+    }
+}
+
+impl IntoStatus for StorageError {
+    fn into_status(self) -> Status {
+        storage_details(&self).into_status(self.to_string())
+    }
+}
+
+impl IntoStatus for IndexError {
+    fn into_status(self) -> Status {
+        index_details(&self).into_status(self.to_string())
+    }
+}
+
+impl IntoStatus for SegmentError {
+    fn into_status(self) -> Status {
+        segment_details(&self).into_status(self.to_string())
+    }
+}
+
+impl IntoStatus for LogError {
+    fn into_status(self) -> Status {
+        log_details(&self).into_status(self.to_string())
+    }
+}
+
+impl IntoStatus for NetworkError {
+    fn into_status(self) -> Status {
+        network_details(&self).into_status(self.to_string())
+    }
+}
+
+impl IntoStatus for ConsensusError {
+    fn into_status(self) -> Status {
+        let mut status = consensus_details(&self).into_status(self.to_string());
+
+        // also expose the leader as plain metadata so clients can redirect without decoding details
+        if let ConsensusError::NotLeader {
+            leader_id: Some(leader_id),
+        } = &self
+            && let Ok(value) = leader_id.parse()
+        {
+            status.metadata_mut().insert(LEADER_ID_METADATA_KEY, value);
+        }
+
+        status
+    }
+}
+
+/// Canonical upper snake case gRPC code names, as used by other gRPC metrics
+pub(crate) fn code_name(code: Code) -> &'static str {
+    match code {
+        Code::Ok => "OK",
+        Code::Cancelled => "CANCELLED",
+        Code::Unknown => "UNKNOWN",
+        Code::InvalidArgument => "INVALID_ARGUMENT",
+        Code::DeadlineExceeded => "DEADLINE_EXCEEDED",
+        Code::NotFound => "NOT_FOUND",
+        Code::AlreadyExists => "ALREADY_EXISTS",
+        Code::PermissionDenied => "PERMISSION_DENIED",
+        Code::ResourceExhausted => "RESOURCE_EXHAUSTED",
+        Code::FailedPrecondition => "FAILED_PRECONDITION",
+        Code::Aborted => "ABORTED",
+        Code::OutOfRange => "OUT_OF_RANGE",
+        Code::Unimplemented => "UNIMPLEMENTED",
+        Code::Internal => "INTERNAL",
+        Code::Unavailable => "UNAVAILABLE",
+        Code::DataLoss => "DATA_LOSS",
+        Code::Unauthenticated => "UNAUTHENTICATED",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+
+    fn error_info(status: &Status) -> (String, HashMap<String, String>) {
+        let info = status.get_details_error_info().expect("ErrorInfo details");
+        assert_eq!(info.domain, ERROR_DOMAIN);
+        (info.reason, info.metadata)
+    }
+
+    #[test]
+    fn test_offset_not_found_reports_valid_range() {
+        let status = LogError::OffsetNotFound {
+            offset: 42,
+            base_offset: 10,
+            next_offset: 20,
+        }
+        .into_status();
+
+        assert_eq!(status.code(), Code::OutOfRange);
+        assert_eq!(status.message(), "Offset 42 not found (range: 10..20)");
+
+        let (reason, metadata) = error_info(&status);
+        assert_eq!(reason, "OFFSET_OUT_OF_RANGE");
+        assert_eq!(metadata["offset"], "42");
+        assert_eq!(metadata["base_offset"], "10");
+        assert_eq!(metadata["next_offset"], "20");
+    }
+
+    #[test]
+    fn test_nested_errors_keep_their_code() {
+        let status = LogError::Segment(SegmentError::Storage(StorageError::CorruptedRecord {
+            position: 128,
+            reason: "torn write".to_string(),
+        }))
+        .into_status();
+
+        assert_eq!(status.code(), Code::DataLoss);
+        let (reason, metadata) = error_info(&status);
+        assert_eq!(reason, "STORE_CORRUPTED_RECORD");
+        assert_eq!(metadata["position"], "128");
+
+        let status = LogError::Segment(SegmentError::Index(IndexError::GrowFailed {
+            current_size: 16,
+            target_size: 32,
+            source: io::Error::other("disk full"),
+        }))
+        .into_status();
+        assert_eq!(status.code(), Code::ResourceExhausted);
+        assert_eq!(error_info(&status).0, "INDEX_GROW_FAILED");
+
+        let status = LogError::Segment(SegmentError::Storage(StorageError::WriteFailed {
+            position: 0,
+            source: io::Error::other("EIO"),
+        }))
+        .into_status();
+        assert_eq!(status.code(), Code::Unavailable);
+    }
+
+    #[test]
+    fn test_fallback_message_is_formatted() {
+        let status = LogError::InvalidConfig {
+            field: "max_store_bytes",
+            reason: "must be positive".to_string(),
+        }
+        .into_status();
+
+        assert_eq!(status.code(), Code::FailedPrecondition);
+        assert_eq!(
+            status.message(),
+            "Invalid log config: max_store_bytes must be positive"
+        );
+    }
+
+    #[test]
+    fn test_paths_stay_on_the_server() {
+        let status = LogError::DirectoryError {
+            path: "/data".to_string(),
+            source: io::Error::other("denied"),
+        }
+        .into_status();
+        assert_eq!(status.code(), Code::Internal);
+        assert_eq!(status.message(), "Log directory error");
+        assert!(error_info(&status).1.is_empty());
+
+        let status = LogError::Segment(SegmentError::Storage(StorageError::OpenFailed {
+            path: "/data/00000000000000000042.log".to_string(),
+            source: io::Error::other("denied"),
+        }))
+        .into_status();
+        assert!(!status.message().contains("/data"));
+        let (reason, metadata) = error_info(&status);
+        assert_eq!(reason, "STORE_OPEN_FAILED");
+        assert_eq!(
+            metadata,
+            HashMap::from([("base_offset".to_string(), "42".to_string())])
+        );
+    }
+
+    #[test]
+    fn test_not_leader_carries_leader_id() {
+        let status = ConsensusError::NotLeader {
+            leader_id: Some("node-1".to_string()),
+        }
+        .into_status();
+
+        assert_eq!(status.code(), Code::FailedPrecondition);
+        assert_eq!(
+            status
+                .metadata()
+                .get(LEADER_ID_METADATA_KEY)
+                .and_then(|v| v.to_str().ok()),
+            Some("node-1")
+        );
+        let (reason, metadata) = error_info(&status);
+        assert_eq!(reason, "NOT_LEADER");
+        assert_eq!(metadata["leader_id"], "node-1");
+    }
+
+    #[test]
+    fn test_consensus_and_network_codes() {
+        assert_eq!(
+            ConsensusError::NoLeader.into_status().code(),
+            Code::Unavailable
+        );
+        assert_eq!(
+            ConsensusError::LogDivergence { index: 3 }
+                .into_status()
+                .code(),
+            Code::Aborted
+        );
+        assert_eq!(
+            NetworkError::Timeout { timeout_ms: 100 }
+                .into_status()
+                .code(),
+            Code::DeadlineExceeded
+        );
+        assert_eq!(
+            NetworkError::AuthenticationFailed.into_status().code(),
+            Code::Unauthenticated
+        );
+    }
+}
@@ -0,0 +1,80 @@
+//! TLS configuration for the gRPC server and its clients.
+//! Certificates and keys are read from PEM files so they can be rotated without rebuilding.
+use crate::ProglogResult;
+use crate::errors::ProglogError;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tonic::transport::{Certificate, ClientTlsConfig, Identity, ServerTlsConfig};
+
+/// TLS settings for the server. Setting `client_ca_path` turns on mutual TLS,
+/// so clients must present a certificate signed by that CA.
+#[derive(Debug, Clone)]
+pub struct ServerTls {
+    /// PEM encoded server certificate (chain)
+    pub cert_path: PathBuf,
+    /// PEM encoded private key for the server certificate
+    pub key_path: PathBuf,
+    /// PEM encoded CA used to verify client certificates
+    pub client_ca_path: Option<PathBuf>,
+}
+
+impl ServerTls {
+    /// Reads the certificates and builds the tonic server configuration
+    pub fn load(&self) -> ProglogResult<ServerTlsConfig> {
+        let identity = Identity::from_pem(read_pem(&self.cert_path)?, read_pem(&self.key_path)?);
+        let mut config = ServerTlsConfig::new().identity(identity);
+
+        if let Some(client_ca_path) = &self.client_ca_path {
+            config = config.client_ca_root(Certificate::from_pem(read_pem(client_ca_path)?));
+        }
+
+        Ok(config)
+    }
+}
+
+/// TLS settings for clients. Setting `cert_path` and `key_path` presents a client
+/// certificate, which is required when the server runs with mutual TLS.
+#[derive(Debug, Clone)]
+pub struct ClientTls {
+    /// PEM encoded CA used to verify the server certificate
+    pub ca_cert_path: PathBuf,
+    /// Name to verify the server certificate against, if it differs from the host in the address
+    pub domain_name: Option<String>,
+    /// PEM encoded client certificate
+    pub cert_path: Option<PathBuf>,
+    /// PEM encoded private key for the client certificate
+    pub key_path: Option<PathBuf>,
+}
+
+impl ClientTls {
+    /// Reads the certificates and builds the tonic client configuration
+    pub fn load(&self) -> ProglogResult<ClientTlsConfig> {
+        if self.cert_path.is_some() != self.key_path.is_some() {
+            return Err(ProglogError::Config {
+                message: "client certificate and key must be configured together".to_string(),
+            });
+        }
+
+        let ca_cert = Certificate::from_pem(read_pem(&self.ca_cert_path)?);
+        let mut config = ClientTlsConfig::new().ca_certificate(ca_cert);
+
+        if let Some(domain_name) = &self.domain_name {
+            config = config.domain_name(domain_name.clone());
+        }
+
+        if let (Some(cert_path), Some(key_path)) = (&self.cert_path, &self.key_path) {
+            config = config.identity(Identity::from_pem(
+                read_pem(cert_path)?,
+                read_pem(key_path)?,
+            ));
+        }
+
+        Ok(config)
+    }
+}
+
+fn read_pem(path: &Path) -> ProglogResult<Vec<u8>> {
+    fs::read(path).map_err(|e| ProglogError::Config {
+        message: format!("failed to read {}: {e}", path.display()),
+    })
+}
@@ -0,0 +1,130 @@
+//! Bounded cache of open sealed segments.
+//!
+//! The log only keeps the manifest entry of a sealed segment; its files are opened when a
+//! read first needs them. This cache keeps the most recently used of those segments mapped,
+//! and closes the least recently used one once more than `capacity` are open, so the number
+//! of file descriptors and mappings stays bounded however many segments the log has.
+//!
+//! Segments are handed out as `Arc`s: one evicted while a scan is still reading it stays
+//! mapped until the scan lets go of it.
+//!
+//! Every operation is O(log n) in the number of open segments, so a large `capacity` does
+//! not slow down reads.
+use crate::storage::segment::Segment;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tracing::debug;
+
+pub(crate) struct SegmentCache {
+    capacity: usize,
+    /// Open segments by base offset, with the tick they were last used at
+    segments: HashMap<u64, (Arc<Segment>, u64)>,
+    /// Base offsets by the tick they were last used at, least recently used first
+    recency: BTreeMap<u64, u64>,
+    tick: u64,
+}
+
+impl SegmentCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            segments: HashMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
+        }
+    }
+
+    /// Returns the open segment starting at `base_offset` and marks it most recently used
+    pub fn get(&mut self, base_offset: u64) -> Option<Arc<Segment>> {
+        let (segment, last_used) = self.segments.get_mut(&base_offset)?;
+        self.recency.remove(last_used);
+        self.tick += 1;
+        *last_used = self.tick;
+        self.recency.insert(self.tick, base_offset);
+        Some(Arc::clone(segment))
+    }
+
+    /// Adds a freshly opened segment, closing the least recently used one if the cache is full
+    pub fn insert(&mut self, segment: Arc<Segment>) {
+        let base_offset = segment.base_offset();
+        self.remove(base_offset);
+        while self.segments.len() >= self.capacity {
+            let Some((_, evicted)) = self.recency.pop_first() else {
+                break;
+            };
+            self.segments.remove(&evicted);
+            debug!(base_offset = evicted, "Closing least recently used segment");
+        }
+
+        self.tick += 1;
+        self.recency.insert(self.tick, base_offset);
+        self.segments.insert(base_offset, (segment, self.tick));
+    }
+
+    /// Drops the segment starting at `base_offset`, e.g. before its files are deleted
+    pub fn remove(&mut self, base_offset: u64) {
+        if let Some((_, last_used)) = self.segments.remove(&base_offset) {
+            self.recency.remove(&last_used);
+        }
+    }
+
+    /// Number of segments currently open
+    pub fn len(&self) -> usize {
+        self.segments.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn segment(dir: &TempDir, base_offset: u64) -> Arc<Segment> {
+        let store_path = dir.path().join(format!("{base_offset}.log"));
+        let index_path = dir.path().join(format!("{base_offset}.idx"));
+        Arc::new(Segment::new(store_path, index_path, base_offset, 1024, 10).unwrap())
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let dir = TempDir::new().unwrap();
+        let mut cache = SegmentCache::new(2);
+
+        cache.insert(segment(&dir, 0));
+        cache.insert(segment(&dir, 10));
+        // reading 0 makes 10 the least recently used
+        assert!(cache.get(0).is_some());
+        cache.insert(segment(&dir, 20));
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(10).is_none());
+        assert!(cache.get(0).is_some());
+        assert!(cache.get(20).is_some());
+
+        cache.remove(0);
+        assert!(cache.get(0).is_none());
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_evicted_segment_stays_readable_while_held() {
+        let dir = TempDir::new().unwrap();
+        let mut cache = SegmentCache::new(1);
+
+        let mut first = Segment::new(
+            dir.path().join("0.log"),
+            dir.path().join("0.idx"),
+            0,
+            1024,
+            10,
+        )
+        .unwrap();
+        first.append(b"still here").unwrap();
+        cache.insert(Arc::new(first));
+
+        let held = cache.get(0).unwrap();
+        cache.insert(segment(&dir, 10));
+        assert!(cache.get(0).is_none());
+        assert_eq!(held.read(0).unwrap(), b"still here");
+    }
+}
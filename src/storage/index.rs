@@ -5,8 +5,9 @@
 #![allow(dead_code)] //TODO: remove this when done with implemenation. Only adding for clippy CI to pass
 use crate::IndexResult;
 use crate::errors::IndexError;
-use crate::storage::IndexContext;
-use memmap2::{MmapMut, MmapOptions};
+use crate::storage::{FileMap, IndexContext};
+use crate::telemetry::metrics;
+use memmap2::{Mmap, MmapOptions};
 use std::fs::{File, OpenOptions};
 use std::path::Path;
 use tracing::{debug, info, instrument, warn};
@@ -14,7 +15,8 @@ use tracing::{debug, info, instrument, warn};
 // Each index entry: 8 bytes offset + 8 bytes position = 16 bytes
 const OFFSET_WIDTH: u64 = 8;
 const POSITION_WIDTH: u64 = 8;
-const ENTRY_WIDTH: u64 = 16; // OFFSET_WIDTH + POSITION_WIDTH
+/// Width of one index entry on disk
+pub const ENTRY_WIDTH: u64 = 16; // OFFSET_WIDTH + POSITION_WIDTH
 
 /// Index provides fast lookups from log offsets/indexes to positions in the Store.
 /// Each entry maps a sequential offset to a byt position in the Store file.
@@ -25,8 +27,10 @@ const ENTRY_WIDTH: u64 = 16; // OFFSET_WIDTH + POSITION_WIDTH
 /// Entry 2: [8-byte offset][8-byte position] = bytes 32-47
 pub struct Index {
     file: File,
-    mmap: MmapMut,
+    mmap: FileMap,
     size: u64, // number of entries (not bytes)
+    sync_on_write: bool,
+    closed: bool,
 }
 
 impl Index {
@@ -63,6 +67,7 @@ impl Index {
                 .map_err(|e| IndexError::CorruptedFile {
                     reason: format!("Failed to truncate corrupted index file: {e}"),
                 })?;
+            metrics::record_recovery_truncation("index", file_len - valid_size);
 
             debug!(
                 original_size = file_len,
@@ -80,7 +85,13 @@ impl Index {
             file.sync_all().with_grow_context(0, new_size)?;
             new_size
         } else {
-            std::cmp::max(file_len, 1000 * ENTRY_WIDTH)
+            // grow a file truncated on close back to the mapped size, writes past its end would fault
+            let map_size = std::cmp::max(file_len, 1000 * ENTRY_WIDTH);
+            if file_len < map_size {
+                file.set_len(map_size)
+                    .with_grow_context(file_len, map_size)?;
+            }
+            map_size
         };
 
         // create the memmap file for index
@@ -102,11 +113,91 @@ impl Index {
 
         Ok(Index {
             file,
-            mmap,
+            mmap: FileMap::Writable(mmap),
             size: num_entries,
+            sync_on_write: true,
+            closed: false,
         })
     }
 
+    /// Opens an existing index without ever modifying it: the file is mapped read-only,
+    /// a partial trailing entry is ignored rather than truncated and writes fail with
+    /// `IndexError::ReadOnly`.
+    ///
+    /// An index that is still being written is preallocated with zeros, so entries are
+    /// counted up to the first unused, all-zero slot. Only the very first entry of the log
+    /// (slot 0 of the segment based at offset 0) may legitimately be all zeros.
+    #[instrument(skip_all, fields(path = ?path.as_ref(), base_offset))]
+    pub fn open_read_only(path: impl AsRef<Path>, base_offset: u64) -> IndexResult<Self> {
+        debug!("Opening index file read-only");
+
+        let path_str = path.as_ref().to_string_lossy();
+        let file = File::open(path.as_ref()).with_open_context(&path_str)?;
+        let file_len = file.metadata().with_open_context(&path_str)?.len();
+
+        let mmap = unsafe { Mmap::map(&file).with_mmap_context(file_len)? };
+
+        let mut index = Index {
+            file,
+            mmap: FileMap::ReadOnly(mmap),
+            size: file_len / ENTRY_WIDTH,
+            sync_on_write: false,
+            closed: false,
+        };
+        for slot in 0..index.size {
+            let unused = index.read_offset_at_index(slot)? == 0
+                && index.read_position_at_index(slot)? == 0
+                && (slot > 0 || base_offset > 0);
+            if unused {
+                index.size = slot;
+                break;
+            }
+        }
+
+        info!(file_len, num_entries = index.size, "Index opened read-only");
+        Ok(index)
+    }
+
+    /// Opens the index of a sealed segment, known to hold exactly `entries` entries,
+    /// read-only and without looking for unused slots
+    #[instrument(skip_all, fields(path = ?path.as_ref(), entries))]
+    pub fn open_sealed(path: impl AsRef<Path>, entries: u64) -> IndexResult<Self> {
+        let path_str = path.as_ref().to_string_lossy();
+        let file = File::open(path.as_ref()).with_open_context(&path_str)?;
+        let file_len = file.metadata().with_open_context(&path_str)?.len();
+        if file_len < entries * ENTRY_WIDTH {
+            return Err(IndexError::CorruptedFile {
+                reason: format!("sealed index is shorter than its recorded {entries} entries"),
+            });
+        }
+
+        let mmap = unsafe { Mmap::map(&file).with_mmap_context(file_len)? };
+        debug!(entries, "Sealed index opened");
+
+        Ok(Index {
+            file,
+            mmap: FileMap::ReadOnly(mmap),
+            size: entries,
+            sync_on_write: false,
+            closed: false,
+        })
+    }
+
+    /// Returns true if the index was opened with `open_read_only` or `open_sealed`
+    pub fn is_read_only(&self) -> bool {
+        self.mmap.is_read_only()
+    }
+
+    /// Returns the whole mapped file, including unused slots
+    pub(crate) fn file_data(&self) -> &[u8] {
+        &self.mmap
+    }
+
+    /// Returns the written entries
+    pub(crate) fn data(&self) -> &[u8] {
+        &self.mmap[..self.size() as usize]
+    }
+
     /// Return the number of entries in the index
     pub fn len(&self) -> u64 {
         self.size
@@ -121,11 +212,58 @@ impl Index {
         self.size * ENTRY_WIDTH
     }
 
+    /// Controls whether every write is flushed to disk before returning (the default)
+    pub fn set_sync_on_write(&mut self, sync_on_write: bool) {
+        self.sync_on_write = sync_on_write;
+    }
+
+    /// Flushes all written entries to disk
+    pub fn flush(&mut self) -> IndexResult<()> {
+        self.mmap.flush().with_write_context(self.size())
+    }
+
+    /// Flushes the index and truncates the file to the written entries.
+    /// Unlike `Drop`, failures are reported to the caller.
+    #[instrument(skip(self), fields(entries = self.size))]
+    pub fn close(mut self) -> IndexResult<()> {
+        // Drop must not repeat the work, even if it fails here
+        self.closed = true;
+        if self.is_read_only() {
+            return Ok(());
+        }
+        let size = self.size();
+        self.mmap.flush().with_write_context(size)?;
+        self.file.set_len(size).with_write_context(size)?;
+        self.file.sync_all().with_write_context(size)?;
+        debug!("Index closed");
+        Ok(())
+    }
+
+    /// Seals the index once its segment is rotated out: flushes it, truncates the file to
+    /// the written entries and remaps it read-only
+    #[instrument(skip(self), fields(entries = self.size))]
+    pub fn seal(&mut self) -> IndexResult<()> {
+        if self.is_read_only() {
+            return Ok(());
+        }
+        let size = self.size();
+        self.mmap.flush().with_write_context(size)?;
+        self.file.set_len(size).with_write_context(size)?;
+        self.file.sync_all().with_write_context(size)?;
+        self.mmap = FileMap::ReadOnly(unsafe { Mmap::map(&self.file).with_mmap_context(size)? });
+        debug!("Index sealed");
+        Ok(())
+    }
+
     /// Writes an entry mapping offset to the position in the store
     #[instrument(skip(self), fields(offset, position))]
     pub fn write(&mut self, offset: u64, position: u64) -> IndexResult<()> {
         debug!(offset, position, "Writing index entry");
 
+        if self.is_read_only() {
+            return Err(IndexError::ReadOnly);
+        }
+
         // Check if we need to grow the memory map
         let entry_start = self.size * ENTRY_WIDTH;
         if entry_start + ENTRY_WIDTH > self.mmap.len() as u64 {
@@ -139,21 +277,24 @@ impl Index {
         };
 
         let entry_pos = (self.size * ENTRY_WIDTH) as usize;
+        let mmap = self.mmap.writable().ok_or(IndexError::ReadOnly)?;
 
         // write offset (8 bytes)
         let offset_bytes = offset.to_le_bytes();
-        self.mmap[entry_pos..entry_pos + OFFSET_WIDTH as usize].copy_from_slice(&offset_bytes);
+        mmap[entry_pos..entry_pos + OFFSET_WIDTH as usize].copy_from_slice(&offset_bytes);
 
         //write position (8 bytes)
         let position_bytes = position.to_le_bytes();
         let pos_start = entry_pos + OFFSET_WIDTH as usize;
-        self.mmap[pos_start..pos_start + POSITION_WIDTH as usize].copy_from_slice(&position_bytes);
+        mmap[pos_start..pos_start + POSITION_WIDTH as usize].copy_from_slice(&position_bytes);
 
         // Flush to ensure durability
-        self.mmap.flush().map_err(|e| IndexError::WriteFailed {
-            position: offset,
-            source: e,
-        })?;
+        if self.sync_on_write {
+            mmap.flush().map_err(|e| IndexError::WriteFailed {
+                position: offset,
+                source: e,
+            })?;
+        }
 
         // Increment size after successful write
         self.size += 1;
@@ -169,8 +310,9 @@ impl Index {
         Ok(())
     }
 
-    /// Reads the position for a given offset using linear search
-    /// Note: We use linear search because entries are stored in order of arrival, not sorted by offset
+    /// Reads the position for a given offset, directly from its slot when offsets were
+    /// written consecutively and by linear search otherwise
+    /// Note: We need the linear search because entries are stored in order of arrival, not sorted by offset
     #[instrument(skip(self), fields(offset))]
     pub fn read(&self, offset: u64) -> IndexResult<u64> {
         debug!(
@@ -183,6 +325,16 @@ impl Index {
             return Err(IndexError::OffsetNotFound { offset });
         }
 
+        // offsets are appended one after another, so the entry is normally found by its
+        // distance from the first offset without searching
+        let first_offset = self.read_offset_at_index(0)?;
+        if let Some(slot) = offset.checked_sub(first_offset)
+            && slot < self.size
+            && self.read_offset_at_index(slot)? == offset
+        {
+            return self.read_position_at_index(slot);
+        }
+
         // We can use linear search here. Not super optimal but we can change it later if needed.
         // to a sorted segment with binary search. Used by Kafka for example and is the distributed long standard.
         for index in 0..self.size {
@@ -264,7 +416,7 @@ impl Index {
         })?;
 
         //Remap our mmap
-        self.mmap = unsafe {
+        self.mmap = FileMap::Writable(unsafe {
             MmapOptions::new()
                 .len(new_capacity as usize)
                 .map_mut(&self.file)
@@ -272,7 +424,7 @@ impl Index {
                     size: new_capacity,
                     source: e,
                 })?
-        };
+        });
 
         info!("Index capacity extended successfully");
         Ok(())
@@ -281,6 +433,9 @@ impl Index {
 
 impl Drop for Index {
     fn drop(&mut self) {
+        if self.closed || self.is_read_only() {
+            return;
+        }
         let _ = self.mmap.flush();
         let _ = self.file.set_len(self.size());
     }
@@ -420,4 +575,32 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_open_read_only_stops_at_unused_slots() -> IndexResult<()> {
+        init_tracing();
+        let temp_file = NamedTempFile::new().unwrap();
+
+        // the writer keeps the index open, so the file is still preallocated
+        let mut index = Index::new(temp_file.path())?;
+        index.write(0, 0)?;
+        index.write(1, 20)?;
+        let file_len = std::fs::metadata(temp_file.path()).unwrap().len();
+
+        let mut reader = Index::open_read_only(temp_file.path(), 0)?;
+        assert!(reader.is_read_only());
+        assert_eq!(reader.len(), 2);
+        assert_eq!(reader.read(1)?, 20);
+        assert!(matches!(reader.write(2, 40), Err(IndexError::ReadOnly)));
+        reader.close()?;
+        assert_eq!(std::fs::metadata(temp_file.path()).unwrap().len(), file_len);
+
+        // an all-zero first slot is only a record in the segment based at offset 0
+        let other = NamedTempFile::new().unwrap();
+        let _writer = Index::new(other.path())?;
+        assert_eq!(Index::open_read_only(other.path(), 0)?.len(), 1);
+        assert!(Index::open_read_only(other.path(), 5)?.is_empty());
+
+        Ok(())
+    }
 }
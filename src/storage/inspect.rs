@@ -0,0 +1,363 @@
+//! Read-only access to the segment files of a log directory, for offline inspection.
+//!
+//! Segments are opened with [`Segment::open_read_only`], the same path a read-only log
+//! takes: nothing here creates, resizes or repairs files, so a live or damaged log can be
+//! looked at as it is on disk.
+//!
+//! Files of a segment that is still open are preallocated, so the store may end in zeros and
+//! the index in all-zero entries. Index entries are read up to the first such unused slot and
+//! records are located through the index, the same way the log reads them.
+use crate::errors::LogError;
+use crate::storage::index::{ENTRY_WIDTH, Index};
+use crate::storage::manifest;
+use crate::storage::segment::Segment;
+use crate::storage::store::{LEN_WIDTH, Store};
+use crate::{LogResult, SegmentResult};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File name of a segment's store
+pub fn store_file_name(base_offset: u64) -> String {
+    format!("{base_offset:020}.log")
+}
+
+/// File name of a segment's index
+pub fn index_file_name(base_offset: u64) -> String {
+    format!("{base_offset:020}.idx")
+}
+
+/// Returns the base offsets of the segments in `dir`, in ascending order
+pub fn segment_base_offsets(dir: impl AsRef<Path>) -> LogResult<Vec<u64>> {
+    base_offsets_with_extension(dir.as_ref(), "log")
+}
+
+/// Returns the base offsets of the index files in `dir`, in ascending order
+pub fn index_base_offsets(dir: impl AsRef<Path>) -> LogResult<Vec<u64>> {
+    base_offsets_with_extension(dir.as_ref(), "idx")
+}
+
+fn base_offsets_with_extension(dir: &Path, wanted: &str) -> LogResult<Vec<u64>> {
+    let directory_error = |source| LogError::DirectoryError {
+        path: dir.to_string_lossy().to_string(),
+        source,
+    };
+
+    let mut base_offsets = Vec::new();
+    for entry in fs::read_dir(dir).map_err(directory_error)? {
+        let path = entry.map_err(directory_error)?.path();
+        if let Some(extension) = path.extension()
+            && extension == wanted
+            && let Some(file_name) = path.file_stem()
+            && let Ok(base_offset) = file_name.to_string_lossy().parse::<u64>()
+        {
+            base_offsets.push(base_offset);
+        }
+    }
+
+    base_offsets.sort_unstable();
+    Ok(base_offsets)
+}
+
+/// Entry of a segment index
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexEntry {
+    /// Position of the entry in the index file, counted in entries
+    pub slot: u64,
+    pub offset: u64,
+    /// Position of the record's length prefix in the store
+    pub position: u64,
+}
+
+/// Record read from a segment store
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredRecord {
+    pub offset: u64,
+    pub position: u64,
+    pub data: Vec<u8>,
+}
+
+/// Sizes and offsets describing one segment
+#[derive(Debug, Clone, PartialEq)]
+pub struct SegmentSummary {
+    pub base_offset: u64,
+    /// Offset after the highest indexed offset
+    pub next_offset: u64,
+    pub store_file_bytes: u64,
+    /// Bytes up to the end of the last indexed record
+    pub store_used_bytes: u64,
+    pub index_file_bytes: u64,
+    pub index_entries: u64,
+}
+
+impl SegmentSummary {
+    /// Fraction of the segment's capacity in use. A segment is full once either its store
+    /// or its index is, so the larger of both ratios is reported.
+    pub fn fill_ratio(&self, max_store_bytes: u64, max_index_entries: u64) -> f64 {
+        let store = self.store_used_bytes as f64 / max_store_bytes.max(1) as f64;
+        let index = self.index_entries as f64 / max_index_entries.max(1) as f64;
+        store.max(index)
+    }
+}
+
+/// Read-only view of a segment's store and index files
+pub struct SegmentFiles {
+    base_offset: u64,
+    store_path: PathBuf,
+    index_path: PathBuf,
+    files: Files,
+}
+
+/// The segment as the log opens it read-only, or just its store when the index is missing
+enum Files {
+    Segment(Segment),
+    StoreOnly(Store),
+}
+
+impl SegmentFiles {
+    /// Opens the segment starting at `base_offset` in `dir` with
+    /// [`Segment::open_read_only`]. A missing index is treated as empty.
+    pub fn open(dir: impl AsRef<Path>, base_offset: u64) -> SegmentResult<Self> {
+        let store_path = dir.as_ref().join(store_file_name(base_offset));
+        let index_path = dir.as_ref().join(index_file_name(base_offset));
+
+        // the segment limits only matter for appends, which a read-only segment refuses
+        let files = if index_path.exists() {
+            Files::Segment(Segment::open_read_only(
+                &store_path,
+                &index_path,
+                base_offset,
+                u64::MAX,
+                u64::MAX,
+            )?)
+        } else {
+            Files::StoreOnly(Store::open_read_only(&store_path)?)
+        };
+
+        Ok(Self {
+            base_offset,
+            store_path,
+            index_path,
+            files,
+        })
+    }
+
+    pub fn base_offset(&self) -> u64 {
+        self.base_offset
+    }
+
+    pub fn store_path(&self) -> &Path {
+        &self.store_path
+    }
+
+    pub fn index_path(&self) -> &Path {
+        &self.index_path
+    }
+
+    fn store(&self) -> &Store {
+        match &self.files {
+            Files::Segment(segment) => segment.store(),
+            Files::StoreOnly(store) => store,
+        }
+    }
+
+    fn index(&self) -> Option<&Index> {
+        match &self.files {
+            Files::Segment(segment) => Some(segment.index()),
+            Files::StoreOnly(_) => None,
+        }
+    }
+
+    /// Returns the index entries up to the first unused, zeroed slot, see
+    /// [`Index::open_read_only`]
+    pub fn index_entries(&self) -> impl Iterator<Item = IndexEntry> + '_ {
+        let index = self.index();
+        (0..index.map_or(0, Index::len)).map_while(move |slot| {
+            let index = index?;
+            Some(IndexEntry {
+                slot,
+                offset: index.read_offset_at_index(slot).ok()?,
+                position: index.read_position_at_index(slot).ok()?,
+            })
+        })
+    }
+
+    /// Reads the record an index entry points at
+    pub fn record(&self, entry: &IndexEntry) -> SegmentResult<StoredRecord> {
+        let (data, _) = self.store().read(entry.position)?;
+        Ok(StoredRecord {
+            offset: entry.offset,
+            position: entry.position,
+            data,
+        })
+    }
+
+    /// Returns the length of the store up to its first torn record, the size recovery
+    /// would truncate it to
+    pub fn store_valid_bytes(&self) -> SegmentResult<u64> {
+        Ok(Store::valid_len(self.store().file_data())?)
+    }
+
+    /// Checksums the first `store_bytes` of the store and `index_entries` of the index the way
+    /// the manifest does, or returns `None` if the files are shorter than that
+    pub fn checksum(&self, store_bytes: u64, index_entries: u64) -> Option<u32> {
+        let store = self.store().file_data().get(..store_bytes as usize)?;
+        let index = self
+            .index()?
+            .file_data()
+            .get(..(index_entries * ENTRY_WIDTH) as usize)?;
+        Some(manifest::checksum(store, index))
+    }
+
+    /// Returns the records of the segment in index order
+    pub fn records(&self) -> impl Iterator<Item = SegmentResult<StoredRecord>> + '_ {
+        self.index_entries().map(|entry| self.record(&entry))
+    }
+
+    pub fn summary(&self) -> SegmentSummary {
+        let mut summary = SegmentSummary {
+            base_offset: self.base_offset,
+            next_offset: self.base_offset,
+            store_file_bytes: self.store().file_data().len() as u64,
+            store_used_bytes: 0,
+            index_file_bytes: self
+                .index()
+                .map_or(0, |index| index.file_data().len() as u64),
+            index_entries: 0,
+        };
+
+        for entry in self.index_entries() {
+            summary.index_entries += 1;
+            summary.next_offset = summary.next_offset.max(entry.offset.saturating_add(1));
+            if let Ok(record) = self.record(&entry) {
+                let end = record.position + LEN_WIDTH + record.data.len() as u64;
+                summary.store_used_bytes = summary.store_used_bytes.max(end);
+            }
+        }
+
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::{SegmentError, StorageError};
+    use crate::storage::log::{Log, LogConfig};
+    use tempfile::TempDir;
+
+    fn write_log(dir: &TempDir, records: usize) {
+        let config = LogConfig::builder()
+            .max_store_bytes(200)
+            .max_index_entries(10)
+            .max_record_bytes(64)
+            .log_dir(dir.path())
+            .build()
+            .unwrap();
+        let mut log = Log::new(config).unwrap();
+        for i in 0..records {
+            log.append(format!("record-{i}").as_bytes()).unwrap();
+        }
+        log.close().unwrap();
+    }
+
+    fn file_bytes(dir: &TempDir) -> Vec<(PathBuf, Vec<u8>)> {
+        let mut files: Vec<_> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| {
+                let path = entry.unwrap().path();
+                let bytes = fs::read(&path).unwrap();
+                (path, bytes)
+            })
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn test_lists_segments_and_records() {
+        let dir = TempDir::new().unwrap();
+        write_log(&dir, 25);
+
+        let base_offsets = segment_base_offsets(dir.path()).unwrap();
+        assert!(base_offsets.len() > 1);
+        assert_eq!(base_offsets[0], 0);
+
+        let mut next = 0;
+        for base_offset in base_offsets {
+            let segment = SegmentFiles::open(dir.path(), base_offset).unwrap();
+            let summary = segment.summary();
+            assert_eq!(summary.base_offset, next);
+            assert_eq!(summary.store_used_bytes, summary.store_file_bytes);
+            assert_eq!(
+                summary.index_entries * ENTRY_WIDTH,
+                summary.index_file_bytes
+            );
+
+            for record in segment.records() {
+                let record = record.unwrap();
+                assert_eq!(record.offset, next);
+                assert_eq!(record.data, format!("record-{next}").as_bytes());
+                next += 1;
+            }
+            assert_eq!(summary.next_offset, next);
+        }
+        assert_eq!(next, 25);
+    }
+
+    #[test]
+    fn test_preallocated_files_are_not_modified() {
+        let dir = TempDir::new().unwrap();
+        let config = LogConfig::builder().log_dir(dir.path()).build().unwrap();
+        let mut log = Log::new(config).unwrap();
+        log.append(b"first").unwrap();
+        log.append(b"second").unwrap();
+
+        // the log is still open, so both files carry preallocated zeros
+        let before = file_bytes(&dir);
+        let segment = SegmentFiles::open(dir.path(), 0).unwrap();
+        let summary = segment.summary();
+        assert_eq!(summary.index_entries, 2);
+        assert_eq!(summary.next_offset, 2);
+        assert_eq!(summary.store_used_bytes, 2 * LEN_WIDTH + 11);
+        assert!(summary.store_file_bytes > summary.store_used_bytes);
+        drop(segment);
+        assert_eq!(file_bytes(&dir), before);
+
+        drop(log);
+    }
+
+    #[test]
+    fn test_reports_torn_records_without_repairing() {
+        let dir = TempDir::new().unwrap();
+        write_log(&dir, 3);
+        let store_path = dir.path().join(store_file_name(0));
+        let store = fs::read(&store_path).unwrap();
+        fs::write(&store_path, &store[..store.len() - 2]).unwrap();
+
+        let segment = SegmentFiles::open(dir.path(), 0).unwrap();
+        let results: Vec<_> = segment.records().collect();
+        assert!(results[..2].iter().all(Result::is_ok));
+        // the torn record starts where the valid part of the store ends
+        assert!(matches!(
+            results[2],
+            Err(SegmentError::Storage(StorageError::ReadBeyondEnd { .. }))
+        ));
+        assert_eq!(segment.summary().index_entries, 3);
+
+        assert_eq!(fs::read(&store_path).unwrap().len(), store.len() - 2);
+    }
+
+    #[test]
+    fn test_fill_ratio_uses_the_fuller_file() {
+        let summary = SegmentSummary {
+            base_offset: 0,
+            next_offset: 5,
+            store_file_bytes: 100,
+            store_used_bytes: 100,
+            index_file_bytes: 80,
+            index_entries: 5,
+        };
+        assert_eq!(summary.fill_ratio(200, 10), 0.5);
+        assert_eq!(summary.fill_ratio(400, 5), 1.0);
+    }
+}
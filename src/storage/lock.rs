@@ -0,0 +1,130 @@
+//! Advisory lock on a log directory, so two processes never append to the same segments.
+//!
+//! A writer holds an exclusive lock on [`LOCK_FILE_NAME`] for as long as its `Log` is open.
+//! Read-only opens take a shared lock, which keeps a writer from starting (and repairing
+//! files underneath them) while they read. A read-only open of a directory a writer already
+//! holds goes ahead without the lock, since read-only mode tolerates concurrent appends.
+//!
+//! The locks are advisory (`flock` on Unix, `LockFileEx` on Windows) and released by the OS
+//! when the file is closed, including when the process dies.
+use crate::LogResult;
+use crate::errors::LogError;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io;
+use std::path::Path;
+use tracing::{debug, warn};
+
+/// Name of the lock file inside a log directory
+pub const LOCK_FILE_NAME: &str = "proglog.lock";
+
+/// Lock on a log directory, released when dropped
+#[derive(Debug)]
+pub struct DirLock {
+    file: Option<File>,
+}
+
+impl DirLock {
+    /// Takes the exclusive lock, creating the lock file if needed.
+    /// Fails with `LogError::Locked` if any other process holds a lock on `dir`.
+    pub fn exclusive(dir: &Path) -> LogResult<Self> {
+        let path = dir.join(LOCK_FILE_NAME);
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .map_err(|e| directory_error(dir, e))?;
+
+        match file.try_lock() {
+            Ok(()) => {
+                debug!(path = ?path, "Locked log directory");
+                Ok(Self { file: Some(file) })
+            }
+            Err(TryLockError::WouldBlock) => Err(LogError::Locked {
+                path: dir.to_string_lossy().to_string(),
+            }),
+            Err(TryLockError::Error(e)) => Err(directory_error(dir, e)),
+        }
+    }
+
+    /// Takes a shared lock if possible. Nothing is created: a directory without a lock file
+    /// has never been opened by a writer, and one held exclusively is read without a lock.
+    pub fn shared(dir: &Path) -> LogResult<Self> {
+        let path = dir.join(LOCK_FILE_NAME);
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self { file: None }),
+            Err(e) => return Err(directory_error(dir, e)),
+        };
+
+        match file.try_lock_shared() {
+            Ok(()) => {
+                debug!(path = ?path, "Locked log directory for reading");
+                Ok(Self { file: Some(file) })
+            }
+            Err(TryLockError::WouldBlock) => {
+                warn!(path = ?path, "Log directory is held by a writer, reading without a lock");
+                Ok(Self { file: None })
+            }
+            Err(TryLockError::Error(e)) => Err(directory_error(dir, e)),
+        }
+    }
+
+    /// Returns true if the lock is actually held
+    pub fn is_held(&self) -> bool {
+        self.file.is_some()
+    }
+}
+
+fn directory_error(dir: &Path, source: io::Error) -> LogError {
+    LogError::DirectoryError {
+        path: dir.to_string_lossy().to_string(),
+        source,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_exclusive_lock_excludes_everyone_else() {
+        let dir = TempDir::new().unwrap();
+
+        let writer = DirLock::exclusive(dir.path()).unwrap();
+        assert!(writer.is_held());
+        assert!(matches!(
+            DirLock::exclusive(dir.path()),
+            Err(LogError::Locked { .. })
+        ));
+        // readers go ahead without the lock
+        assert!(!DirLock::shared(dir.path()).unwrap().is_held());
+
+        drop(writer);
+        assert!(DirLock::exclusive(dir.path()).unwrap().is_held());
+    }
+
+    #[test]
+    fn test_shared_locks_keep_writers_out() {
+        let dir = TempDir::new().unwrap();
+
+        // nothing to lock before a writer has created the lock file
+        assert!(!DirLock::shared(dir.path()).unwrap().is_held());
+        assert!(!dir.path().join(LOCK_FILE_NAME).exists());
+        drop(DirLock::exclusive(dir.path()).unwrap());
+
+        let reader = DirLock::shared(dir.path()).unwrap();
+        let other_reader = DirLock::shared(dir.path()).unwrap();
+        assert!(reader.is_held() && other_reader.is_held());
+        assert!(matches!(
+            DirLock::exclusive(dir.path()),
+            Err(LogError::Locked { .. })
+        ));
+
+        drop(reader);
+        drop(other_reader);
+        assert!(DirLock::exclusive(dir.path()).is_ok());
+    }
+}
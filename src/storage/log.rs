@@ -1,65 +1,257 @@
 //! Log here is a collection of segments that abstracts a single continous distributed log.
-use crate::errors::LogError;
+use crate::errors::{LogError, SegmentError, StorageError};
+use crate::storage::cache::SegmentCache;
+use crate::storage::index::ENTRY_WIDTH;
+use crate::storage::inspect::{index_file_name, segment_base_offsets, store_file_name};
+use crate::storage::lock::DirLock;
+use crate::storage::manifest::{MANIFEST_FILE_NAME, Manifest, SegmentEntry};
 use crate::storage::segment::Segment;
+use crate::storage::snapshot;
+use crate::storage::store::{LEN_WIDTH, MAX_RECORD_BYTES};
 use crate::storage::traits::StorageCleanup;
+use crate::storage::verify::{self, VerifyReport};
+use crate::telemetry::metrics;
 use crate::{LogResult, storage::traits::LocalFileSystem};
-use std::fs::{self, read_dir};
-use std::path::PathBuf;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufReader, Read};
+use std::iter::Rev;
+use std::ops::{Bound, Deref, RangeBounds};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Instant;
 use tracing::{debug, info, instrument, warn};
 
-/// Configuration for the log
+/// When appended records are flushed to disk
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Durability {
+    /// Flush every record before the append returns
+    #[default]
+    Always,
+    /// Leave write-back to the OS; segments are flushed when they are rotated or closed
+    Os,
+}
+
+impl FromStr for Durability {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "always" => Ok(Durability::Always),
+            "os" => Ok(Durability::Os),
+            other => Err(format!(
+                "unknown durability `{other}`, expected `always` or `os`"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Durability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Durability::Always => f.write_str("always"),
+            Durability::Os => f.write_str("os"),
+        }
+    }
+}
+
+/// Configuration for the log. Build it with [`LogConfig::builder`] to have the limits validated up front.
 #[derive(Debug, Clone)]
 pub struct LogConfig {
     /// Maximum size of a segment's store in bytes
     pub max_store_bytes: u64,
     /// Maximum number of index entries per segment
     pub max_index_entries: u64,
+    /// Largest record accepted by `append`
+    pub max_record_bytes: u64,
     /// Directory where log segments are stored
     pub log_dir: PathBuf,
+    /// Oldest segments are deleted once the log grows beyond this many bytes (`None` keeps everything)
+    pub retention_bytes: Option<u64>,
+    /// When appended records are flushed to disk
+    pub durability: Durability,
+    /// Most sealed segments kept open at once; the others are opened again when read
+    pub max_open_segments: usize,
 }
 
 impl Default for LogConfig {
     fn default() -> Self {
         Self {
-            max_store_bytes: 200 * 200, // 1 MB default
+            max_store_bytes: 1024 * 1024, // 1 MiB
             max_index_entries: 1024,
+            max_record_bytes: 64 * 1024, // 64 KiB
             log_dir: PathBuf::from("data"),
+            retention_bytes: None,
+            durability: Durability::Always,
+            max_open_segments: 64,
+        }
+    }
+}
+
+impl LogConfig {
+    /// Starts from the defaults; `build()` validates the result
+    pub fn builder() -> LogConfigBuilder {
+        LogConfigBuilder::default()
+    }
+
+    /// Checks that the limits describe a usable log
+    pub fn validate(&self) -> LogResult<()> {
+        let invalid = |field, reason: String| Err(LogError::InvalidConfig { field, reason });
+
+        if self.max_index_entries == 0 {
+            return invalid("max_index_entries", "must be greater than zero".to_string());
+        }
+        if self.max_index_entries.checked_mul(ENTRY_WIDTH).is_none() {
+            return invalid(
+                "max_index_entries",
+                format!(
+                    "{} entries do not fit in an index file",
+                    self.max_index_entries
+                ),
+            );
+        }
+        if self.max_record_bytes == 0 {
+            return invalid("max_record_bytes", "must be greater than zero".to_string());
+        }
+        if self.max_record_bytes > MAX_RECORD_BYTES {
+            return invalid(
+                "max_record_bytes",
+                format!("must be at most {MAX_RECORD_BYTES} bytes"),
+            );
+        }
+        // a segment must fit at least one record, otherwise every segment is instantly full
+        if self.max_store_bytes < self.max_record_bytes + LEN_WIDTH {
+            return invalid(
+                "max_store_bytes",
+                format!(
+                    "must hold at least one {} byte record ({} bytes)",
+                    self.max_record_bytes,
+                    self.max_record_bytes + LEN_WIDTH
+                ),
+            );
+        }
+        if self.max_open_segments == 0 {
+            return invalid("max_open_segments", "must be greater than zero".to_string());
+        }
+        if let Some(retention_bytes) = self.retention_bytes
+            && retention_bytes < self.max_store_bytes
+        {
+            return invalid(
+                "retention_bytes",
+                format!(
+                    "must be at least max_store_bytes ({})",
+                    self.max_store_bytes
+                ),
+            );
         }
+
+        Ok(())
+    }
+}
+
+/// Builder for [`LogConfig`]
+#[derive(Debug, Clone, Default)]
+pub struct LogConfigBuilder {
+    config: LogConfig,
+}
+
+impl LogConfigBuilder {
+    pub fn max_store_bytes(mut self, max_store_bytes: u64) -> Self {
+        self.config.max_store_bytes = max_store_bytes;
+        self
+    }
+
+    pub fn max_index_entries(mut self, max_index_entries: u64) -> Self {
+        self.config.max_index_entries = max_index_entries;
+        self
+    }
+
+    pub fn max_record_bytes(mut self, max_record_bytes: u64) -> Self {
+        self.config.max_record_bytes = max_record_bytes;
+        self
+    }
+
+    pub fn log_dir(mut self, log_dir: impl Into<PathBuf>) -> Self {
+        self.config.log_dir = log_dir.into();
+        self
+    }
+
+    pub fn retention_bytes(mut self, retention_bytes: Option<u64>) -> Self {
+        self.config.retention_bytes = retention_bytes;
+        self
+    }
+
+    pub fn durability(mut self, durability: Durability) -> Self {
+        self.config.durability = durability;
+        self
+    }
+
+    pub fn max_open_segments(mut self, max_open_segments: usize) -> Self {
+        self.config.max_open_segments = max_open_segments;
+        self
+    }
+
+    /// Validates the limits and returns the config
+    pub fn build(self) -> LogResult<LogConfig> {
+        self.config.validate()?;
+        Ok(self.config)
     }
 }
 
 /// Log manages multiple segments and provides a unified interface for a distributed log.
 /// It handles segment rotation, offset assignment, and routing reads to the appropriate segment
 pub struct Log {
-    segments: Vec<Segment>,
-    active_segment_index: usize,
+    /// Segment that appends go to, `None` only for an empty read-only log
+    active: Option<Segment>,
     next_offset: u64,
     config: LogConfig,
+    read_only: bool,
+    /// Manifest entries of the segments before the active one, by base offset. Read-only
+    /// logs record segments the manifest does not describe as unsealed entries.
+    sealed: BTreeMap<u64, SegmentEntry>,
+    /// Sealed segments currently open, see [`cache`](crate::storage::cache)
+    cache: Mutex<SegmentCache>,
+    // declared last so it is released only after the segments are closed
+    _lock: DirLock,
 }
 
 impl Log {
+    /// Opens the log in `config.log_dir`, creating the directory and first segment if needed.
+    /// The directory is locked for as long as the log is open; fails with `LogError::Locked`
+    /// if another process has it open.
     #[instrument(skip_all, fields(log_dir = ?config.log_dir))]
     pub fn new(config: LogConfig) -> LogResult<Self> {
         debug!("Creating new log");
 
+        config.validate()?;
+
         // Check that the log directory exists
         fs::create_dir_all(&config.log_dir).map_err(|e| LogError::DirectoryError {
             path: config.log_dir.to_string_lossy().to_string(),
             source: e,
         })?;
+        let lock = DirLock::exclusive(&config.log_dir)?;
 
         let mut log = Log {
-            segments: Vec::new(),
-            active_segment_index: 0,
+            active: None,
             next_offset: 0,
+            cache: Mutex::new(SegmentCache::new(config.max_open_segments)),
             config,
+            read_only: false,
+            sealed: BTreeMap::new(),
+            _lock: lock,
         };
 
         // load existing segments or create the first one
         log.load_segments()?;
+        log.record_state();
 
         info!(
-            segments_count = log.segments.len(),
+            segments_count = log.segment_count(),
             next_offset = log.next_offset,
             "Log created successfully"
         );
@@ -67,19 +259,86 @@ impl Log {
         Ok(log)
     }
 
+    /// Opens an existing log directory for reading only. Files are never created, resized
+    /// or repaired, so any number of processes (backup tools, inspectors) can open a log a
+    /// server is still writing to. Appends, truncation and rotation fail with
+    /// `StorageError::ReadOnly`.
+    ///
+    /// The log reflects the records present when it was opened; reopen it to see later
+    /// appends. An empty directory yields an empty log without segments. A shared lock keeps
+    /// writers from starting while it is open, see [`lock`](crate::storage::lock).
+    #[instrument(skip_all, fields(log_dir = ?config.log_dir))]
+    pub fn open_read_only(config: LogConfig) -> LogResult<Self> {
+        debug!("Opening log read-only");
+
+        config.validate()?;
+        let lock = DirLock::shared(&config.log_dir)?;
+
+        let mut log = Log {
+            active: None,
+            next_offset: 0,
+            cache: Mutex::new(SegmentCache::new(config.max_open_segments)),
+            config,
+            read_only: true,
+            sealed: BTreeMap::new(),
+            _lock: lock,
+        };
+        log.load_segments()?;
+
+        info!(
+            segments_count = log.segment_count(),
+            next_offset = log.next_offset,
+            "Log opened read-only"
+        );
+
+        Ok(log)
+    }
+
+    /// Checks the segment files in `log_dir` for torn records, index entries pointing outside
+    /// their store, offset gaps or overlaps between segments and orphan files, without
+    /// modifying anything. See [`verify`](crate::storage::verify).
+    pub fn verify(log_dir: impl AsRef<Path>) -> LogResult<VerifyReport> {
+        let _lock = DirLock::shared(log_dir.as_ref())?;
+        verify::run(log_dir.as_ref(), false)
+    }
+
+    /// Runs the checks of [`Log::verify`] and repairs what can be repaired: torn records and
+    /// bad index entries are truncated away, orphan indexes deleted and missing indexes
+    /// rebuilt. Gaps and overlaps are only reported. Fails with `LogError::Locked` while the
+    /// log is open elsewhere.
+    pub fn repair(log_dir: impl AsRef<Path>) -> LogResult<VerifyReport> {
+        let _lock = DirLock::exclusive(log_dir.as_ref())?;
+        verify::run(log_dir.as_ref(), true)
+    }
+
+    /// Returns true if the log was opened with `open_read_only`
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
     /// Appends data to the log and returns the assigned offset
     #[instrument(skip(self, data), fields(data_len = data.len()))]
     pub fn append(&mut self, data: &[u8]) -> LogResult<u64> {
         debug!("Appending data to log");
+        self.ensure_writable()?;
+
+        if data.len() as u64 > self.config.max_record_bytes {
+            return Err(LogError::RecordTooLarge {
+                size: data.len() as u64,
+                max_record_bytes: self.config.max_record_bytes,
+            });
+        }
 
         if self.active_segment().is_full() {
             info!("Active segment is full, rotating to a new segment");
             self.rotate_segment()?;
+            self.enforce_retention()?;
         }
 
         let offset = self.active_segment_mut().append(data)?;
 
         self.next_offset = offset + 1;
+        metrics::record_append(data.len(), data.len() as u64 + LEN_WIDTH, self.next_offset);
         info!(offset, "Data append to log");
         Ok(offset)
     }
@@ -88,9 +347,11 @@ impl Log {
     #[instrument(skip(self), fields(offset))]
     pub fn read(&self, offset: u64) -> LogResult<Vec<u8>> {
         debug!(offset, "Reading from log");
+        let start = Instant::now();
 
         let segment = self.find_segment_for_offset(offset)?;
         let data = segment.read(offset)?;
+        metrics::record_read(start.elapsed());
 
         debug!(offset, data_len = data.len(), "Successfully read from log");
 
@@ -98,8 +359,8 @@ impl Log {
     }
 
     /// Efficiently scans records sequentially starting from the given offset.
-    /// This is faster than calling `read()` repeatedly because it avoids index lookups
-    /// and scans the store files directly.
+    /// This is faster than calling `read()` repeatedly because it looks up only the first
+    /// record of each segment in the index and then walks the store file record by record.
     ///
     /// # Example
     /// ```ignore
@@ -110,12 +371,203 @@ impl Log {
     /// ```
     #[instrument(skip(self), fields(start_offset))]
     pub fn scan_from(&self, start_offset: u64) -> LogScanIterator<'_> {
+        self.scan_range(start_offset..)
+    }
+
+    /// Scans the records in `range`, e.g. `5000..6000` or `..=42`. An unbounded start begins
+    /// at the base offset and the end is clamped to the next offset, so `log.scan_range(..)`
+    /// scans the whole log. The iterator is double-ended: `.rev()` reads newest first.
+    ///
+    /// # Example
+    /// ```ignore
+    /// for result in log.scan_range(5000..6000).rev() {
+    ///     let (offset, data) = result?;
+    ///     process(offset, data);
+    /// }
+    /// ```
+    #[instrument(skip(self, range))]
+    pub fn scan_range(&self, range: impl RangeBounds<u64>) -> LogScanIterator<'_> {
+        let start_offset = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start.saturating_add(1),
+            Bound::Unbounded => self.base_offset(),
+        };
+        let end_offset = match range.end_bound() {
+            Bound::Included(&end) => end.saturating_add(1),
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.next_offset,
+        };
+
         LogScanIterator {
             log: self,
             current_offset: start_offset,
-            current_segment_idx: 0,
+            end_offset: end_offset.min(self.next_offset),
+            current_segment: None,
             current_position: None,
+            back_segment: None,
+        }
+    }
+
+    /// Scans backwards from `offset` (inclusive) down to the base offset, newest record
+    /// first. Offsets past the end of the log start at the latest record.
+    ///
+    /// ```ignore
+    /// // the last 100 records
+    /// let latest: Vec<_> = log.scan_rev_from(u64::MAX).take(100).collect::<LogResult<_>>()?;
+    /// ```
+    pub fn scan_rev_from(&self, offset: u64) -> Rev<LogScanIterator<'_>> {
+        self.scan_range(..=offset).rev()
+    }
+
+    /// Returns a reader over the raw bytes of the log: every segment's store file in offset
+    /// order, records framed as `[8-byte length][data]` exactly as on disk. Nothing is
+    /// decoded, so this is the cheap way to ship a whole log for a backup or a snapshot.
+    /// [`Log::restore_from`] turns such a stream back into a log.
+    ///
+    /// Every segment ends with its last indexed record, so reading a read-only log next to
+    /// a live writer does not stream the space the writer has preallocated. The stream
+    /// carries no offsets; restoring it numbers the records from the offset the target log
+    /// is at.
+    ///
+    /// ```ignore
+    /// let mut backup = File::create("log.bak")?;
+    /// io::copy(&mut log.reader(), &mut backup)?;
+    /// ```
+    pub fn reader(&self) -> LogReader<'_> {
+        let mut bases: Vec<u64> = self.sealed.keys().copied().collect();
+        bases.extend(self.active.iter().map(|s| s.base_offset()));
+
+        LogReader {
+            log: self,
+            bases: bases.into_iter(),
+            current_segment: None,
+            position: 0,
+        }
+    }
+
+    /// Rebuilds the log from a stream produced by [`Log::reader`]: each record is appended
+    /// in turn, so segments rotate and indexes are built as for any append. Returns the
+    /// number of records restored.
+    ///
+    /// Only an empty log can be restored into, otherwise fails with `LogError::NotEmpty`.
+    /// A stream ending inside a record fails with `LogError::RestoreFailed`, leaving the
+    /// records before it in the log.
+    #[instrument(skip_all, fields(log_dir = ?self.config.log_dir))]
+    pub fn restore_from(&mut self, reader: impl Read) -> LogResult<u64> {
+        self.ensure_writable()?;
+        if !self.is_empty() {
+            return Err(LogError::NotEmpty {
+                path: self.config.log_dir.to_string_lossy().to_string(),
+                next_offset: self.next_offset,
+            });
+        }
+
+        let mut reader = BufReader::new(reader);
+        let mut position = 0;
+        let mut restored = 0;
+        while let Some(len) = read_frame_len(&mut reader)
+            .map_err(|source| LogError::RestoreFailed { position, source })?
+        {
+            if len > self.config.max_record_bytes {
+                return Err(LogError::RecordTooLarge {
+                    size: len,
+                    max_record_bytes: self.config.max_record_bytes,
+                });
+            }
+
+            let mut data = vec![0; len as usize];
+            reader
+                .read_exact(&mut data)
+                .map_err(|source| LogError::RestoreFailed { position, source })?;
+            self.append(&data)?;
+
+            position += LEN_WIDTH + len;
+            restored += 1;
+        }
+
+        info!(restored, bytes = position, "Log restored from stream");
+        Ok(restored)
+    }
+
+    /// Writes a consistent copy of the log as it is now into `dest_dir`, which must not
+    /// already hold a log: every sealed segment, the active segment up to the next offset
+    /// and a manifest describing them. Returns that manifest; its last entry ends at the
+    /// offset the snapshot was taken at.
+    ///
+    /// Sealed segments are hard linked where possible, so only the written part of the
+    /// active segment is copied and the log is borrowed just long enough for appends to
+    /// resume right after. The snapshot is a log directory of its own, see
+    /// [`snapshot`](crate::storage::snapshot); [`Log::restore`] turns it back into a log.
+    #[instrument(skip_all, fields(log_dir = ?self.config.log_dir, dest_dir = ?dest_dir.as_ref()))]
+    pub fn snapshot(&self, dest_dir: impl AsRef<Path>) -> LogResult<Manifest> {
+        let dest_dir = dest_dir.as_ref();
+        fs::create_dir_all(dest_dir).map_err(snapshot::snapshot_error(dest_dir))?;
+        if !segment_base_offsets(dest_dir)?.is_empty() || dest_dir.join(MANIFEST_FILE_NAME).exists()
+        {
+            return Err(snapshot::snapshot_error(dest_dir)(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "directory already holds a log",
+            )));
+        }
+
+        let mut entries = Vec::with_capacity(self.segment_count());
+        for entry in self.sealed.values() {
+            if entry.sealed {
+                snapshot::link_segment(&self.config.log_dir, dest_dir, entry.base_offset)?;
+                entries.push(entry.clone());
+            } else {
+                // segments of a read-only log the manifest did not describe may still grow
+                let segment = self.open_sealed_segment(entry)?;
+                entries.push(snapshot::write_segment(dest_dir, &segment)?);
+            }
+        }
+        if let Some(active) = &self.active {
+            entries.push(snapshot::write_segment(dest_dir, active)?);
+        }
+
+        let manifest = Manifest::new(entries);
+        manifest.write(dest_dir)?;
+        info!(
+            segments = manifest.segments.len(),
+            next_offset = self.next_offset,
+            "Snapshot written"
+        );
+        Ok(manifest)
+    }
+
+    /// Restores a snapshot taken with [`Log::snapshot`] into `config.log_dir` and opens it
+    /// for writing. Segment files are copied, never linked, so the snapshot stays intact
+    /// and can be restored again. Offsets are those of the snapshotted log.
+    ///
+    /// Fails with `LogError::NotEmpty` if the directory already holds records, and with
+    /// `LogError::SnapshotFailed` if `snapshot_dir` has no readable manifest. A failed restore
+    /// removes whatever it copied, so it can be retried into the same directory.
+    #[instrument(skip_all, fields(snapshot_dir = ?snapshot_dir.as_ref(), log_dir = ?config.log_dir))]
+    pub fn restore(snapshot_dir: impl AsRef<Path>, config: LogConfig) -> LogResult<Self> {
+        let snapshot_dir = snapshot_dir.as_ref();
+        let manifest_path = snapshot_dir.join(MANIFEST_FILE_NAME);
+        let manifest = Manifest::load(snapshot_dir).ok_or_else(|| {
+            snapshot::snapshot_error(&manifest_path)(io::Error::new(
+                io::ErrorKind::NotFound,
+                "snapshot has no readable manifest",
+            ))
+        })?;
+
+        let mut log = Log::new(config)?;
+        if !log.is_empty() {
+            return Err(LogError::NotEmpty {
+                path: log.config.log_dir.to_string_lossy().to_string(),
+                next_offset: log.next_offset,
+            });
         }
+        log.load_snapshot(snapshot_dir, manifest)?;
+
+        info!(
+            segments_count = log.segment_count(),
+            next_offset = log.next_offset,
+            "Log restored from snapshot"
+        );
+        Ok(log)
     }
 
     pub fn next_offset(&self) -> u64 {
@@ -124,7 +576,12 @@ impl Log {
 
     /// Returns the lowest offset available in the log
     pub fn base_offset(&self) -> u64 {
-        self.segments.first().map(|s| s.base_offset()).unwrap_or(0)
+        self.sealed
+            .keys()
+            .next()
+            .copied()
+            .or_else(|| self.active.as_ref().map(|s| s.base_offset()))
+            .unwrap_or(0)
     }
 
     /// Returns the highest offset ni the log (if any records exist)
@@ -137,62 +594,59 @@ impl Log {
     }
 
     pub fn segment_count(&self) -> usize {
-        self.segments.len()
+        self.sealed.len() + usize::from(self.active.is_some())
     }
 
     pub fn is_empty(&self) -> bool {
         // check also that the log is empty regardless of whether empty segment objects exist or not.
-        self.segments.is_empty() || self.segments.iter().all(|s| s.is_empty())
+        self.sealed.values().all(|entry| entry.index_entries == 0)
+            && self.active.as_ref().is_none_or(|s| s.is_empty())
     }
 
     /// Returns total size of the log which contains the total size of all segments in bytes
     pub fn total_size(&self) -> u64 {
-        self.segments.iter().map(|s| s.store_size()).sum()
+        let sealed: u64 = self.sealed.values().map(|entry| entry.store_bytes).sum();
+        sealed + self.active.as_ref().map_or(0, |s| s.store_size())
     }
 
     /// truncates the log and keeps only the segments that are less than the truncate point
     #[instrument(skip(self), fields(offset))]
     pub fn truncate(&mut self, offset: u64) -> LogResult<()> {
         info!(offset, "Truncating log");
-
-        let cleanup = LocalFileSystem;
-        let mut segments_to_remove = Vec::new();
-
-        for segment in &self.segments {
-            if segment.base_offset() >= offset {
-                segments_to_remove.push(segment.base_offset());
-            }
+        self.ensure_writable()?;
+
+        let mut segments_to_remove: Vec<u64> =
+            self.sealed.range(offset..).map(|(&b, _)| b).collect();
+        if let Some(active) = &self.active
+            && active.base_offset() >= offset
+        {
+            segments_to_remove.push(active.base_offset());
+            // close the files before deleting them
+            self.active = None;
         }
+        self.sealed.retain(|&base_offset, _| base_offset < offset);
 
         for base_offset in segments_to_remove {
-            let store_path = self.config.log_dir.join(format!("{base_offset:020}.log"));
-            let index_path = self.config.log_dir.join(format!("{base_offset:020}.idx"));
-
-            cleanup
-                .cleanup_segment(&store_path, &index_path)
-                .map_err(|e| LogError::CleanupError {
-                    base_offset,
-                    source: e.into(),
-                })?;
-        }
-        self.segments
-            .retain(|segment| segment.base_offset() < offset);
-
-        // this is for the edge case so that we always at least have one segment
-        if self.segments.is_empty() {
-            // Create a new segment starting at the truncate offset
-            let segment = self.create_segment(offset)?;
-            self.segments.push(segment);
-            self.active_segment_index = 0;
+            self.remove_segment_files(base_offset)?;
         }
 
-        // Update active segment index if needed
-        if self.active_segment_index >= self.segments.len() && !self.segments.is_empty() {
-            self.active_segment_index = self.segments.len() - 1;
+        if self.active.is_none() {
+            let segment = match self.sealed.pop_last() {
+                // the last sealed segment is the active one again, and has to be writable
+                Some((base_offset, _)) => {
+                    self.cache_mut().remove(base_offset);
+                    self.create_segment(base_offset)?
+                }
+                // this is for the edge case so that we always at least have one segment
+                None => self.create_segment(offset)?,
+            };
+            self.active = Some(segment);
         }
+        self.write_manifest()?;
 
         self.next_offset = offset;
 
+        self.record_state();
         info!(offset, "Log truncated");
         Ok(())
     }
@@ -200,88 +654,311 @@ impl Log {
     /// rotate_segment creates a new segment and makes it active
     #[instrument(skip(self))]
     pub fn rotate_segment(&mut self) -> LogResult<()> {
+        self.ensure_writable()?;
         let base_offset = self.next_offset;
 
         debug!(base_offset, "Creating new segment");
 
+        // the outgoing segment is only sealed once it has a successor, so failing to create
+        // one leaves it active and writable
         let segment = self.create_segment(base_offset)?;
-        self.segments.push(segment);
-        self.active_segment_index = self.segments.len() - 1;
+
+        // sealing flushes the outgoing segment, which also makes it durable
+        // when appends are not synced one by one
+        let sealed = match self.active_segment_mut().seal() {
+            Ok(sealed) => sealed,
+            Err(e) => {
+                drop(segment);
+                self.remove_segment_files(base_offset)?;
+                return Err(e.into());
+            }
+        };
+        // the outgoing segment is open already, so it starts out cached
+        if let Some(previous) = self.active.replace(segment) {
+            self.cache_mut().insert(Arc::new(previous));
+        }
+        self.sealed.insert(sealed.base_offset, sealed);
+        self.write_manifest()?;
+        metrics::record_rotation();
+        self.record_state();
 
         info!(
             base_offset,
-            active_segment_index = self.active_segment_index,
-            total_segments = self.segments.len(),
+            total_segments = self.segment_count(),
             "Segment rotated successfully"
         );
 
         Ok(())
     }
 
+    /// Flushes and closes the active segment. Errors are reported instead of being
+    /// swallowed the way `Drop` has to. Sealed segments have nothing left to flush.
+    #[instrument(skip(self), fields(log_dir = ?self.config.log_dir))]
+    pub fn close(self) -> LogResult<()> {
+        if let Some(segment) = self.active {
+            let base_offset = segment.base_offset();
+            if let Err(e) = segment.close() {
+                warn!(base_offset, error = %e, "Failed to close segment");
+                return Err(e.into());
+            }
+        }
+
+        info!(next_offset = self.next_offset, "Log closed");
+        Ok(())
+    }
+
+    /// Deletes the oldest segments while the log is larger than `retention_bytes`.
+    /// The active segment is never deleted.
+    #[instrument(skip(self))]
+    fn enforce_retention(&mut self) -> LogResult<()> {
+        let Some(retention_bytes) = self.config.retention_bytes else {
+            return Ok(());
+        };
+
+        let mut deleted = false;
+        while self.total_size() > retention_bytes
+            && let Some((base_offset, _)) = self.sealed.pop_first()
+        {
+            self.remove_segment_files(base_offset)?;
+
+            info!(
+                base_offset,
+                retention_bytes,
+                new_base_offset = self.base_offset(),
+                "Deleted segment past retention"
+            );
+            deleted = true;
+        }
+
+        if deleted {
+            self.write_manifest()?;
+        }
+        self.record_state();
+        Ok(())
+    }
+
+    fn ensure_writable(&self) -> LogResult<()> {
+        if self.read_only {
+            return Err(SegmentError::from(StorageError::ReadOnly).into());
+        }
+        Ok(())
+    }
+
+    /// Publishes the segment count, size and next offset gauges
+    fn record_state(&self) {
+        metrics::record_log_state(self.segment_count(), self.total_size(), self.next_offset);
+    }
+
     /// Loads existing segments from disk or creates the first segment
     #[instrument(skip(self))]
     fn load_segments(&mut self) -> LogResult<()> {
         debug!("Loading existing segments");
 
-        let entries = read_dir(&self.config.log_dir).map_err(|e| LogError::DirectoryError {
-            path: self.config.log_dir.to_string_lossy().to_string(),
-            source: e,
-        })?;
-
-        let mut segment_offset = Vec::new();
+        // base offsets of all .log files, in order
+        let segment_offset = segment_base_offsets(&self.config.log_dir)?;
 
-        // find all .log files and extract their base offsets.
-        for entry in entries {
-            let entry = entry.map_err(|e| LogError::DirectoryError {
-                path: self.config.log_dir.to_string_lossy().to_string(),
-                source: e,
-            })?;
-
-            let path = entry.path();
-            if let Some(extension) = path.extension()
-                && extension == "log"
-                && let Some(file_name) = path.file_stem()
-                && let Ok(base_offset) = file_name.to_string_lossy().parse::<u64>()
-            {
-                segment_offset.push(base_offset);
+        let Some(&active_base_offset) = segment_offset.last() else {
+            if self.read_only {
+                debug!("No existing segments found");
+            } else {
+                debug!("No existing segments found, creating initial segment");
+                self.active = Some(self.create_segment(0)?);
+                self.next_offset = 0;
+            }
+            return Ok(());
+        };
+        debug!("Found {} existing segments", segment_offset.len());
+
+        let manifest = Manifest::load(&self.config.log_dir);
+        let mut manifest_stale = false;
+        let mut validated = 1;
+
+        for &base_offset in &segment_offset[..segment_offset.len() - 1] {
+            // sealed segments left as the manifest describes them are not even opened
+            // until read; the active one is always validated
+            let sealed = manifest
+                .as_ref()
+                .and_then(|manifest| manifest.sealed(base_offset))
+                .filter(|entry| self.files_match(entry));
+            if let Some(entry) = sealed {
+                self.sealed.insert(base_offset, entry.clone());
+                continue;
             }
+
+            // unknown to the manifest, or left preallocated by a crash
+            validated += 1;
+            let mut segment = self.create_segment(base_offset)?;
+            let entry = if self.read_only {
+                segment.manifest_entry(false)
+            } else {
+                manifest_stale = true;
+                segment.seal()?
+            };
+            self.sealed.insert(base_offset, entry);
+            self.cache_mut().insert(Arc::new(segment));
         }
 
-        // Sort offsets to load segments in order
-        segment_offset.sort_unstable();
+        let active = self.create_segment(active_base_offset)?;
+        self.next_offset = active.next_offset();
+        self.active = Some(active);
 
-        if segment_offset.is_empty() {
-            debug!("No existing segments found, creating initial segment");
-            let segment = self.create_segment(0)?;
-            self.segments.push(segment);
-            self.active_segment_index = 0;
-            self.next_offset = 0;
-        } else {
-            debug!("Found {} existing segments", segment_offset.len());
+        if manifest_stale {
+            self.write_manifest()?;
+        }
 
-            for base_offset in segment_offset {
-                let segment = self.create_segment(base_offset)?;
-                self.segments.push(segment);
-            }
+        info!(
+            loaded_segments = self.segment_count(),
+            validated_segments = validated,
+            next_offset = self.next_offset,
+            "Loaded existing segments"
+        );
 
-            self.active_segment_index = self.segments.len() - 1;
+        Ok(())
+    }
 
-            let last_segment = &self.segments[self.active_segment_index];
-            self.next_offset = last_segment.next_offset();
+    /// Replaces the segments of an empty log with the ones of a snapshot, then loads them
+    fn load_snapshot(&mut self, snapshot_dir: &Path, manifest: Manifest) -> LogResult<()> {
+        // drop the empty segments `Log::new` opened or created
+        let mut empty: Vec<u64> = self.sealed.keys().copied().collect();
+        empty.extend(self.active.take().map(|segment| segment.base_offset()));
+        self.sealed.clear();
 
-            info!(
-                loaded_segments = self.segments.len(),
-                next_offset = self.next_offset,
-                "Loaded existing segments"
-            );
+        for base_offset in empty {
+            self.remove_segment_files(base_offset)?;
         }
 
+        if let Err(e) = self.copy_snapshot(snapshot_dir, &manifest) {
+            warn!(error = %e, "Restore failed, removing the files it copied");
+            self.discard_restore(&manifest);
+            return Err(e);
+        }
+        self.record_state();
         Ok(())
     }
 
+    fn copy_snapshot(&mut self, snapshot_dir: &Path, manifest: &Manifest) -> LogResult<()> {
+        for entry in &manifest.segments {
+            snapshot::copy_segment(snapshot_dir, &self.config.log_dir, entry.base_offset)?;
+        }
+        manifest.write(&self.config.log_dir)?;
+
+        self.load_segments()?;
+        // the last segment is active again
+        self.write_manifest()
+    }
+
+    /// Empties the log directory again after a failed restore. It held no records before,
+    /// so every segment file in it came from the snapshot or from opening what was copied.
+    fn discard_restore(&mut self, manifest: &Manifest) {
+        self.active = None;
+        let mut base_offsets: Vec<u64> = self.sealed.keys().copied().collect();
+        self.sealed.clear();
+        base_offsets.extend(manifest.segments.iter().map(|entry| entry.base_offset));
+        base_offsets.extend(segment_base_offsets(&self.config.log_dir).unwrap_or_default());
+
+        let mut names = vec![MANIFEST_FILE_NAME.to_string()];
+        for base_offset in base_offsets {
+            self.cache_mut().remove(base_offset);
+            names.extend([store_file_name(base_offset), index_file_name(base_offset)]);
+        }
+        for name in names {
+            match fs::remove_file(self.config.log_dir.join(&name)) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                    warn!(file = %name, error = %e, "Failed to remove restored file");
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Returns true if a sealed segment's files still have the sizes its entry records.
+    /// Files a crash left preallocated do not, and go through recovery instead.
+    fn files_match(&self, entry: &SegmentEntry) -> bool {
+        let len = |name: String| {
+            fs::metadata(self.config.log_dir.join(name))
+                .map(|metadata| metadata.len())
+                .ok()
+        };
+        len(store_file_name(entry.base_offset)) == Some(entry.store_bytes)
+            && len(index_file_name(entry.base_offset)) == Some(entry.index_entries * ENTRY_WIDTH)
+    }
+
+    /// Returns a sealed segment from the cache, opening it if it is not open yet
+    fn open_sealed_segment(&self, entry: &SegmentEntry) -> LogResult<Arc<Segment>> {
+        let mut cache = self.cache.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(segment) = cache.get(entry.base_offset) {
+            return Ok(segment);
+        }
+
+        let store_path = self.config.log_dir.join(store_file_name(entry.base_offset));
+        let index_path = self.config.log_dir.join(index_file_name(entry.base_offset));
+        let segment = if entry.sealed {
+            Segment::open_sealed(
+                store_path,
+                index_path,
+                entry,
+                self.config.max_store_bytes,
+                self.config.max_index_entries,
+            )?
+        } else {
+            Segment::open_read_only(
+                store_path,
+                index_path,
+                entry.base_offset,
+                self.config.max_store_bytes,
+                self.config.max_index_entries,
+            )?
+        };
+        let segment = Arc::new(segment);
+        cache.insert(Arc::clone(&segment));
+        debug!(
+            base_offset = entry.base_offset,
+            open_segments = cache.len(),
+            "Opened sealed segment"
+        );
+        Ok(segment)
+    }
+
+    /// Writes the manifest: sealed segments as recorded when they were rotated out,
+    /// the active one as it is now
+    fn write_manifest(&self) -> LogResult<()> {
+        let entries = self
+            .sealed
+            .values()
+            .cloned()
+            .chain(self.active.iter().map(|s| s.manifest_entry(false)))
+            .collect();
+        Manifest::new(entries).write(&self.config.log_dir)
+    }
+
+    /// Deletes the files of a segment no longer in `active` or `sealed`, closing them first
+    /// if the cache still holds them open
+    fn remove_segment_files(&mut self, base_offset: u64) -> LogResult<()> {
+        self.cache_mut().remove(base_offset);
+
+        let store_path = self.config.log_dir.join(store_file_name(base_offset));
+        let index_path = self.config.log_dir.join(index_file_name(base_offset));
+        LocalFileSystem
+            .cleanup_segment(&store_path, &index_path)
+            .map_err(|e| LogError::CleanupError {
+                base_offset,
+                source: e.into(),
+            })
+    }
+
     fn create_segment(&self, base_offset: u64) -> LogResult<Segment> {
-        let store_path = self.config.log_dir.join(format!("{base_offset:020}.log"));
-        let index_path = self.config.log_dir.join(format!("{base_offset:020}.idx"));
+        let store_path = self.config.log_dir.join(store_file_name(base_offset));
+        let index_path = self.config.log_dir.join(index_file_name(base_offset));
+
+        if self.read_only {
+            return Ok(Segment::open_read_only(
+                store_path,
+                index_path,
+                base_offset,
+                self.config.max_store_bytes,
+                self.config.max_index_entries,
+            )?);
+        }
 
         debug!(
             base_offset,
@@ -289,22 +966,34 @@ impl Log {
             index_path = ?index_path,
             "Creating segment files"
         );
+        // a sealed segment being reopened may share its files with a snapshot
+        snapshot::unshare_segment(&self.config.log_dir, base_offset)?;
 
-        Segment::new(
+        let mut segment = Segment::new(
             store_path,
             index_path,
             base_offset,
             self.config.max_store_bytes,
             self.config.max_index_entries,
-        )
-        .map_err(LogError::from)
+        )?;
+        segment.set_sync_on_append(self.config.durability == Durability::Always);
+
+        Ok(segment)
     }
 
-    fn find_segment_for_offset(&self, offset: u64) -> LogResult<&Segment> {
-        for segment in &self.segments {
-            if segment.contains_offset(offset) {
-                return Ok(segment);
-            }
+    /// Finds the segment holding `offset` by its base offset, in O(log n) segments
+    fn find_segment_for_offset(&self, offset: u64) -> LogResult<SegmentRef<'_>> {
+        if let Some(active) = &self.active
+            && active.contains_offset(offset)
+        {
+            return Ok(SegmentRef::Active(active));
+        }
+
+        // the last sealed segment starting at or before the offset is the only candidate
+        if let Some((_, entry)) = self.sealed.range(..=offset).next_back()
+            && offset < entry.next_offset
+        {
+            return Ok(SegmentRef::Sealed(self.open_sealed_segment(entry)?));
         }
 
         Err(LogError::OffsetNotFound {
@@ -314,23 +1003,69 @@ impl Log {
         })
     }
 
+    /// Returns the segment starting at `base_offset`, the active one or a sealed one
+    fn segment_ref(&self, base_offset: u64) -> LogResult<SegmentRef<'_>> {
+        match (&self.active, self.sealed.get(&base_offset)) {
+            (_, Some(entry)) => Ok(SegmentRef::Sealed(self.open_sealed_segment(entry)?)),
+            (Some(active), None) if active.base_offset() == base_offset => {
+                Ok(SegmentRef::Active(active))
+            }
+            _ => Err(LogError::OffsetNotFound {
+                offset: base_offset,
+                base_offset: self.base_offset(),
+                next_offset: self.next_offset,
+            }),
+        }
+    }
+
     /// Returns a reference to the active segment
     fn active_segment(&self) -> &Segment {
-        &self.segments[self.active_segment_index]
+        self.active
+            .as_ref()
+            .expect("a writable log always has an active segment")
     }
 
     /// Returns a mutable reference to the active segment
     fn active_segment_mut(&mut self) -> &mut Segment {
-        &mut self.segments[self.active_segment_index]
+        self.active
+            .as_mut()
+            .expect("a writable log always has an active segment")
+    }
+
+    fn cache_mut(&mut self) -> &mut SegmentCache {
+        self.cache.get_mut().unwrap_or_else(PoisonError::into_inner)
     }
 }
 
-/// Iterator for efficiently scanning log records sequentially
+/// A segment being read: the active one, or a sealed one held open by the cache
+enum SegmentRef<'a> {
+    Active(&'a Segment),
+    Sealed(Arc<Segment>),
+}
+
+impl Deref for SegmentRef<'_> {
+    type Target = Segment;
+
+    fn deref(&self) -> &Segment {
+        match self {
+            SegmentRef::Active(segment) => segment,
+            SegmentRef::Sealed(segment) => segment,
+        }
+    }
+}
+
+/// Iterator for efficiently scanning log records sequentially, from either end
 pub struct LogScanIterator<'a> {
     log: &'a Log,
     current_offset: u64,
-    current_segment_idx: usize,
+    /// Offset after the last record to scan; `next_back` reads the record before it
+    end_offset: u64,
+    current_segment: Option<SegmentRef<'a>>,
+    /// Store position of `current_offset` in `current_segment`, once it has been looked up
     current_position: Option<u64>,
+    /// Segment of the last record read from the back. Records have no trailer to walk
+    /// the store backwards with, so every record read from the back is looked up.
+    back_segment: Option<SegmentRef<'a>>,
 }
 
 impl<'a> Iterator for LogScanIterator<'a> {
@@ -338,43 +1073,65 @@ impl<'a> Iterator for LogScanIterator<'a> {
 
     fn next(&mut self) -> Option<Self::Item> {
         // check if we're at the end
-        if self.current_offset >= self.log.next_offset {
+        if self.current_offset >= self.end_offset {
             return None;
         }
 
-        if self.current_position.is_none() {
-            match self.find_segment_with_offset() {
-                Ok(seg_idx) => {
-                    self.current_segment_idx = seg_idx;
-                    let segment = &self.log.segments[seg_idx];
-                    match segment.read(self.current_offset) {
-                        Ok(data) => {
-                            let offset = self.current_offset;
-                            self.current_offset += 1;
-                            return Some(Ok((offset, data)));
-                        }
-                        Err(e) => return Some(Err(e.into())),
-                    }
+        // walk the current segment by position until it runs out, then look up where
+        // the offset starts in the next one
+        let (segment, position) = match (self.current_segment.take(), self.current_position) {
+            (Some(segment), Some(position)) if segment.contains_offset(self.current_offset) => {
+                (segment, position)
+            }
+            _ => {
+                let segment = match self.find_segment_with_offset() {
+                    Ok(segment) => segment,
+                    Err(e) => return Some(Err(e)),
+                };
+                match segment.position(self.current_offset) {
+                    Ok(position) => (segment, position),
+                    Err(e) => return Some(Err(e.into())),
                 }
-                Err(e) => return Some(Err(e)),
             }
-        }
-
-        let segement = &self.log.segments[self.current_segment_idx];
+        };
 
-        if !segement.contains_offset(self.current_offset) {
-            self.current_segment_idx += 1;
-            if self.current_segment_idx >= self.log.segments.len() {
-                return None;
+        let result = segment.read_at(position);
+        self.current_segment = Some(segment);
+        match result {
+            Ok((data, next_position)) => {
+                self.current_position = Some(next_position);
+                let offset = self.current_offset;
+                self.current_offset += 1;
+                Some(Ok((offset, data)))
+            }
+            Err(e) => {
+                self.current_position = None;
+                Some(Err(e.into()))
             }
-            self.current_position = None;
-            return self.next();
         }
+    }
+}
+
+impl DoubleEndedIterator for LogScanIterator<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.current_offset >= self.end_offset {
+            return None;
+        }
+        let offset = self.end_offset - 1;
+
+        let segment = match self.back_segment.take() {
+            Some(segment) if segment.contains_offset(offset) => segment,
+            _ => match self.log.find_segment_for_offset(offset) {
+                Ok(segment) => segment,
+                Err(e) => return Some(Err(e)),
+            },
+        };
 
-        match segement.read(self.current_offset) {
+        let result = segment.read(offset);
+        self.back_segment = Some(segment);
+        match result {
             Ok(data) => {
-                let offset = self.current_offset;
-                self.current_offset += 1;
+                self.end_offset = offset;
                 Some(Ok((offset, data)))
             }
             Err(e) => Some(Err(e.into())),
@@ -383,23 +1140,68 @@ impl<'a> Iterator for LogScanIterator<'a> {
 }
 
 impl<'a> LogScanIterator<'a> {
-    fn find_segment_with_offset(&self) -> LogResult<usize> {
-        for (idx, segment) in self.log.segments.iter().enumerate() {
-            if segment.contains_offset(self.current_offset) {
-                return Ok(idx);
-            }
-        }
-        Err(LogError::OffsetNotFound {
-            offset: self.current_offset,
-            base_offset: self.log.base_offset(),
-            next_offset: self.log.next_offset,
-        })
+    /// Looks up the segment of the next offset, only needed when a scan starts or leaves a segment
+    fn find_segment_with_offset(&self) -> LogResult<SegmentRef<'a>> {
+        self.log.find_segment_for_offset(self.current_offset)
     }
 }
 
-#[cfg(test)]
-mod tests {
+/// Reader over the store files of a log, one segment after another, see [`Log::reader`]
+pub struct LogReader<'a> {
+    log: &'a Log,
+    /// Base offsets of the segments not read yet
+    bases: std::vec::IntoIter<u64>,
+    current_segment: Option<SegmentRef<'a>>,
+    /// Bytes of `current_segment` already read
+    position: usize,
+}
+
+impl Read for LogReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if let Some(segment) = &self.current_segment {
+                let remaining = &segment.store_data()[self.position..];
+                if !remaining.is_empty() {
+                    let n = remaining.len().min(buf.len());
+                    buf[..n].copy_from_slice(&remaining[..n]);
+                    self.position += n;
+                    return Ok(n);
+                }
+            }
+
+            let Some(base_offset) = self.bases.next() else {
+                return Ok(0);
+            };
+            self.current_segment = Some(
+                self.log
+                    .segment_ref(base_offset)
+                    .map_err(io::Error::other)?,
+            );
+            self.position = 0;
+        }
+    }
+}
+
+/// Reads the length prefix of the next record, `None` if the stream ends cleanly before it
+fn read_frame_len(reader: &mut impl Read) -> io::Result<Option<u64>> {
+    let mut len = [0; LEN_WIDTH as usize];
+    let mut filled = 0;
+    while filled < len.len() {
+        match reader.read(&mut len[filled..]) {
+            Ok(0) if filled == 0 => return Ok(None),
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(Some(u64::from_le_bytes(len)))
+}
+
+#[cfg(test)]
+mod tests {
     use super::*;
+    use crate::storage::lock::LOCK_FILE_NAME;
     use std::sync::Once;
     use tempfile::TempDir;
     use tracing_subscriber::{EnvFilter, fmt};
@@ -418,11 +1220,17 @@ mod tests {
     }
 
     fn test_config(temp_dir: &TempDir) -> LogConfig {
-        LogConfig {
-            max_store_bytes: 200, //we keep this small to test rotation later
-            max_index_entries: 10,
-            log_dir: temp_dir.path().to_path_buf(),
-        }
+        LogConfig::builder()
+            .max_store_bytes(200) //we keep this small to test rotation later
+            .max_index_entries(10)
+            .max_record_bytes(64)
+            .log_dir(temp_dir.path())
+            .build()
+            .unwrap()
+    }
+
+    fn open_segments(log: &Log) -> usize {
+        log.cache.lock().unwrap().len()
     }
 
     #[test]
@@ -587,4 +1395,837 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_scan_range_across_segments() -> LogResult<()> {
+        init_tracing();
+        let temp_dir = TempDir::new().unwrap();
+        let mut log = Log::new(test_config(&temp_dir))?;
+        for i in 0..25 {
+            log.append(format!("Record {i}").as_bytes())?;
+        }
+        assert!(log.segment_count() > 2);
+
+        let offsets = |iter: &mut dyn Iterator<Item = LogResult<(u64, Vec<u8>)>>| {
+            iter.map(|result| {
+                result.map(|(offset, data)| {
+                    assert_eq!(data, format!("Record {offset}").as_bytes());
+                    offset
+                })
+            })
+            .collect::<LogResult<Vec<_>>>()
+        };
+
+        assert_eq!(
+            offsets(&mut log.scan_range(5..23))?,
+            (5..23).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            offsets(&mut log.scan_range(..))?,
+            (0..25).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            offsets(&mut log.scan_range(..=4))?,
+            (0..5).collect::<Vec<_>>()
+        );
+        // the end is clamped to the log, empty ranges yield nothing
+        assert_eq!(
+            offsets(&mut log.scan_range(20..100))?,
+            (20..25).collect::<Vec<_>>()
+        );
+        assert!(offsets(&mut log.scan_range(12..12))?.is_empty());
+        assert!(offsets(&mut log.scan_range(25..))?.is_empty());
+
+        // backwards, crossing segments the other way
+        assert_eq!(
+            offsets(&mut log.scan_range(5..23).rev())?,
+            (5..23).rev().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            offsets(&mut log.scan_rev_from(17))?,
+            (0..=17).rev().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            offsets(&mut log.scan_rev_from(u64::MAX).take(3))?,
+            [24, 23, 22]
+        );
+
+        // both ends meet in the middle without reading a record twice
+        let mut scan = log.scan_range(8..12);
+        assert_eq!(scan.next().unwrap()?.0, 8);
+        assert_eq!(scan.next_back().unwrap()?.0, 11);
+        assert_eq!(scan.next_back().unwrap()?.0, 10);
+        assert_eq!(scan.next().unwrap()?.0, 9);
+        assert!(scan.next().is_none());
+        assert!(scan.next_back().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_walks_the_store_instead_of_the_index() -> LogResult<()> {
+        init_tracing();
+        let temp_dir = TempDir::new().unwrap();
+        let config = test_config(&temp_dir);
+
+        let mut log = Log::new(config.clone())?;
+        for i in 0..25 {
+            log.append(format!("Record {i}").as_bytes())?;
+        }
+        log.close()?;
+
+        // point the index entry of offset 3 nowhere; only lookups through it notice
+        let index_path = temp_dir.path().join(index_file_name(0));
+        let mut index = fs::read(&index_path).unwrap();
+        let entry = 3 * ENTRY_WIDTH as usize;
+        index[entry + 8..entry + 16].copy_from_slice(&u64::MAX.to_le_bytes());
+        fs::write(&index_path, index).unwrap();
+
+        let log = Log::new(config)?;
+        assert!(log.read(3).is_err());
+
+        let scanned: Vec<_> = log.scan_from(1).collect::<LogResult<_>>()?;
+        assert_eq!(scanned.len(), 24);
+        for (offset, data) in scanned {
+            assert_eq!(data, format!("Record {offset}").as_bytes());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_retention_deletes_oldest_segments() -> LogResult<()> {
+        init_tracing();
+        let temp_dir = TempDir::new().unwrap();
+        let config = LogConfig {
+            retention_bytes: Some(300),
+            ..test_config(&temp_dir)
+        };
+        let mut log = Log::new(config)?;
+
+        for i in 0..60 {
+            let data = format!("Record number {i}");
+            log.append(data.as_bytes())?;
+        }
+
+        // only the newest segments survive, the rest are gone from disk as well
+        assert!(log.total_size() <= 300 + 200);
+        assert!(log.base_offset() > 0);
+        assert!(matches!(
+            log.read(0),
+            Err(LogError::OffsetNotFound { offset: 0, .. })
+        ));
+        assert_eq!(log.read(59)?, b"Record number 59");
+
+        // two files per segment, and the manifest only lists what is left
+        let files = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|ext| ext == "log" || ext == "idx")
+            })
+            .count();
+        assert_eq!(files, log.segment_count() * 2);
+        let manifest = Manifest::load(temp_dir.path()).unwrap();
+        assert_eq!(manifest.segments.len(), log.segment_count());
+        assert_eq!(manifest.segments[0].base_offset, log.base_offset());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_os_durability_skips_per_append_sync() -> LogResult<()> {
+        init_tracing();
+        let temp_dir = TempDir::new().unwrap();
+        let config = LogConfig {
+            durability: Durability::Os,
+            ..test_config(&temp_dir)
+        };
+
+        {
+            let mut log = Log::new(config.clone())?;
+            assert!(!log.active_segment().syncs_on_append());
+            for i in 0..15 {
+                log.append(format!("Record {i}").as_bytes())?;
+            }
+            // segments created by rotation keep the mode
+            assert!(log.segment_count() > 1);
+            assert!(!log.active_segment().syncs_on_append());
+
+            // rotation still flushed and sealed the previous segment
+            let (&base_offset, entry) = log.sealed.first_key_value().unwrap();
+            let store_len = fs::metadata(temp_dir.path().join(store_file_name(base_offset)))
+                .unwrap()
+                .len();
+            assert_eq!(store_len, entry.store_bytes);
+            log.close()?;
+        }
+
+        let log = Log::new(config)?;
+        assert_eq!(log.next_offset(), 15);
+        assert_eq!(log.read(14)?, b"Record 14");
+
+        // the default mode syncs every append
+        let temp_dir = TempDir::new().unwrap();
+        let log = Log::new(test_config(&temp_dir))?;
+        assert!(log.active_segment().syncs_on_append());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_builder_validates_limits() {
+        let temp_dir = TempDir::new().unwrap();
+        let invalid = [
+            LogConfig::builder().max_index_entries(0),
+            LogConfig::builder().max_index_entries(u64::MAX),
+            LogConfig::builder().max_record_bytes(0),
+            LogConfig::builder().max_record_bytes(MAX_RECORD_BYTES + 1),
+            LogConfig::builder()
+                .max_store_bytes(1024)
+                .max_record_bytes(1024),
+            LogConfig::builder().retention_bytes(Some(10)),
+        ];
+
+        for builder in invalid {
+            let builder = builder.log_dir(temp_dir.path());
+            assert!(
+                matches!(builder.clone().build(), Err(LogError::InvalidConfig { .. })),
+                "{builder:?} should be rejected"
+            );
+        }
+
+        let config = LogConfig::builder()
+            .max_store_bytes(1024 + LEN_WIDTH)
+            .max_record_bytes(1024)
+            .log_dir(temp_dir.path())
+            .build()
+            .unwrap();
+        assert_eq!(config.max_store_bytes, 1032);
+        assert!(LogConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_new_rejects_invalid_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = LogConfig {
+            max_store_bytes: 0,
+            log_dir: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        let result = Log::new(config);
+        assert!(matches!(
+            result,
+            Err(LogError::InvalidConfig {
+                field: "max_store_bytes",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_append_rejects_oversized_record() -> LogResult<()> {
+        init_tracing();
+        let temp_dir = TempDir::new().unwrap();
+        let mut log = Log::new(test_config(&temp_dir))?;
+
+        let result = log.append(&[0u8; 65]);
+        assert!(matches!(
+            result,
+            Err(LogError::RecordTooLarge {
+                size: 65,
+                max_record_bytes: 64
+            })
+        ));
+        assert_eq!(log.next_offset(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_close_and_reopen() -> LogResult<()> {
+        init_tracing();
+        let temp_dir = TempDir::new().unwrap();
+        let config = test_config(&temp_dir);
+
+        let mut log = Log::new(config.clone())?;
+        for i in 0..15 {
+            log.append(format!("Record {i}").as_bytes())?;
+        }
+        let total_size = log.total_size();
+        log.close()?;
+
+        // files are truncated to their real size, so nothing is left to repair
+        let on_disk: u64 = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "log"))
+            .map(|path| fs::metadata(path).unwrap().len())
+            .sum();
+        assert_eq!(on_disk, total_size);
+
+        let log = Log::new(config)?;
+        assert_eq!(log.next_offset(), 15);
+        assert_eq!(log.read(14)?, b"Record 14");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_append_after_reopen() -> LogResult<()> {
+        init_tracing();
+        let temp_dir = TempDir::new().unwrap();
+        let config = test_config(&temp_dir);
+
+        let mut log = Log::new(config.clone())?;
+        log.append(b"before close")?;
+        log.close()?;
+
+        // the files were truncated on close and have to grow back before they are mapped
+        let mut log = Log::new(config)?;
+        for i in 1..15 {
+            assert_eq!(log.append(format!("Record {i}").as_bytes())?, i);
+        }
+        assert_eq!(log.read(0)?, b"before close");
+        assert_eq!(log.read(14)?, b"Record 14");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_clean_restart_opens_sealed_segments_from_manifest() -> LogResult<()> {
+        init_tracing();
+        let temp_dir = TempDir::new().unwrap();
+        let config = test_config(&temp_dir);
+
+        let mut log = Log::new(config.clone())?;
+        for i in 0..25 {
+            log.append(format!("Record {i}").as_bytes())?;
+        }
+        let segment_count = log.segment_count();
+        log.close()?;
+
+        let manifest = Manifest::load(temp_dir.path()).unwrap();
+        assert_eq!(manifest.segments.len(), segment_count);
+        let (active, sealed) = manifest.segments.split_last().unwrap();
+        assert!(!active.sealed && active.checksum.is_none());
+        assert!(
+            sealed
+                .iter()
+                .all(|entry| entry.sealed && entry.checksum.is_some())
+        );
+
+        let mut log = Log::new(config.clone())?;
+        assert_eq!(log.segment_count(), segment_count);
+        assert_eq!(log.next_offset(), 25);
+        // sealed segments are left closed until they are read
+        assert_eq!(open_segments(&log), 0);
+        assert_eq!(log.read(3)?, b"Record 3");
+        assert_eq!(open_segments(&log), 1);
+        assert_eq!(log.append(b"Record 25")?, 25);
+
+        // truncating back into a sealed segment makes it writable again
+        log.truncate(10)?;
+        assert_eq!(log.segment_count(), 1);
+        assert!(!log.active_segment().is_read_only());
+        assert_eq!(log.append(b"Record 10 again")?, 10);
+        log.close()?;
+
+        let log = Log::new(config)?;
+        assert_eq!(log.next_offset(), 11);
+        assert_eq!(log.read(10)?, b"Record 10 again");
+        Ok(())
+    }
+
+    #[test]
+    fn test_rotation_seals_previous_segment() -> LogResult<()> {
+        init_tracing();
+        let temp_dir = TempDir::new().unwrap();
+        let mut log = Log::new(test_config(&temp_dir))?;
+        for i in 0..15 {
+            log.append(format!("Record {i}").as_bytes())?;
+        }
+        assert_eq!(log.segment_count(), 2);
+
+        let sealed = log.cache.lock().unwrap().get(0).unwrap();
+        assert!(sealed.is_read_only());
+        assert!(!log.active_segment().is_read_only());
+        let store_len = fs::metadata(temp_dir.path().join(store_file_name(0)))
+            .unwrap()
+            .len();
+        let index_len = fs::metadata(temp_dir.path().join(index_file_name(0)))
+            .unwrap()
+            .len();
+        assert_eq!(store_len, sealed.store_size());
+        assert_eq!(index_len, sealed.index_entries() * ENTRY_WIDTH);
+        assert_eq!(log.read(0)?, b"Record 0");
+
+        let manifest = Manifest::load(temp_dir.path()).unwrap();
+        assert_eq!(manifest.sealed(0), log.sealed.get(&0));
+        assert!(manifest.sealed(0).is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_failed_rotation_keeps_active_segment_writable() -> LogResult<()> {
+        init_tracing();
+        let temp_dir = TempDir::new().unwrap();
+        let mut log = Log::new(test_config(&temp_dir))?;
+        log.append(b"Record 0")?;
+
+        // a directory in the way of the next store makes creating the segment fail
+        let blocker = temp_dir.path().join(store_file_name(1));
+        fs::create_dir(&blocker).unwrap();
+        assert!(log.rotate_segment().is_err());
+        fs::remove_dir(&blocker).unwrap();
+
+        assert!(!log.active_segment().is_read_only());
+        assert_eq!(log.append(b"Record 1")?, 1);
+        assert_eq!(log.segment_count(), 1);
+
+        log.rotate_segment()?;
+        assert_eq!(log.segment_count(), 2);
+        assert_eq!(log.read(1)?, b"Record 1");
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_segments_are_bounded() -> LogResult<()> {
+        init_tracing();
+        let temp_dir = TempDir::new().unwrap();
+        let config = LogConfig {
+            max_open_segments: 2,
+            ..test_config(&temp_dir)
+        };
+
+        let mut log = Log::new(config.clone())?;
+        for i in 0..60 {
+            log.append(format!("Record {i}").as_bytes())?;
+        }
+        assert_eq!(log.segment_count(), 6);
+        assert_eq!(open_segments(&log), 2);
+        drop(log);
+
+        let log = Log::new(config)?;
+        assert_eq!(open_segments(&log), 0);
+        for offset in (0..60).rev() {
+            assert_eq!(log.read(offset)?, format!("Record {offset}").as_bytes());
+            assert!(open_segments(&log) <= 2);
+        }
+        let scanned: Vec<_> = log.scan_from(0).collect::<LogResult<_>>()?;
+        assert_eq!(scanned.len(), 60);
+        assert_eq!(open_segments(&log), 2);
+        assert_eq!(
+            log.total_size(),
+            10 * (LEN_WIDTH + 8) + 50 * (LEN_WIDTH + 9)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_stale_manifest_falls_back_to_recovery() -> LogResult<()> {
+        init_tracing();
+        let temp_dir = TempDir::new().unwrap();
+        let config = test_config(&temp_dir);
+
+        let mut log = Log::new(config.clone())?;
+        for i in 0..25 {
+            log.append(format!("Record {i}").as_bytes())?;
+        }
+        log.close()?;
+
+        // a segment whose files no longer match its entry is recovered instead
+        let mut manifest = Manifest::load(temp_dir.path()).unwrap();
+        manifest.segments[0].store_bytes += 1;
+        manifest.write(temp_dir.path())?;
+        let log = Log::new(config.clone())?;
+        assert_eq!(open_segments(&log), 1);
+        assert_eq!(
+            log.sealed[&0].store_bytes + 1,
+            manifest.segments[0].store_bytes
+        );
+        assert_eq!(log.read(0)?, b"Record 0");
+        drop(log);
+
+        // and so is every segment without a manifest
+        fs::write(
+            temp_dir
+                .path()
+                .join(crate::storage::manifest::MANIFEST_FILE_NAME),
+            b"garbage",
+        )
+        .unwrap();
+        let log = Log::new(config)?;
+        assert_eq!(open_segments(&log), 2);
+        assert_eq!(log.next_offset(), 25);
+        // the manifest is rewritten for the next restart
+        assert!(Manifest::load(temp_dir.path()).is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_read_only_reads_live_log() -> LogResult<()> {
+        init_tracing();
+        let temp_dir = TempDir::new().unwrap();
+        let config = test_config(&temp_dir);
+
+        let mut writer = Log::new(config.clone())?;
+        for i in 0..15 {
+            writer.append(format!("Record {i}").as_bytes())?;
+        }
+        let files = |dir: &TempDir| {
+            let mut files: Vec<_> = fs::read_dir(dir.path())
+                .unwrap()
+                .map(|entry| {
+                    let path = entry.unwrap().path();
+                    (path.clone(), fs::read(path).unwrap())
+                })
+                .collect();
+            files.sort();
+            files
+        };
+        let before = files(&temp_dir);
+
+        let mut reader = Log::open_read_only(config.clone())?;
+        let other_reader = Log::open_read_only(config)?;
+        assert!(reader.is_read_only());
+        assert_eq!(reader.segment_count(), writer.segment_count());
+        assert_eq!(reader.next_offset(), 15);
+        assert_eq!(other_reader.read(14)?, b"Record 14");
+        let scanned: Vec<_> = reader.scan_from(0).collect::<LogResult<_>>()?;
+        assert_eq!(scanned.len(), 15);
+
+        let read_only = |result: LogResult<()>| {
+            matches!(
+                result,
+                Err(LogError::Segment(SegmentError::Storage(
+                    StorageError::ReadOnly
+                )))
+            )
+        };
+        assert!(read_only(reader.append(b"nope").map(|_| ())));
+        assert!(read_only(reader.rotate_segment()));
+        assert!(read_only(reader.truncate(0)));
+        reader.close()?;
+        drop(other_reader);
+        assert_eq!(files(&temp_dir), before);
+
+        // the writer is unaffected by its readers
+        assert_eq!(writer.append(b"Record 15")?, 15);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_read_only_never_creates_files() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let log = Log::open_read_only(test_config(&temp_dir)).unwrap();
+        assert!(log.is_empty());
+        assert_eq!(log.segment_count(), 0);
+        assert!(matches!(log.read(0), Err(LogError::OffsetNotFound { .. })));
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+
+        let missing = LogConfig {
+            log_dir: temp_dir.path().join("missing"),
+            ..test_config(&temp_dir)
+        };
+        assert!(matches!(
+            Log::open_read_only(missing),
+            Err(LogError::DirectoryError { .. })
+        ));
+        assert!(!temp_dir.path().join("missing").exists());
+    }
+
+    #[test]
+    fn test_second_writer_is_locked_out() -> LogResult<()> {
+        init_tracing();
+        let temp_dir = TempDir::new().unwrap();
+        let config = test_config(&temp_dir);
+
+        let mut log = Log::new(config.clone())?;
+        log.append(b"only writer")?;
+        assert!(matches!(
+            Log::new(config.clone()),
+            Err(LogError::Locked { .. })
+        ));
+        assert!(matches!(
+            Log::repair(temp_dir.path()),
+            Err(LogError::Locked { .. })
+        ));
+        // readers still see the live log
+        assert_eq!(
+            Log::open_read_only(config.clone())?.read(0)?,
+            b"only writer"
+        );
+        log.close()?;
+
+        let reader = Log::open_read_only(config.clone())?;
+        assert!(matches!(
+            Log::new(config.clone()),
+            Err(LogError::Locked { .. })
+        ));
+        drop(reader);
+
+        assert_eq!(Log::new(config)?.read(0)?, b"only writer");
+        Ok(())
+    }
+
+    #[test]
+    fn test_truncate_after_snapshot_leaves_snapshot_intact() -> LogResult<()> {
+        init_tracing();
+        let temp_dir = TempDir::new().unwrap();
+        let snapshot_dir = temp_dir.path().join("snapshot");
+        let mut log = Log::new(LogConfig {
+            max_store_bytes: 1024,
+            max_index_entries: 100,
+            ..test_config(&temp_dir)
+        })?;
+        // segments 0 and 3, with room left in segment 0
+        for i in 0..5 {
+            if i == 3 {
+                log.rotate_segment()?;
+            }
+            log.append(format!("Record {i}").as_bytes())?;
+        }
+
+        log.snapshot(&snapshot_dir)?;
+        let snapshot_store = snapshot_dir.join(store_file_name(0));
+        let before = fs::read(&snapshot_store).unwrap();
+
+        // segment 0 is written to again, through its own copy of the files
+        log.truncate(3)?;
+        assert_eq!(log.append(b"after-snapshot")?, 3);
+        log.close()?;
+        assert_eq!(fs::read(&snapshot_store).unwrap(), before);
+        assert!(Log::verify(&snapshot_dir)?.is_consistent());
+
+        let restore_dir = TempDir::new().unwrap();
+        let restored = Log::restore(&snapshot_dir, test_config(&restore_dir))?;
+        assert_eq!(restored.next_offset(), 5);
+        for i in 0..5 {
+            assert_eq!(restored.read(i)?, format!("Record {i}").as_bytes());
+        }
+        assert!(Log::verify(restore_dir.path())?.is_consistent());
+        Ok(())
+    }
+
+    #[test]
+    fn test_reader_streams_store_files_and_restores() -> LogResult<()> {
+        init_tracing();
+        let source_dir = TempDir::new().unwrap();
+        let mut source = Log::new(LogConfig {
+            max_open_segments: 1,
+            ..test_config(&source_dir)
+        })?;
+        for i in 0..25 {
+            source.append(format!("Record {i}").as_bytes())?;
+        }
+        assert!(source.segment_count() > 2);
+
+        let mut bytes = Vec::new();
+        source.reader().read_to_end(&mut bytes).unwrap();
+        assert_eq!(bytes.len() as u64, source.total_size());
+        let first_store = fs::read(source_dir.path().join(store_file_name(0))).unwrap();
+        assert!(bytes.starts_with(&first_store));
+
+        let target_dir = TempDir::new().unwrap();
+        let mut target = Log::new(test_config(&target_dir))?;
+        assert_eq!(target.restore_from(bytes.as_slice())?, 25);
+        assert_eq!(target.segment_count(), source.segment_count());
+        for i in 0..25 {
+            assert_eq!(target.read(i)?, format!("Record {i}").as_bytes());
+        }
+
+        // a restored log streams back the same bytes
+        let mut restored = Vec::new();
+        target.reader().read_to_end(&mut restored).unwrap();
+        assert_eq!(restored, bytes);
+
+        assert!(matches!(
+            target.restore_from(bytes.as_slice()),
+            Err(LogError::NotEmpty {
+                next_offset: 25,
+                ..
+            })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_reader_of_read_only_log_skips_preallocated_space() -> LogResult<()> {
+        init_tracing();
+        let temp_dir = TempDir::new().unwrap();
+        let config = LogConfig {
+            max_store_bytes: 1024 * 1024,
+            ..test_config(&temp_dir)
+        };
+
+        // the writer stays open, its active segment keeps its preallocated zeros
+        let mut writer = Log::new(config.clone())?;
+        for i in 0..5 {
+            writer.append(format!("Record {i}").as_bytes())?;
+        }
+        let mut expected = Vec::new();
+        writer.reader().read_to_end(&mut expected).unwrap();
+
+        let reader = Log::open_read_only(config.clone())?;
+        assert_eq!(reader.total_size(), writer.total_size());
+        let mut bytes = Vec::new();
+        io::copy(&mut reader.reader(), &mut bytes).unwrap();
+        assert_eq!(bytes, expected);
+
+        let restore_dir = TempDir::new().unwrap();
+        let mut restored = Log::new(test_config(&restore_dir))?;
+        assert_eq!(restored.restore_from(bytes.as_slice())?, 5);
+
+        let manifest = reader.snapshot(temp_dir.path().join("snapshot"))?;
+        assert_eq!(manifest.segments.len(), 1);
+        assert_eq!(manifest.segments[0].store_bytes, writer.total_size());
+        assert_eq!(manifest.segments[0].next_offset, 5);
+
+        // rotated segments are trimmed, the live one is bounded by its last indexed record
+        for i in 5..25 {
+            writer.append(format!("Record {i}").as_bytes())?;
+        }
+        assert!(writer.segment_count() > 1);
+        let mut expected = Vec::new();
+        writer.reader().read_to_end(&mut expected).unwrap();
+        drop(reader);
+
+        let reader = Log::open_read_only(config)?;
+        let mut bytes = Vec::new();
+        reader.reader().read_to_end(&mut bytes).unwrap();
+        assert_eq!(bytes, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_from_torn_stream() -> LogResult<()> {
+        init_tracing();
+        let temp_dir = TempDir::new().unwrap();
+        let mut log = Log::new(test_config(&temp_dir))?;
+
+        let mut bytes = Vec::new();
+        for data in [&b"first"[..], b"second"] {
+            bytes.extend_from_slice(&(data.len() as u64).to_le_bytes());
+            bytes.extend_from_slice(data);
+        }
+        bytes.truncate(bytes.len() - 2);
+
+        assert!(matches!(
+            log.restore_from(bytes.as_slice()),
+            Err(LogError::RestoreFailed { position: 13, .. })
+        ));
+        assert_eq!(log.next_offset(), 1);
+        assert_eq!(log.read(0)?, b"first");
+
+        let temp_dir = TempDir::new().unwrap();
+        let mut log = Log::new(test_config(&temp_dir))?;
+        assert_eq!(log.restore_from(&[][..])?, 0);
+        assert!(matches!(
+            log.restore_from(&1000u64.to_le_bytes()[..]),
+            Err(LogError::RecordTooLarge { size: 1000, .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_snapshot_and_restore() -> LogResult<()> {
+        init_tracing();
+        let temp_dir = TempDir::new().unwrap();
+        let snapshot_dir = temp_dir.path().join("snapshot");
+        let mut log = Log::new(test_config(&temp_dir))?;
+        for i in 0..25 {
+            log.append(format!("Record {i}").as_bytes())?;
+        }
+        assert!(log.segment_count() > 2);
+
+        let manifest = log.snapshot(&snapshot_dir)?;
+        assert_eq!(manifest.segments.len(), log.segment_count());
+        assert!(manifest.segments.iter().all(|entry| entry.sealed));
+        assert_eq!(manifest.segments.last().unwrap().next_offset, 25);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            let inode = |dir: &Path| fs::metadata(dir.join(store_file_name(0))).unwrap().ino();
+            assert_eq!(inode(temp_dir.path()), inode(&snapshot_dir));
+        }
+
+        // writes after the snapshot do not reach it
+        for i in 25..30 {
+            log.append(format!("Record {i}").as_bytes())?;
+        }
+        assert!(Log::verify(&snapshot_dir)?.is_consistent());
+        assert!(matches!(
+            log.snapshot(&snapshot_dir),
+            Err(LogError::SnapshotFailed { .. })
+        ));
+
+        let restore_dir = TempDir::new().unwrap();
+        let mut restored = Log::restore(&snapshot_dir, test_config(&restore_dir))?;
+        assert_eq!(restored.next_offset(), 25);
+        assert_eq!(restored.segment_count(), manifest.segments.len());
+        for i in 0..25 {
+            assert_eq!(restored.read(i)?, format!("Record {i}").as_bytes());
+        }
+        assert_eq!(restored.append(b"after restore")?, 25);
+        restored.close()?;
+
+        // the restored log reopens cleanly and left the snapshot untouched
+        assert_eq!(
+            Log::new(test_config(&restore_dir))?.read(25)?,
+            b"after restore"
+        );
+        assert!(Log::verify(&snapshot_dir)?.is_consistent());
+        assert_eq!(Manifest::load(&snapshot_dir), Some(manifest));
+
+        assert!(matches!(
+            Log::restore(&snapshot_dir, test_config(&restore_dir)),
+            Err(LogError::NotEmpty {
+                next_offset: 26,
+                ..
+            })
+        ));
+        assert!(matches!(
+            Log::restore(temp_dir.path().join("missing"), test_config(&restore_dir)),
+            Err(LogError::SnapshotFailed { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_failed_restore_leaves_directory_empty() -> LogResult<()> {
+        init_tracing();
+        let temp_dir = TempDir::new().unwrap();
+        let snapshot_dir = temp_dir.path().join("snapshot");
+        let mut log = Log::new(test_config(&temp_dir))?;
+        for i in 0..25 {
+            log.append(format!("Record {i}").as_bytes())?;
+        }
+        let manifest = log.snapshot(&snapshot_dir)?;
+
+        // the copy fails after the first segments are in place
+        let last = manifest.segments.last().unwrap().base_offset;
+        let index_path = snapshot_dir.join(index_file_name(last));
+        let index = fs::read(&index_path).unwrap();
+        fs::remove_file(&index_path).unwrap();
+
+        let restore_dir = TempDir::new().unwrap();
+        assert!(matches!(
+            Log::restore(&snapshot_dir, test_config(&restore_dir)),
+            Err(LogError::SnapshotFailed { .. })
+        ));
+        let left: Vec<_> = fs::read_dir(restore_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .filter(|name| name != LOCK_FILE_NAME)
+            .collect();
+        assert!(left.is_empty(), "{left:?}");
+
+        fs::write(&index_path, index).unwrap();
+        let restored = Log::restore(&snapshot_dir, test_config(&restore_dir))?;
+        assert_eq!(restored.next_offset(), 25);
+        Ok(())
+    }
 }
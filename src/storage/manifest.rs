@@ -0,0 +1,174 @@
+//! Manifest describing the segments of a log directory, so a clean restart does not have to
+//! scan every store and index.
+//!
+//! The manifest lists every segment with its offsets and sizes. Segments that have been
+//! rotated out are sealed: they never change again, and their entry carries a CRC32 of the
+//! store and index contents. On startup a sealed segment whose files still have the recorded
+//! sizes is opened read-only as described, without running recovery; anything else (the
+//! active segment, files left preallocated by a crash, segments missing from the manifest)
+//! goes through the full `Segment::new` path. The manifest is only ever a hint: losing or
+//! corrupting it costs a slow start, not data.
+//!
+//! It is written to a temporary file and renamed over the old one, so readers never see a
+//! partial manifest.
+use crate::LogResult;
+use crate::errors::LogError;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+use tracing::{debug, warn};
+
+/// Name of the manifest file inside a log directory
+pub const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+const MANIFEST_VERSION: u32 = 1;
+
+/// Description of one segment
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SegmentEntry {
+    pub base_offset: u64,
+    pub next_offset: u64,
+    /// Sealed segments are never written again
+    pub sealed: bool,
+    pub store_bytes: u64,
+    pub index_entries: u64,
+    /// CRC32 of the store followed by the index, for sealed segments only
+    pub checksum: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    version: u32,
+    pub segments: Vec<SegmentEntry>,
+}
+
+impl Manifest {
+    pub fn new(segments: Vec<SegmentEntry>) -> Self {
+        Self {
+            version: MANIFEST_VERSION,
+            segments,
+        }
+    }
+
+    /// Reads the manifest of `dir`. A missing, unreadable or unknown-version manifest is
+    /// logged and treated as absent, since every segment can be recovered without it.
+    pub fn load(dir: &Path) -> Option<Self> {
+        let path = dir.join(MANIFEST_FILE_NAME);
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return None,
+            Err(e) => {
+                warn!(path = ?path, error = %e, "Failed to read manifest, ignoring it");
+                return None;
+            }
+        };
+
+        match serde_json::from_slice::<Manifest>(&bytes) {
+            Ok(manifest) if manifest.version == MANIFEST_VERSION => Some(manifest),
+            Ok(manifest) => {
+                warn!(
+                    version = manifest.version,
+                    "Unknown manifest version, ignoring it"
+                );
+                None
+            }
+            Err(e) => {
+                warn!(path = ?path, error = %e, "Corrupted manifest, ignoring it");
+                None
+            }
+        }
+    }
+
+    /// Atomically replaces the manifest of `dir`
+    pub fn write(&self, dir: &Path) -> LogResult<()> {
+        let path = dir.join(MANIFEST_FILE_NAME);
+        let tmp_path = dir.join(format!("{MANIFEST_FILE_NAME}.tmp"));
+        let manifest_error = |source| LogError::ManifestError {
+            path: path.to_string_lossy().to_string(),
+            source,
+        };
+
+        let bytes = serde_json::to_vec(self)
+            .map_err(io::Error::from)
+            .map_err(manifest_error)?;
+        let mut file = File::create(&tmp_path).map_err(manifest_error)?;
+        file.write_all(&bytes).map_err(manifest_error)?;
+        file.sync_all().map_err(manifest_error)?;
+        fs::rename(&tmp_path, &path).map_err(manifest_error)?;
+        // make the rename itself durable
+        File::open(dir)
+            .and_then(|dir| dir.sync_all())
+            .map_err(manifest_error)?;
+
+        debug!(segments = self.segments.len(), "Manifest written");
+        Ok(())
+    }
+
+    /// Returns the entry of a sealed segment
+    pub fn sealed(&self, base_offset: u64) -> Option<&SegmentEntry> {
+        self.segments
+            .iter()
+            .find(|entry| entry.sealed && entry.base_offset == base_offset)
+    }
+}
+
+/// Checksum recorded for a sealed segment
+pub fn checksum(store: &[u8], index: &[u8]) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(store);
+    hasher.update(index);
+    hasher.finalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn entry(base_offset: u64, sealed: bool) -> SegmentEntry {
+        SegmentEntry {
+            base_offset,
+            next_offset: base_offset + 10,
+            sealed,
+            store_bytes: 200,
+            index_entries: 10,
+            checksum: sealed.then_some(42),
+        }
+    }
+
+    #[test]
+    fn test_write_and_load() -> LogResult<()> {
+        let dir = TempDir::new().unwrap();
+        assert_eq!(Manifest::load(dir.path()), None);
+
+        let manifest = Manifest::new(vec![entry(0, true), entry(10, false)]);
+        manifest.write(dir.path())?;
+        let loaded = Manifest::load(dir.path()).unwrap();
+        assert_eq!(loaded, manifest);
+        assert_eq!(loaded.sealed(0), Some(&entry(0, true)));
+        assert_eq!(loaded.sealed(10), None);
+
+        // no temporary file is left behind
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_corrupted_manifest_is_ignored() {
+        let dir = TempDir::new().unwrap();
+        fs::write(
+            dir.path().join(MANIFEST_FILE_NAME),
+            b"{\"version\":1,\"segm",
+        )
+        .unwrap();
+        assert_eq!(Manifest::load(dir.path()), None);
+
+        fs::write(
+            dir.path().join(MANIFEST_FILE_NAME),
+            b"{\"version\":99,\"segments\":[]}",
+        )
+        .unwrap();
+        assert_eq!(Manifest::load(dir.path()), None);
+    }
+}
@@ -1,12 +1,60 @@
 use crate::errors::IndexError;
 use crate::errors::StorageError;
 use crate::{IndexResult, StorageResult};
+use memmap2::{Mmap, MmapMut};
 use std::io;
+use std::ops::Deref;
+pub(crate) mod cache;
 pub mod index;
+pub mod inspect;
+pub mod lock;
 pub mod log;
+pub mod manifest;
 pub mod segment;
+pub(crate) mod snapshot;
 pub mod store;
 pub mod traits;
+pub mod verify;
+
+/// Memory map of a store or index file. Files opened read-only get a read-only map,
+/// so writes through it are impossible rather than merely forbidden.
+pub(crate) enum FileMap {
+    Writable(MmapMut),
+    ReadOnly(Mmap),
+}
+
+impl FileMap {
+    /// Returns the map for writing, or `None` if the file was opened read-only
+    pub(crate) fn writable(&mut self) -> Option<&mut MmapMut> {
+        match self {
+            FileMap::Writable(mmap) => Some(mmap),
+            FileMap::ReadOnly(_) => None,
+        }
+    }
+
+    pub(crate) fn is_read_only(&self) -> bool {
+        matches!(self, FileMap::ReadOnly(_))
+    }
+
+    /// Flushes outstanding writes; a read-only map has none
+    pub(crate) fn flush(&self) -> io::Result<()> {
+        match self {
+            FileMap::Writable(mmap) => mmap.flush(),
+            FileMap::ReadOnly(_) => Ok(()),
+        }
+    }
+}
+
+impl Deref for FileMap {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            FileMap::Writable(mmap) => mmap,
+            FileMap::ReadOnly(mmap) => mmap,
+        }
+    }
+}
 
 pub trait StorageContext<T> {
     fn with_open_context(self, path: &str) -> StorageResult<T>;
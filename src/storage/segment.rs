@@ -1,11 +1,12 @@
 //! Segment combines the Store and Index to provide a logical log segment
 //! Each segment handles a contiguous range of offsets and manages the coordination between storing data and indexing it.
 use crate::SegmentResult;
-use crate::errors::SegmentError;
+use crate::errors::{SegmentError, StorageError};
 use crate::storage::index::Index;
+use crate::storage::manifest::{self, SegmentEntry};
 use crate::storage::store::Store;
 use std::path::Path;
-use tracing::{debug, info, instrument};
+use tracing::{debug, info, instrument, warn};
 
 pub struct Segment {
     store: Store,
@@ -30,20 +31,7 @@ impl Segment {
         let store = Store::new(store_path)?;
         let index = Index::new(index_path)?;
 
-        // determine next offset based on existing index entries
-        let next_offset = if index.is_empty() {
-            base_offset
-        } else {
-            // find the highest offset in the index + 1
-            let mut highest_offset = base_offset;
-            for i in 0..index.len() {
-                let offset = index.read_offset_at_index(i)?;
-                if offset > highest_offset {
-                    highest_offset = offset;
-                }
-            }
-            highest_offset + 1
-        };
+        let next_offset = Self::next_offset_from_index(&index, base_offset)?;
         info!(
             base_offset,
             next_offset,
@@ -62,9 +50,119 @@ impl Segment {
         })
     }
 
+    /// Opens an existing segment without modifying its files, see `Store::open_read_only`
+    /// and `Index::open_read_only`. Appends fail with `StorageError::ReadOnly`.
+    ///
+    /// The store's size ends with the last indexed record, so the space a writer still
+    /// holding the segment has preallocated is not counted.
+    #[instrument(skip_all, fields(base_offset))]
+    pub fn open_read_only(
+        store_path: impl AsRef<Path>,
+        index_path: impl AsRef<Path>,
+        base_offset: u64,
+        max_store_bytes: u64,
+        max_index_entries: u64,
+    ) -> SegmentResult<Self> {
+        debug!(base_offset, "Opening segment read-only");
+
+        let mut store = Store::open_read_only(store_path)?;
+        let index = Index::open_read_only(index_path, base_offset)?;
+
+        // a live writer's preallocated zeros read as empty records, only what the index
+        // reaches has been written
+        if let Some(last) = index.len().checked_sub(1) {
+            let position = index.read_position_at_index(last)?;
+            match store.read(position) {
+                Ok((_, len)) => store.clamp_size(position + len),
+                Err(e) => warn!(position, error = %e, "Last indexed record is unreadable"),
+            }
+        } else {
+            store.clamp_size(0);
+        }
+
+        let next_offset = Self::next_offset_from_index(&index, base_offset)?;
+        info!(
+            base_offset,
+            next_offset,
+            index_entries = index.len(),
+            "Segment opened read-only"
+        );
+
+        Ok(Segment {
+            store,
+            index,
+            base_offset,
+            next_offset,
+            max_store_bytes,
+            max_index_entries,
+        })
+    }
+
+    /// Opens a sealed segment as described by its manifest entry, read-only and without
+    /// recovery or scanning its index
+    #[instrument(skip_all, fields(base_offset = entry.base_offset))]
+    pub fn open_sealed(
+        store_path: impl AsRef<Path>,
+        index_path: impl AsRef<Path>,
+        entry: &SegmentEntry,
+        max_store_bytes: u64,
+        max_index_entries: u64,
+    ) -> SegmentResult<Self> {
+        let store = Store::open_sealed(store_path, entry.store_bytes)?;
+        let index = Index::open_sealed(index_path, entry.index_entries)?;
+
+        debug!(
+            base_offset = entry.base_offset,
+            next_offset = entry.next_offset,
+            "Sealed segment opened"
+        );
+
+        Ok(Segment {
+            store,
+            index,
+            base_offset: entry.base_offset,
+            next_offset: entry.next_offset,
+            max_store_bytes,
+            max_index_entries,
+        })
+    }
+
+    /// Describes the segment for the manifest. Only sealed entries carry a checksum,
+    /// an active segment keeps changing.
+    pub fn manifest_entry(&self, sealed: bool) -> SegmentEntry {
+        SegmentEntry {
+            base_offset: self.base_offset,
+            next_offset: self.next_offset,
+            sealed,
+            store_bytes: self.store.size(),
+            index_entries: self.index.len(),
+            checksum: sealed.then(|| manifest::checksum(self.store.data(), self.index.data())),
+        }
+    }
+
+    /// Determines the next offset from the highest offset in the index
+    fn next_offset_from_index(index: &Index, base_offset: u64) -> SegmentResult<u64> {
+        if index.is_empty() {
+            return Ok(base_offset);
+        }
+
+        let mut highest_offset = base_offset;
+        for i in 0..index.len() {
+            let offset = index.read_offset_at_index(i)?;
+            if offset > highest_offset {
+                highest_offset = offset;
+            }
+        }
+        Ok(highest_offset + 1)
+    }
+
     /// Appends data to the segment and returns the assigned offset
     #[instrument(skip(self, data), fields(data_len = data.len()))]
     pub fn append(&mut self, data: &[u8]) -> SegmentResult<u64> {
+        if self.is_read_only() {
+            return Err(StorageError::ReadOnly.into());
+        }
+
         if self.is_full() {
             return Err(SegmentError::SegmentFull {
                 base_offset: self.base_offset,
@@ -104,15 +202,7 @@ impl Segment {
             "Reading from segment"
         );
 
-        if offset < self.base_offset || offset >= self.next_offset {
-            return Err(SegmentError::OffsetOutOfRange {
-                offset,
-                base_offset: self.base_offset,
-                next_offset: self.next_offset,
-            });
-        }
-
-        let position = self.index.read(offset)?;
+        let position = self.position(offset)?;
 
         //read the data from the store
         let (data, _) = self.store.read(position)?;
@@ -126,6 +216,76 @@ impl Segment {
         Ok(data)
     }
 
+    /// Looks up the store position of the record at `offset` in the index
+    pub fn position(&self, offset: u64) -> SegmentResult<u64> {
+        if !self.contains_offset(offset) {
+            return Err(SegmentError::OffsetOutOfRange {
+                offset,
+                base_offset: self.base_offset,
+                next_offset: self.next_offset,
+            });
+        }
+
+        Ok(self.index.read(offset)?)
+    }
+
+    /// Reads the record starting at `position` in the store, without going through the
+    /// index. Returns the data and the position of the next record, which holds the next
+    /// offset since records are stored in offset order.
+    pub fn read_at(&self, position: u64) -> SegmentResult<(Vec<u8>, u64)> {
+        let (data, bytes_read) = self.store.read(position)?;
+        Ok((data, position + bytes_read))
+    }
+
+    /// Controls whether appends are flushed to disk immediately (the default)
+    pub fn set_sync_on_append(&mut self, sync_on_append: bool) {
+        self.store.set_sync_on_append(sync_on_append);
+        self.index.set_sync_on_write(sync_on_append);
+    }
+
+    /// Returns true if appends are flushed to disk immediately
+    #[cfg(test)]
+    pub(crate) fn syncs_on_append(&self) -> bool {
+        self.store.syncs_on_append()
+    }
+
+    /// Flushes the store and index to disk
+    #[instrument(skip(self), fields(base_offset = self.base_offset))]
+    pub fn flush(&mut self) -> SegmentResult<()> {
+        self.store.flush()?;
+        self.index.flush()?;
+        Ok(())
+    }
+
+    /// Seals the segment after rotation: both files are flushed, trimmed to their contents
+    /// and remapped read-only, so appends fail with `StorageError::ReadOnly` from then on.
+    /// Returns the manifest entry of the sealed segment.
+    #[instrument(skip(self), fields(base_offset = self.base_offset))]
+    pub fn seal(&mut self) -> SegmentResult<SegmentEntry> {
+        self.store.seal()?;
+        self.index.seal()?;
+        info!(
+            base_offset = self.base_offset,
+            next_offset = self.next_offset,
+            store_size = self.store.size(),
+            "Segment sealed"
+        );
+        Ok(self.manifest_entry(true))
+    }
+
+    /// Flushes and closes the store and index, reporting the first failure
+    #[instrument(skip(self), fields(base_offset = self.base_offset))]
+    pub fn close(self) -> SegmentResult<()> {
+        let Segment { store, index, .. } = self;
+
+        // close both files even if the first one fails
+        let store_result = store.close();
+        let index_result = index.close();
+        store_result?;
+        index_result?;
+        Ok(())
+    }
+
     /// Returns the base offset (first offset) of this segment
     pub fn base_offset(&self) -> u64 {
         self.base_offset
@@ -151,6 +311,24 @@ impl Segment {
         self.store.size()
     }
 
+    pub(crate) fn store(&self) -> &Store {
+        &self.store
+    }
+
+    pub(crate) fn index(&self) -> &Index {
+        &self.index
+    }
+
+    /// Returns the raw store bytes, length prefixes included
+    pub(crate) fn store_data(&self) -> &[u8] {
+        self.store.data()
+    }
+
+    /// Returns the raw index entries
+    pub(crate) fn index_data(&self) -> &[u8] {
+        self.index.data()
+    }
+
     /// Returns the number of entries in the index
     pub fn index_entries(&self) -> u64 {
         self.index.len()
@@ -160,6 +338,11 @@ impl Segment {
     pub fn is_empty(&self) -> bool {
         self.index.is_empty()
     }
+
+    /// Returns true if the segment was opened with `open_read_only` or `open_sealed`
+    pub fn is_read_only(&self) -> bool {
+        self.store.is_read_only()
+    }
 }
 
 #[cfg(test)]
@@ -345,4 +528,98 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_seal_trims_files_and_rejects_appends() -> SegmentResult<()> {
+        init_tracing();
+        let temp_dir = TempDir::new().unwrap();
+        let store_path = temp_dir.path().join("segment.log");
+        let index_path = temp_dir.path().join("segment.idx");
+
+        let mut segment = Segment::new(&store_path, &index_path, 10, 1024 * 1024, 1000)?;
+        for i in 0..3 {
+            segment.append(format!("record{i}").as_bytes())?;
+        }
+        let entry = segment.seal()?;
+
+        // the preallocated space is gone from both files
+        assert!(segment.is_read_only());
+        assert_eq!(std::fs::metadata(&store_path).unwrap().len(), 3 * 15);
+        assert_eq!(
+            std::fs::metadata(&index_path).unwrap().len(),
+            3 * crate::storage::index::ENTRY_WIDTH
+        );
+        assert!(matches!(
+            segment.append(b"too late"),
+            Err(SegmentError::Storage(StorageError::ReadOnly))
+        ));
+        assert_eq!(segment.read(12)?, b"record2");
+
+        assert!(entry.sealed);
+        assert_eq!(entry.next_offset, 13);
+        assert_eq!(entry.store_bytes, 45);
+        assert_eq!(entry.index_entries, 3);
+        assert!(entry.checksum.is_some());
+
+        // dropping a sealed segment leaves its files alone
+        drop(segment);
+        let segment = Segment::open_sealed(&store_path, &index_path, &entry, 1024 * 1024, 1000)?;
+        assert_eq!(segment.manifest_entry(true), entry);
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_at_walks_records_in_offset_order() -> SegmentResult<()> {
+        init_tracing();
+        let temp_dir = TempDir::new().unwrap();
+        let store_path = temp_dir.path().join("segment.log");
+        let index_path = temp_dir.path().join("segment.idx");
+
+        let mut segment = Segment::new(&store_path, &index_path, 5, 1024 * 1024, 1000)?;
+        let records = ["first", "second record", "third"];
+        for record in records {
+            segment.append(record.as_bytes())?;
+        }
+
+        let mut position = segment.position(5)?;
+        for record in records {
+            let (data, next_position) = segment.read_at(position)?;
+            assert_eq!(data, record.as_bytes());
+            position = next_position;
+        }
+        assert_eq!(position, segment.store_size());
+        assert_eq!(segment.position(6)?, 8 + 5);
+        assert!(matches!(
+            segment.position(8),
+            Err(SegmentError::OffsetOutOfRange { offset: 8, .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_only_ignores_preallocated_space() -> SegmentResult<()> {
+        init_tracing();
+        let temp_dir = TempDir::new().unwrap();
+        let store_path = temp_dir.path().join("segment.log");
+        let index_path = temp_dir.path().join("segment.idx");
+
+        // the writer stays open, so its files keep their preallocated zeros. A segment based
+        // at 0 could not tell an unused first index slot from a first empty record.
+        let mut writer = Segment::new(&store_path, &index_path, 10, 1024 * 1024, 1000)?;
+        let reader = Segment::open_read_only(&store_path, &index_path, 10, 1024 * 1024, 1000)?;
+        assert_eq!(reader.store_size(), 0);
+        assert!(reader.store_data().is_empty());
+
+        for i in 0..5 {
+            writer.append(format!("record{i}").as_bytes())?;
+        }
+        assert!(std::fs::metadata(&store_path).unwrap().len() > writer.store_size());
+
+        let reader = Segment::open_read_only(&store_path, &index_path, 10, 1024 * 1024, 1000)?;
+        assert_eq!(reader.store_size(), writer.store_size());
+        assert_eq!(reader.store_data(), writer.store_data());
+        assert_eq!(reader.manifest_entry(false), writer.manifest_entry(false));
+        assert_eq!(reader.read(14)?, b"record4");
+        Ok(())
+    }
 }
@@ -0,0 +1,118 @@
+//! Point-in-time copies of a log directory, see [`Log::snapshot`](crate::storage::log::Log::snapshot)
+//! and [`Log::restore`](crate::storage::log::Log::restore).
+//!
+//! A snapshot is itself a log directory: one store and index per segment plus a manifest in
+//! which every segment is sealed and checksummed, so it can be opened read-only, checked
+//! with `Log::verify` or restored like any other log.
+//!
+//! A sealed segment is not written while it stays sealed, so snapshotting it costs a hard
+//! link per file (a copy when the snapshot lives on another filesystem). Only the written
+//! part of the active segment is copied. The log does reopen sealed segments for writing,
+//! when truncation makes one active again or recovery rebuilds one the manifest lost track
+//! of; [`unshare_segment`] first gives it files of its own, so those writes never reach a
+//! snapshot. Restoring always copies, for the same reason.
+use crate::LogResult;
+use crate::errors::LogError;
+use crate::storage::inspect::{index_file_name, store_file_name};
+use crate::storage::manifest::SegmentEntry;
+use crate::storage::segment::Segment;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+use tracing::debug;
+
+/// Hard links the files of a sealed segment into `dest_dir`, copying them if linking fails
+pub(crate) fn link_segment(src_dir: &Path, dest_dir: &Path, base_offset: u64) -> LogResult<()> {
+    for name in [store_file_name(base_offset), index_file_name(base_offset)] {
+        let (src, dest) = (src_dir.join(&name), dest_dir.join(&name));
+        if let Err(e) = fs::hard_link(&src, &dest) {
+            debug!(file = %name, error = %e, "Failed to hard link segment file, copying it");
+            copy_file(&src, &dest)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes the records and index entries `segment` holds right now into `dest_dir`,
+/// returning its entry for the snapshot manifest
+pub(crate) fn write_segment(dest_dir: &Path, segment: &Segment) -> LogResult<SegmentEntry> {
+    // describe the segment from the same bytes that get written
+    let entry = segment.manifest_entry(true);
+    write_file(
+        &dest_dir.join(store_file_name(entry.base_offset)),
+        segment.store_data(),
+    )?;
+    write_file(
+        &dest_dir.join(index_file_name(entry.base_offset)),
+        segment.index_data(),
+    )?;
+    Ok(entry)
+}
+
+/// Replaces segment files that are hard linked elsewhere, e.g. into a snapshot, with
+/// private copies, so opening the segment for writing cannot change the other links.
+/// Missing files are left for the segment to create.
+pub(crate) fn unshare_segment(dir: &Path, base_offset: u64) -> LogResult<()> {
+    for name in [store_file_name(base_offset), index_file_name(base_offset)] {
+        let path = dir.join(&name);
+        let shared = match fs::metadata(&path) {
+            Ok(metadata) => is_shared(&metadata),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(snapshot_error(&path)(e)),
+        };
+        if !shared {
+            continue;
+        }
+
+        let tmp_path = dir.join(format!("{name}.tmp"));
+        copy_file(&path, &tmp_path)?;
+        fs::rename(&tmp_path, &path)
+            .and_then(|_| File::open(dir)?.sync_all())
+            .map_err(snapshot_error(&path))?;
+        debug!(file = %name, "Copied hard linked segment file before writing to it");
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn is_shared(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    metadata.nlink() > 1
+}
+
+// without a link count every file may be linked into a snapshot
+#[cfg(not(unix))]
+fn is_shared(_metadata: &fs::Metadata) -> bool {
+    true
+}
+
+/// Copies the files of a snapshot segment into the log directory being restored
+pub(crate) fn copy_segment(src_dir: &Path, dest_dir: &Path, base_offset: u64) -> LogResult<()> {
+    for name in [store_file_name(base_offset), index_file_name(base_offset)] {
+        copy_file(&src_dir.join(&name), &dest_dir.join(&name))?;
+    }
+    Ok(())
+}
+
+/// Maps an I/O failure on `path` to `LogError::SnapshotFailed`
+pub(crate) fn snapshot_error(path: &Path) -> impl Fn(io::Error) -> LogError + '_ {
+    move |source| LogError::SnapshotFailed {
+        path: path.to_string_lossy().to_string(),
+        source,
+    }
+}
+
+fn copy_file(src: &Path, dest: &Path) -> LogResult<()> {
+    fs::copy(src, dest)
+        .and_then(|_| File::open(dest)?.sync_all())
+        .map_err(snapshot_error(dest))
+}
+
+fn write_file(path: &Path, bytes: &[u8]) -> LogResult<()> {
+    File::create_new(path)
+        .and_then(|mut file| {
+            file.write_all(bytes)?;
+            file.sync_all()
+        })
+        .map_err(snapshot_error(path))
+}
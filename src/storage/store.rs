@@ -1,13 +1,17 @@
 use crate::StorageResult;
 use crate::errors::StorageError;
-use crate::storage::StorageContext;
-use memmap2::{MmapMut, MmapOptions};
+use crate::storage::{FileMap, StorageContext};
+use crate::telemetry::metrics;
+use memmap2::{Mmap, MmapOptions};
 use std::fs::{File, OpenOptions};
 use std::path::Path;
 use tracing::{debug, info, instrument, warn};
 
-// the length of each record is stored as u64 (8 bytes) before each record
-const LEN_WIDTH: u64 = 8;
+/// The length of each record is stored as u64 (8 bytes) before each record
+pub const LEN_WIDTH: u64 = 8;
+
+/// Largest record length recovery accepts; longer length prefixes are treated as corruption
+pub const MAX_RECORD_BYTES: u64 = 100 * 1024 * 1024;
 
 /// Store represents an append-only file that holds the actual log records.
 /// Each record is prefixed with its lengnth for efficiency.
@@ -15,8 +19,10 @@ const LEN_WIDTH: u64 = 8;
 /// Format: [8-byte length][record data][8-byte length][record data]
 pub struct Store {
     file: File,
-    mmap: MmapMut,
+    mmap: FileMap,
     size: u64,
+    sync_on_append: bool,
+    closed: bool,
 }
 
 impl Store {
@@ -66,8 +72,14 @@ impl Store {
                 .with_grow_context(file_len, new_size)?;
             new_size
         } else {
-            // Existing file - use current size, it's already been truncated to the right size
-            std::cmp::max(actual_data_size, 1024 * 1024)
+            // Existing file - it was truncated to its data on close, so grow it back to the
+            // mapped size; writing past the end of the file would fault
+            let map_size = std::cmp::max(actual_data_size, 1024 * 1024);
+            if actual_data_size < map_size {
+                file.set_len(map_size)
+                    .with_grow_context(actual_data_size, map_size)?;
+            }
+            map_size
         };
 
         let mmap = unsafe {
@@ -85,11 +97,88 @@ impl Store {
 
         Ok(Store {
             file,
-            mmap,
+            mmap: FileMap::Writable(mmap),
             size: actual_data_size,
+            sync_on_append: true,
+            closed: false,
+        })
+    }
+
+    /// Opens an existing store without ever modifying it: the file is mapped read-only,
+    /// torn records at the end are left in place and appends fail with `StorageError::ReadOnly`.
+    ///
+    /// The store's size is fixed when it is opened. The file may still be written by another
+    /// process, whose preallocated zeros are indistinguishable from empty records and are
+    /// counted in the size until `clamp_size` cuts them off; `Segment::open_read_only`
+    /// does so at the end of the last indexed record.
+    #[instrument(skip_all, fields(path = ?path.as_ref()))]
+    pub fn open_read_only(path: impl AsRef<Path>) -> StorageResult<Self> {
+        debug!("Opening store file read-only");
+
+        let path_str = path.as_ref().to_string_lossy();
+        let file = File::open(path.as_ref()).with_open_context(&path_str)?;
+        let file_len = file.metadata().with_open_context(&path_str)?.len();
+
+        let mmap = unsafe { Mmap::map(&file).with_mmap_context(file_len)? };
+        let size = Self::valid_len(&mmap)?;
+
+        info!(file_len, size, "Store opened read-only");
+
+        Ok(Store {
+            file,
+            mmap: FileMap::ReadOnly(mmap),
+            size,
+            sync_on_append: false,
+            closed: false,
+        })
+    }
+
+    /// Opens the store of a sealed segment, known to hold exactly `size` bytes of complete
+    /// records. Like `open_read_only` nothing is modified, and the records are not scanned.
+    #[instrument(skip_all, fields(path = ?path.as_ref(), size))]
+    pub fn open_sealed(path: impl AsRef<Path>, size: u64) -> StorageResult<Self> {
+        let path_str = path.as_ref().to_string_lossy();
+        let file = File::open(path.as_ref()).with_open_context(&path_str)?;
+        let file_len = file.metadata().with_open_context(&path_str)?.len();
+        if file_len < size {
+            return Err(StorageError::CorruptedRecord {
+                position: file_len,
+                reason: format!("sealed store is shorter than its recorded {size} bytes"),
+            });
+        }
+
+        let mmap = unsafe { Mmap::map(&file).with_mmap_context(file_len)? };
+        debug!(size, "Sealed store opened");
+
+        Ok(Store {
+            file,
+            mmap: FileMap::ReadOnly(mmap),
+            size,
+            sync_on_append: false,
+            closed: false,
         })
     }
 
+    /// Returns true if the store was opened with `open_read_only` or `open_sealed`
+    pub fn is_read_only(&self) -> bool {
+        self.mmap.is_read_only()
+    }
+
+    /// Shrinks the size of a read-only store to at most `size` bytes, leaving the file alone
+    pub(crate) fn clamp_size(&mut self, size: u64) {
+        self.size = self.size.min(size);
+    }
+
+    /// Returns the whole mapped file, including anything past `size`
+    pub(crate) fn file_data(&self) -> &[u8] {
+        &self.mmap
+    }
+
+    /// Returns the written records, length prefixes included
+    pub(crate) fn data(&self) -> &[u8] {
+        &self.mmap[..self.size as usize]
+    }
+
     /// Appends a record to the store and returns its position and number of bytes written.
     ///
     /// Returns: (position_where_record_starts, total_bytes_written)
@@ -97,6 +186,10 @@ impl Store {
     pub fn append(&mut self, data: &[u8]) -> StorageResult<(u64, u64)> {
         debug!("Appending record to the store");
 
+        if self.is_read_only() {
+            return Err(StorageError::ReadOnly);
+        }
+
         let record_len = data.len() as u64;
         let total_len = LEN_WIDTH + record_len;
 
@@ -112,18 +205,21 @@ impl Store {
         }
 
         let pos = self.size;
+        let mmap = self.mmap.writable().ok_or(StorageError::ReadOnly)?;
 
         // Write length prefix
         let len_bytes = record_len.to_le_bytes();
-        self.mmap[self.size as usize..(self.size + LEN_WIDTH) as usize].copy_from_slice(&len_bytes);
+        mmap[self.size as usize..(self.size + LEN_WIDTH) as usize].copy_from_slice(&len_bytes);
         self.size += LEN_WIDTH;
 
         // Write the actual record data
-        self.mmap[self.size as usize..(self.size + record_len) as usize].copy_from_slice(data);
+        mmap[self.size as usize..(self.size + record_len) as usize].copy_from_slice(data);
         self.size += record_len;
 
         //Flush the mmap to ensure durability and contents written to disk
-        self.mmap.flush().with_write_context(pos)?;
+        if self.sync_on_append {
+            mmap.flush().with_write_context(pos)?;
+        }
 
         info!(
             postion = pos,
@@ -177,7 +273,8 @@ impl Store {
 
         // Read the record length
         let data_start = pos + LEN_WIDTH;
-        let data_end = data_start + record_len;
+        // a corrupted length prefix can be large enough to overflow
+        let data_end = data_start.saturating_add(record_len);
 
         if data_end > self.size {
             warn!(
@@ -208,9 +305,63 @@ impl Store {
         self.size
     }
 
+    /// Controls whether every append is flushed to disk before returning (the default).
+    /// When disabled, writes are left to the OS page cache until `flush` is called.
+    pub fn set_sync_on_append(&mut self, sync_on_append: bool) {
+        self.sync_on_append = sync_on_append;
+    }
+
+    /// Returns true if every append is flushed to disk before returning
+    #[cfg(test)]
+    pub(crate) fn syncs_on_append(&self) -> bool {
+        self.sync_on_append
+    }
+
+    /// Flushes all written records to disk
+    pub fn flush(&mut self) -> StorageResult<()> {
+        self.mmap.flush().with_write_context(self.size)
+    }
+
+    /// Flushes the store and truncates the file to its real size.
+    /// Unlike `Drop`, failures are reported to the caller.
+    #[instrument(skip(self), fields(size = self.size))]
+    pub fn close(mut self) -> StorageResult<()> {
+        // Drop must not repeat the work, even if it fails here
+        self.closed = true;
+        if self.is_read_only() {
+            return Ok(());
+        }
+        self.mmap.flush().with_write_context(self.size)?;
+        self.file.set_len(self.size).with_write_context(self.size)?;
+        self.file.sync_all().with_write_context(self.size)?;
+        debug!("Store closed");
+        Ok(())
+    }
+
+    /// Seals the store once its segment is rotated out: flushes it, truncates the file to
+    /// the written records and remaps it read-only, releasing the preallocated space.
+    #[instrument(skip(self), fields(size = self.size))]
+    pub fn seal(&mut self) -> StorageResult<()> {
+        if self.is_read_only() {
+            return Ok(());
+        }
+        self.mmap.flush().with_write_context(self.size)?;
+        self.file.set_len(self.size).with_write_context(self.size)?;
+        self.file.sync_all().with_write_context(self.size)?;
+        // the writable map still covers the truncated tail and is dropped here
+        self.mmap =
+            FileMap::ReadOnly(unsafe { Mmap::map(&self.file).with_mmap_context(self.size)? });
+        debug!("Store sealed");
+        Ok(())
+    }
+
     /// Grows the memory map to accomodate more data
     #[instrument(skip(self))]
     pub fn grow(&mut self, needed: u64) -> StorageResult<()> {
+        if self.is_read_only() {
+            return Err(StorageError::ReadOnly);
+        }
+
         let current_capacity = self.mmap.len() as u64;
         let new_capacity = std::cmp::max(current_capacity * 2, self.size + needed + 1024 * 1024); // add 1mb extra buffer to our target
 
@@ -231,12 +382,12 @@ impl Store {
             .sync_all()
             .with_grow_context(current_capacity, new_capacity)?;
 
-        self.mmap = unsafe {
+        self.mmap = FileMap::Writable(unsafe {
             MmapOptions::new()
                 .len(new_capacity as usize)
                 .map_mut(&self.file)
                 .with_mmap_context(new_capacity)?
-        };
+        });
 
         info!("Store capacity grown successfully");
         Ok(())
@@ -258,17 +409,49 @@ impl Store {
                 .with_mmap_context(file_len)?
         };
 
+        let last_valid_pos = Self::valid_len(&mmap)?;
+
+        // If we found any torn records, truncate the file
+        if last_valid_pos < file_len {
+            warn!(
+                original_size = file_len,
+                truncated_size = last_valid_pos,
+                "Truncating file to remove torn records"
+            );
+
+            file.set_len(last_valid_pos).with_open_context(path)?;
+            file.sync_all().with_open_context(path)?;
+            metrics::record_recovery_truncation("store", file_len - last_valid_pos);
+
+            info!(
+                recovered_size = last_valid_pos,
+                removed_bytes = file_len - last_valid_pos,
+                "Recovery scan completed - file repaired"
+            );
+        } else {
+            info!(
+                file_size = file_len,
+                "Recovery scan completed - no torn records found"
+            );
+        }
+
+        Ok(last_valid_pos)
+    }
+
+    /// Walks the records from the start of the file and returns the length of the
+    /// complete ones, stopping at the first torn or implausibly long record
+    pub(crate) fn valid_len(mmap: &[u8]) -> StorageResult<u64> {
+        let file_len = mmap.len() as u64;
         let mut pos = 0u64;
         let mut last_valid_pos = 0u64;
 
         while pos < file_len {
             // Check if we have enough bytes for a length prefix
-            // c
             if pos + LEN_WIDTH > file_len {
                 warn!(
                     position = pos,
                     file_len = file_len,
-                    "Incomplete length prefix at end of file - truncating"
+                    "Incomplete length prefix at end of file"
                 );
                 break;
             }
@@ -285,8 +468,7 @@ impl Store {
             debug!(position = pos, record_len, "Found record during scan");
 
             // Check if record length is reasonable (prevent runaway reads)
-            if record_len > 100 * 1024 * 1024 {
-                // 100MB max record size
+            if record_len > MAX_RECORD_BYTES {
                 warn!(
                     position = pos,
                     record_len = record_len,
@@ -303,7 +485,7 @@ impl Store {
                     record_len = record_len,
                     record_end = record_end,
                     file_len = file_len,
-                    "Incomplete record data - truncating"
+                    "Incomplete record data"
                 );
                 break;
             }
@@ -313,35 +495,15 @@ impl Store {
             pos = record_end;
         }
 
-        // If we found any torn records, truncate the file
-        if last_valid_pos < file_len {
-            warn!(
-                original_size = file_len,
-                truncated_size = last_valid_pos,
-                "Truncating file to remove torn records"
-            );
-
-            file.set_len(last_valid_pos).with_open_context(path)?;
-            file.sync_all().with_open_context(path)?;
-
-            info!(
-                recovered_size = last_valid_pos,
-                removed_bytes = file_len - last_valid_pos,
-                "Recovery scan completed - file repaired"
-            );
-        } else {
-            info!(
-                file_size = file_len,
-                "Recovery scan completed - no torn records found"
-            );
-        }
-
         Ok(last_valid_pos)
     }
 }
 
 impl Drop for Store {
     fn drop(&mut self) {
+        if self.closed || self.is_read_only() {
+            return;
+        }
         // flush all data before dropping
         let _ = self.mmap.flush();
         // truncate file to actual size to avoid sparse files
@@ -531,4 +693,74 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_store_close_truncates_file() -> StorageResult<()> {
+        init_tracing();
+        let temp_file = NamedTempFile::new().unwrap();
+        let path = temp_file.path().to_path_buf();
+
+        let mut store = Store::new(&path)?;
+        store.append(b"hello")?;
+        store.append(b"world")?;
+        let size = store.size();
+        store.close()?;
+
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), size);
+
+        let store = Store::new(&path)?;
+        assert_eq!(store.read(0)?.0, b"hello");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_rejects_overflowing_length() -> StorageResult<()> {
+        init_tracing();
+        let temp_file = NamedTempFile::new().unwrap();
+        let mut store = Store::new(temp_file.path())?;
+        store.append(b"hello")?;
+
+        store.mmap.writable().unwrap()[..LEN_WIDTH as usize]
+            .copy_from_slice(&u64::MAX.to_le_bytes());
+
+        assert!(matches!(
+            store.read(0),
+            Err(StorageError::CorruptedRecord { position: 0, .. })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_read_only_leaves_file_untouched() -> StorageResult<()> {
+        init_tracing();
+        let temp_file = NamedTempFile::new().unwrap();
+        let path = temp_file.path().to_path_buf();
+
+        let mut store = Store::new(&path)?;
+        store.append(b"hello")?;
+        store.close()?;
+
+        // torn record at the end, which Store::new would cut off
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes.extend_from_slice(&50u64.to_le_bytes());
+        bytes.extend_from_slice(b"torn");
+        std::fs::write(&path, &bytes).unwrap();
+
+        let mut store = Store::open_read_only(&path)?;
+        assert!(store.is_read_only());
+        assert_eq!(store.size(), LEN_WIDTH + 5);
+        assert_eq!(store.read(0)?.0, b"hello");
+        assert!(matches!(store.append(b"nope"), Err(StorageError::ReadOnly)));
+        assert!(matches!(store.grow(1), Err(StorageError::ReadOnly)));
+        store.flush()?;
+        drop(store);
+
+        assert_eq!(std::fs::read(&path).unwrap(), bytes);
+        Store::open_read_only(&path)?.close()?;
+        assert_eq!(std::fs::read(&path).unwrap(), bytes);
+
+        Ok(())
+    }
 }
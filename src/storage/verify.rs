@@ -0,0 +1,561 @@
+//! Consistency checks over the files of a log directory, behind [`Log::verify`] and [`Log::repair`].
+//!
+//! Opening a log repairs it implicitly: `Store::new` cuts off torn records and `Index::new`
+//! drops a partial trailing entry, logging warnings only. Verification looks at the same
+//! problems without modifying anything and reports each of them; repair then applies the fix
+//! recovery would, plus the ones it cannot (out-of-bounds index entries, orphan files).
+//!
+//! Repair rewrites files in place and must not run while a server has the log open. Segment
+//! files hard linked into a snapshot are copied first, so repairing never changes a snapshot.
+//!
+//! [`Log::verify`]: crate::storage::log::Log::verify
+//! [`Log::repair`]: crate::storage::log::Log::repair
+use crate::errors::LogError;
+use crate::storage::StorageContext;
+use crate::storage::index::{ENTRY_WIDTH, Index};
+use crate::storage::inspect::{
+    SegmentFiles, index_base_offsets, index_file_name, segment_base_offsets, store_file_name,
+};
+use crate::storage::manifest::{Manifest, SegmentEntry};
+use crate::storage::snapshot;
+use crate::storage::store::{LEN_WIDTH, Store};
+use crate::storage::traits::{LocalFileSystem, StorageCleanup};
+use crate::{LogResult, SegmentResult};
+use serde::Serialize;
+use std::fmt;
+use std::fs::OpenOptions;
+use std::path::Path;
+use tracing::{info, instrument, warn};
+
+/// Problem found in a log directory
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Issue {
+    /// The store ends in an incomplete record
+    TornRecords {
+        base_offset: u64,
+        valid_bytes: u64,
+        file_bytes: u64,
+    },
+    /// The index size is not a multiple of the entry width
+    PartialIndexEntry { base_offset: u64, file_bytes: u64 },
+    /// An index entry points at a record that is not complete in the store.
+    /// Repair drops it together with every entry after it.
+    IndexOutOfBounds {
+        base_offset: u64,
+        offset: u64,
+        position: u64,
+    },
+    /// Offsets between the end of the previous segment and this one are missing
+    OffsetGap { base_offset: u64, expected: u64 },
+    /// The segment starts before the previous one ends
+    OffsetOverlap { base_offset: u64, expected: u64 },
+    /// An index file without a store
+    OrphanIndex { base_offset: u64 },
+    /// A store file without an index. Repair rebuilds the index from the store.
+    OrphanStore { base_offset: u64 },
+    /// A sealed segment no longer matches the checksum in the manifest
+    ChecksumMismatch { base_offset: u64 },
+}
+
+impl Issue {
+    /// Gaps and overlaps mean records are missing or duplicated and a checksum mismatch
+    /// means records were altered, none of which repair can undo
+    pub fn is_repairable(&self) -> bool {
+        !matches!(
+            self,
+            Issue::OffsetGap { .. } | Issue::OffsetOverlap { .. } | Issue::ChecksumMismatch { .. }
+        )
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::TornRecords {
+                base_offset,
+                valid_bytes,
+                file_bytes,
+            } => write!(
+                f,
+                "segment {base_offset}: {} bytes of torn records after byte {valid_bytes} of the store",
+                file_bytes - valid_bytes
+            ),
+            Issue::PartialIndexEntry {
+                base_offset,
+                file_bytes,
+            } => write!(
+                f,
+                "segment {base_offset}: index of {file_bytes} bytes ends in a partial entry"
+            ),
+            Issue::IndexOutOfBounds {
+                base_offset,
+                offset,
+                position,
+            } => write!(
+                f,
+                "segment {base_offset}: index entry for offset {offset} points outside the store (position {position})"
+            ),
+            Issue::OffsetGap {
+                base_offset,
+                expected,
+            } => write!(
+                f,
+                "segment {base_offset}: offsets {expected}..{base_offset} are missing before it"
+            ),
+            Issue::OffsetOverlap {
+                base_offset,
+                expected,
+            } => write!(
+                f,
+                "segment {base_offset}: overlaps the previous segment, which ends at {expected}"
+            ),
+            Issue::OrphanIndex { base_offset } => {
+                write!(f, "segment {base_offset}: index file without a store")
+            }
+            Issue::OrphanStore { base_offset } => {
+                write!(f, "segment {base_offset}: store file without an index")
+            }
+            Issue::ChecksumMismatch { base_offset } => {
+                write!(
+                    f,
+                    "segment {base_offset}: sealed segment does not match its manifest checksum"
+                )
+            }
+        }
+    }
+}
+
+/// An issue and whether it has been repaired
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Finding {
+    #[serde(flatten)]
+    pub issue: Issue,
+    pub repaired: bool,
+}
+
+/// Outcome of verifying a log directory
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct VerifyReport {
+    pub segments: usize,
+    /// Records reachable through the indexes
+    pub records: u64,
+    pub findings: Vec<Finding>,
+}
+
+impl VerifyReport {
+    /// Returns true if nothing is left unrepaired
+    pub fn is_consistent(&self) -> bool {
+        self.findings.iter().all(|finding| finding.repaired)
+    }
+
+    fn push(&mut self, issue: Issue, repaired: bool) {
+        warn!(%issue, repaired, "Log verification found an issue");
+        self.findings.push(Finding { issue, repaired });
+    }
+}
+
+/// Checks every segment in `dir`, repairing what can be repaired if `repair` is set
+#[instrument(skip_all, fields(dir = ?dir, repair))]
+pub(crate) fn run(dir: &Path, repair: bool) -> LogResult<VerifyReport> {
+    let stores = segment_base_offsets(dir)?;
+    let indexes = index_base_offsets(dir)?;
+    let manifest = Manifest::load(dir);
+    let mut report = VerifyReport::default();
+    let mut repaired_segments = Vec::new();
+
+    for &base_offset in indexes.iter().filter(|base| !stores.contains(base)) {
+        if repair {
+            LocalFileSystem
+                .delete_file(&dir.join(index_file_name(base_offset)))
+                .map_err(|e| LogError::CleanupError {
+                    base_offset,
+                    source: e.into(),
+                })?;
+        }
+        report.push(Issue::OrphanIndex { base_offset }, repair);
+    }
+
+    let mut previous_next_offset = None;
+    for &base_offset in &stores {
+        let has_index = indexes.contains(&base_offset);
+        let sealed = manifest
+            .as_ref()
+            .and_then(|manifest| manifest.sealed(base_offset));
+        let findings = report.findings.len();
+        let next_offset = check_segment(dir, base_offset, has_index, sealed, repair, &mut report)?;
+        if report.findings[findings..]
+            .iter()
+            .any(|finding| finding.repaired)
+        {
+            repaired_segments.push(base_offset);
+        }
+
+        match previous_next_offset {
+            Some(expected) if base_offset > expected => {
+                report.push(
+                    Issue::OffsetGap {
+                        base_offset,
+                        expected,
+                    },
+                    false,
+                );
+            }
+            Some(expected) if base_offset < expected => {
+                report.push(
+                    Issue::OffsetOverlap {
+                        base_offset,
+                        expected,
+                    },
+                    false,
+                );
+            }
+            _ => {}
+        }
+        previous_next_offset = Some(next_offset);
+        report.segments += 1;
+    }
+
+    // repaired segments no longer match their entries; the log recomputes them on open
+    if let Some(mut manifest) = manifest
+        && !repaired_segments.is_empty()
+    {
+        manifest
+            .segments
+            .retain(|entry| !repaired_segments.contains(&entry.base_offset));
+        manifest.write(dir)?;
+    }
+
+    info!(
+        segments = report.segments,
+        records = report.records,
+        findings = report.findings.len(),
+        "Log verification completed"
+    );
+    Ok(report)
+}
+
+/// Checks one segment and returns its next offset
+fn check_segment(
+    dir: &Path,
+    base_offset: u64,
+    has_index: bool,
+    sealed: Option<&SegmentEntry>,
+    repair: bool,
+    report: &mut VerifyReport,
+) -> LogResult<u64> {
+    let store_path = dir.join(store_file_name(base_offset));
+    let index_path = dir.join(index_file_name(base_offset));
+
+    let files = SegmentFiles::open(dir, base_offset)?;
+    let summary = files.summary();
+    let valid_bytes = files.store_valid_bytes()?;
+
+    let torn = valid_bytes < summary.store_file_bytes;
+    let partial_entry = summary.index_file_bytes % ENTRY_WIDTH != 0;
+
+    let mut next_offset = base_offset;
+    let mut records = 0;
+    let mut out_of_bounds = None;
+    for entry in files.index_entries() {
+        let in_bounds = files.record(&entry).is_ok_and(|record| {
+            record.position + LEN_WIDTH + record.data.len() as u64 <= valid_bytes
+        });
+        if !in_bounds {
+            out_of_bounds = Some(entry);
+            break;
+        }
+        records += 1;
+        next_offset = next_offset.max(entry.offset + 1);
+    }
+
+    // damaged files cannot match their checksum, and are reported as what they are instead
+    let intact = has_index && !torn && !partial_entry && out_of_bounds.is_none();
+    if let Some(entry) = sealed
+        && intact
+        && files.checksum(entry.store_bytes, entry.index_entries) != entry.checksum
+    {
+        report.push(Issue::ChecksumMismatch { base_offset }, false);
+    }
+    // the maps must be gone before files are truncated underneath them
+    drop(files);
+    if repair && (torn || !has_index || partial_entry || out_of_bounds.is_some()) {
+        snapshot::unshare_segment(dir, base_offset)?;
+    }
+
+    if torn {
+        if repair {
+            truncate(&store_path, valid_bytes)?;
+        }
+        report.push(
+            Issue::TornRecords {
+                base_offset,
+                valid_bytes,
+                file_bytes: summary.store_file_bytes,
+            },
+            repair,
+        );
+    }
+
+    if !has_index {
+        // the records are intact, only the index to reach them is missing
+        let positions = record_positions(&store_path)?;
+        next_offset = base_offset + positions.len() as u64;
+        if repair {
+            rebuild_index(&index_path, base_offset, &positions)?;
+            records = positions.len() as u64;
+        }
+        report.push(Issue::OrphanStore { base_offset }, repair);
+    }
+
+    if partial_entry || out_of_bounds.is_some() {
+        if repair {
+            let entries = match out_of_bounds {
+                Some(entry) => entry.slot,
+                None => summary.index_file_bytes / ENTRY_WIDTH,
+            };
+            truncate(&index_path, entries * ENTRY_WIDTH)?;
+        }
+        if partial_entry {
+            report.push(
+                Issue::PartialIndexEntry {
+                    base_offset,
+                    file_bytes: summary.index_file_bytes,
+                },
+                repair,
+            );
+        }
+        if let Some(entry) = out_of_bounds {
+            report.push(
+                Issue::IndexOutOfBounds {
+                    base_offset,
+                    offset: entry.offset,
+                    position: entry.position,
+                },
+                repair,
+            );
+        }
+    }
+
+    report.records += records;
+    Ok(next_offset)
+}
+
+/// Returns the positions of the complete records in a store
+fn record_positions(store_path: &Path) -> SegmentResult<Vec<u64>> {
+    let store = Store::open_read_only(store_path)?;
+
+    let mut positions = Vec::new();
+    let mut position = 0;
+    while position < store.size() {
+        let (_, bytes_read) = store.read(position)?;
+        positions.push(position);
+        position += bytes_read;
+    }
+    Ok(positions)
+}
+
+/// Writes a fresh index for records at `positions`, numbered from `base_offset`
+fn rebuild_index(index_path: &Path, base_offset: u64, positions: &[u64]) -> SegmentResult<()> {
+    let mut index = Index::new(index_path)?;
+    for (offset, &position) in (base_offset..).zip(positions) {
+        index.write(offset, position)?;
+    }
+    index.close()?;
+
+    info!(base_offset, records = positions.len(), "Rebuilt index");
+    Ok(())
+}
+
+fn truncate(path: &Path, len: u64) -> SegmentResult<()> {
+    let path_str = path.to_string_lossy();
+    let file = OpenOptions::new()
+        .write(true)
+        .open(path)
+        .with_open_context(&path_str)?;
+    file.set_len(len)
+        .and_then(|_| file.sync_all())
+        .with_write_context(len)?;
+    info!(path = %path_str, len, "Truncated file");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::log::{Log, LogConfig};
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn config(dir: &TempDir) -> LogConfig {
+        LogConfig::builder()
+            .max_index_entries(5)
+            .log_dir(dir.path())
+            .build()
+            .unwrap()
+    }
+
+    /// Writes 25 records into segments based at 0, 5, 10, 15 and 20
+    fn write_log(dir: &TempDir) {
+        let mut log = Log::new(config(dir)).unwrap();
+        for i in 0..25 {
+            log.append(format!("record-{i}").as_bytes()).unwrap();
+        }
+        log.close().unwrap();
+    }
+
+    fn append_bytes(path: &Path, bytes: &[u8]) {
+        let mut contents = fs::read(path).unwrap();
+        contents.extend_from_slice(bytes);
+        fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn test_clean_log_has_no_findings() {
+        let dir = TempDir::new().unwrap();
+        write_log(&dir);
+
+        let report = Log::verify(dir.path()).unwrap();
+        assert_eq!(report.segments, 5);
+        assert_eq!(report.records, 25);
+        assert!(report.findings.is_empty());
+        assert!(report.is_consistent());
+    }
+
+    #[test]
+    fn test_sealed_segment_checksum_mismatch() {
+        let dir = TempDir::new().unwrap();
+        write_log(&dir);
+
+        // same size, different bytes: only the checksum can tell
+        let path = dir.path().join(store_file_name(5));
+        let mut contents = fs::read(&path).unwrap();
+        *contents.last_mut().unwrap() ^= 0xff;
+        fs::write(&path, contents).unwrap();
+
+        let report = Log::repair(dir.path()).unwrap();
+        let findings: Vec<_> = report
+            .findings
+            .iter()
+            .map(|f| (f.issue.clone(), f.repaired))
+            .collect();
+        assert_eq!(
+            findings,
+            [(Issue::ChecksumMismatch { base_offset: 5 }, false)]
+        );
+        assert!(!report.is_consistent());
+    }
+
+    #[test]
+    fn test_verify_reports_and_repair_fixes_damage() {
+        let dir = TempDir::new().unwrap();
+        write_log(&dir);
+        let path = |name: String| dir.path().join(name);
+
+        fs::remove_file(path(index_file_name(0))).unwrap();
+        fs::remove_file(path(store_file_name(10))).unwrap();
+        fs::remove_file(path(index_file_name(10))).unwrap();
+        append_bytes(&path(index_file_name(15)), &[1, 2, 3]);
+        let store_len = fs::metadata(path(store_file_name(20))).unwrap().len();
+        append_bytes(
+            &path(store_file_name(20)),
+            &[&50u64.to_le_bytes()[..], b"torn"].concat(),
+        );
+        append_bytes(
+            &path(index_file_name(20)),
+            &[25u64.to_le_bytes(), store_len.to_le_bytes()].concat(),
+        );
+        fs::write(path(index_file_name(99)), [0; ENTRY_WIDTH as usize]).unwrap();
+
+        let damaged = |dir: &TempDir| {
+            let mut files: Vec<_> = fs::read_dir(dir.path())
+                .unwrap()
+                .map(|entry| {
+                    let path = entry.unwrap().path();
+                    (path.clone(), fs::read(path).unwrap())
+                })
+                .collect();
+            files.sort();
+            files
+        };
+        let before = damaged(&dir);
+
+        let expected = vec![
+            Issue::OrphanIndex { base_offset: 99 },
+            Issue::OrphanStore { base_offset: 0 },
+            Issue::PartialIndexEntry {
+                base_offset: 15,
+                file_bytes: 5 * ENTRY_WIDTH + 3,
+            },
+            Issue::OffsetGap {
+                base_offset: 15,
+                expected: 10,
+            },
+            Issue::TornRecords {
+                base_offset: 20,
+                valid_bytes: store_len,
+                file_bytes: store_len + LEN_WIDTH + 4,
+            },
+            Issue::IndexOutOfBounds {
+                base_offset: 20,
+                offset: 25,
+                position: store_len,
+            },
+        ];
+
+        let report = Log::verify(dir.path()).unwrap();
+        let issues: Vec<_> = report.findings.iter().map(|f| f.issue.clone()).collect();
+        assert_eq!(issues, expected);
+        assert!(report.findings.iter().all(|f| !f.repaired));
+        assert_eq!(report.records, 15);
+        assert_eq!(damaged(&dir), before);
+
+        let report = Log::repair(dir.path()).unwrap();
+        let repaired: Vec<_> = report
+            .findings
+            .iter()
+            .map(|f| (f.issue.clone(), f.repaired))
+            .collect();
+        let expected_repaired: Vec<_> = expected
+            .iter()
+            .map(|issue| (issue.clone(), issue.is_repairable()))
+            .collect();
+        assert_eq!(repaired, expected_repaired);
+        assert_eq!(report.records, 20);
+        assert!(!report.is_consistent());
+
+        // only the gap, which cannot be repaired, is left
+        let report = Log::verify(dir.path()).unwrap();
+        assert_eq!(report.findings.len(), 1);
+        assert!(matches!(report.findings[0].issue, Issue::OffsetGap { .. }));
+        assert!(!path(index_file_name(99)).exists());
+
+        let log = Log::new(config(&dir)).unwrap();
+        assert_eq!(log.read(0).unwrap(), b"record-0");
+        assert_eq!(log.read(4).unwrap(), b"record-4");
+        assert_eq!(log.next_offset(), 25);
+    }
+
+    #[test]
+    fn test_repair_leaves_snapshots_alone() {
+        let dir = TempDir::new().unwrap();
+        let snapshot_dir = TempDir::new().unwrap();
+        write_log(&dir);
+        let log = Log::new(config(&dir)).unwrap();
+        log.snapshot(snapshot_dir.path()).unwrap();
+        log.close().unwrap();
+
+        // replace the live store with a torn copy; its index stays linked into the snapshot
+        let store_path = dir.path().join(store_file_name(5));
+        let contents = fs::read(&store_path).unwrap();
+        fs::remove_file(&store_path).unwrap();
+        fs::write(&store_path, &contents[..contents.len() - 2]).unwrap();
+
+        let report = Log::repair(dir.path()).unwrap();
+        assert!(report.findings.iter().any(|f| f.repaired));
+
+        let report = Log::verify(snapshot_dir.path()).unwrap();
+        assert!(report.findings.is_empty(), "{:?}", report.findings);
+        assert_eq!(report.records, 25);
+    }
+}
@@ -0,0 +1,147 @@
+//! Prometheus metrics.
+//!
+//! Storage and the gRPC service record through the `metrics` facade, which is a no-op
+//! until [`install`] sets up the exporter and its HTTP `/metrics` endpoint.
+
+use crate::ProglogResult;
+use crate::errors::ProglogError;
+use crate::server::status::code_name;
+use metrics::{counter, describe_counter, describe_gauge, describe_histogram, gauge, histogram};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder};
+use std::net::SocketAddr;
+use std::time::Duration;
+use tonic::{Code, Status};
+
+pub const RECORDS_APPENDED: &str = "proglog_records_appended_total";
+pub const BYTES_APPENDED: &str = "proglog_bytes_appended_total";
+pub const READ_DURATION: &str = "proglog_read_duration_seconds";
+pub const SEGMENT_ROTATIONS: &str = "proglog_segment_rotations_total";
+pub const SEGMENTS: &str = "proglog_segments";
+pub const LOG_SIZE: &str = "proglog_log_size_bytes";
+pub const NEXT_OFFSET: &str = "proglog_next_offset";
+pub const RECOVERY_TRUNCATIONS: &str = "proglog_recovery_truncations_total";
+pub const RECOVERY_TRUNCATED_BYTES: &str = "proglog_recovery_truncated_bytes_total";
+pub const RPC_REQUESTS: &str = "proglog_rpc_requests_total";
+pub const RPC_DURATION: &str = "proglog_rpc_duration_seconds";
+
+/// Histogram buckets for latencies, from 10µs to 5s
+const LATENCY_BUCKETS: &[f64] = &[
+    0.00001, 0.00005, 0.0001, 0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0,
+];
+
+/// Installs the global Prometheus recorder and serves it over HTTP on `addr`.
+/// Must be called from within the Tokio runtime.
+pub fn install(addr: SocketAddr) -> ProglogResult<()> {
+    PrometheusBuilder::new()
+        .with_http_listener(addr)
+        .set_buckets_for_metric(Matcher::Suffix("_seconds".to_string()), LATENCY_BUCKETS)
+        .and_then(|builder| builder.install())
+        .map_err(|e| ProglogError::Config {
+            message: format!("failed to start metrics endpoint on {addr}: {e}"),
+        })?;
+
+    describe();
+    Ok(())
+}
+
+fn describe() {
+    describe_counter!(RECORDS_APPENDED, "Records appended to the log");
+    describe_counter!(BYTES_APPENDED, "Record payload bytes appended to the log");
+    describe_histogram!(READ_DURATION, "Time to read a record from the log");
+    describe_counter!(SEGMENT_ROTATIONS, "Segments rotated because they were full");
+    describe_gauge!(SEGMENTS, "Segments currently in the log");
+    describe_gauge!(LOG_SIZE, "Size of all segment stores in bytes");
+    describe_gauge!(NEXT_OFFSET, "Offset the next appended record will get");
+    describe_counter!(
+        RECOVERY_TRUNCATIONS,
+        "Files truncated on startup to remove torn writes"
+    );
+    describe_counter!(
+        RECOVERY_TRUNCATED_BYTES,
+        "Bytes removed on startup to repair torn writes"
+    );
+    describe_counter!(RPC_REQUESTS, "gRPC calls by method and status code");
+    describe_histogram!(RPC_DURATION, "gRPC call latency by method");
+}
+
+/// Records a successful append; `stored_bytes` includes the length prefix
+pub(crate) fn record_append(data_len: usize, stored_bytes: u64, next_offset: u64) {
+    counter!(RECORDS_APPENDED).increment(1);
+    counter!(BYTES_APPENDED).increment(data_len as u64);
+    gauge!(LOG_SIZE).increment(stored_bytes as f64);
+    gauge!(NEXT_OFFSET).set(next_offset as f64);
+}
+
+pub(crate) fn record_read(elapsed: Duration) {
+    histogram!(READ_DURATION).record(elapsed.as_secs_f64());
+}
+
+pub(crate) fn record_rotation() {
+    counter!(SEGMENT_ROTATIONS).increment(1);
+}
+
+/// Sets the log gauges after the segment layout changed
+pub(crate) fn record_log_state(segments: usize, size: u64, next_offset: u64) {
+    gauge!(SEGMENTS).set(segments as f64);
+    gauge!(LOG_SIZE).set(size as f64);
+    gauge!(NEXT_OFFSET).set(next_offset as f64);
+}
+
+/// Records a repair on startup; `file` is `"store"` or `"index"`
+pub(crate) fn record_recovery_truncation(file: &'static str, removed_bytes: u64) {
+    counter!(RECOVERY_TRUNCATIONS, "file" => file).increment(1);
+    counter!(RECOVERY_TRUNCATED_BYTES, "file" => file).increment(removed_bytes);
+}
+
+pub(crate) fn record_rpc<T>(method: &'static str, result: &Result<T, Status>, elapsed: Duration) {
+    let code = match result {
+        Ok(_) => Code::Ok,
+        Err(status) => status.code(),
+    };
+
+    counter!(RPC_REQUESTS, "method" => method, "code" => code_name(code)).increment(1);
+    histogram!(RPC_DURATION, "method" => method).record(elapsed.as_secs_f64());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::log::{Log, LogConfig};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_log_operations_are_recorded() {
+        let temp_dir = TempDir::new().unwrap();
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+
+        metrics::with_local_recorder(&recorder, || {
+            let config = LogConfig::builder()
+                .max_store_bytes(100)
+                .max_record_bytes(32)
+                .log_dir(temp_dir.path())
+                .build()
+                .unwrap();
+            let mut log = Log::new(config).unwrap();
+            for i in 0..10 {
+                log.append(format!("record-{i}").as_bytes()).unwrap();
+            }
+            log.read(3).unwrap();
+
+            let status: Result<(), Status> = Err(Status::out_of_range("nope"));
+            record_rpc("Consume", &status, Duration::from_millis(1));
+        });
+
+        let output = handle.render();
+        assert!(output.contains("proglog_records_appended_total 10"));
+        assert!(output.contains("proglog_bytes_appended_total 80"));
+        assert!(output.contains("proglog_next_offset 10"));
+        assert!(output.contains("proglog_segments 2"));
+        assert!(output.contains("proglog_segment_rotations_total 1"));
+        assert!(output.contains("proglog_read_duration_seconds"));
+        assert!(
+            output
+                .contains("proglog_rpc_requests_total{method=\"Consume\",code=\"OUT_OF_RANGE\"} 1")
+        );
+    }
+}
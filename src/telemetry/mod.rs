@@ -0,0 +1,3 @@
+//! Observability: Prometheus metrics and OpenTelemetry tracing for the log and the gRPC service.
+pub mod metrics;
+pub mod trace;
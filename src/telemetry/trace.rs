@@ -0,0 +1,161 @@
+//! Distributed tracing with OpenTelemetry.
+//!
+//! `LogService` continues the caller's trace from the W3C `traceparent`/`tracestate`
+//! request metadata, so the `append`/`read` spans of the storage layer become children of
+//! the client's span. With an OTLP endpoint configured, spans are exported to that collector.
+
+use crate::ProglogResult;
+use crate::errors::ProglogError;
+use opentelemetry::KeyValue;
+use opentelemetry::propagation::{Extractor, TextMapPropagator};
+use opentelemetry::trace::{TraceContextExt, TracerProvider as _};
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use tonic::metadata::{KeyRef, MetadataMap};
+use tracing::{Span, warn};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Registry};
+
+pub const SERVICE_NAME: &str = "proglog";
+
+/// Flushes and shuts down the span exporter when dropped
+#[must_use = "dropping the guard stops span export"]
+#[derive(Debug, Default)]
+pub struct TracingGuard {
+    provider: Option<SdkTracerProvider>,
+}
+
+impl TracingGuard {
+    /// Exports every finished span that is still buffered
+    pub fn flush(&self) {
+        if let Some(provider) = &self.provider
+            && let Err(e) = provider.force_flush()
+        {
+            warn!(error = %e, "Failed to flush spans");
+        }
+    }
+}
+
+impl Drop for TracingGuard {
+    fn drop(&mut self) {
+        if let Some(provider) = self.provider.take()
+            && let Err(e) = provider.shutdown()
+        {
+            eprintln!("failed to shut down span exporter: {e}");
+        }
+    }
+}
+
+/// Builds a tracer provider exporting over OTLP/gRPC to `endpoint`, e.g. `http://localhost:4317`.
+/// Must be called from within the Tokio runtime.
+pub fn otlp_provider(endpoint: &str, instance_id: &str) -> ProglogResult<SdkTracerProvider> {
+    let exporter = SpanExporter::builder()
+        .with_tonic()
+        .with_endpoint(endpoint)
+        .build()
+        .map_err(|e| ProglogError::Config {
+            message: format!("failed to create OTLP exporter for {endpoint}: {e}"),
+        })?;
+
+    let resource = Resource::builder()
+        .with_service_name(SERVICE_NAME)
+        .with_attribute(KeyValue::new(
+            "service.instance.id",
+            instance_id.to_string(),
+        ))
+        .build();
+
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(resource)
+        .build())
+}
+
+/// Installs the global subscriber: formatted logs filtered by `log_level`, plus OTLP
+/// span export when a provider is given.
+pub fn init(log_level: &str, provider: Option<SdkTracerProvider>) -> ProglogResult<TracingGuard> {
+    let otel_layer = provider
+        .as_ref()
+        .map(|provider| tracing_opentelemetry::layer().with_tracer(provider.tracer(SERVICE_NAME)));
+
+    Registry::default()
+        .with(
+            EnvFilter::try_new(log_level).map_err(|e| ProglogError::Config {
+                message: format!("invalid log_level `{log_level}`: {e}"),
+            })?,
+        )
+        .with(tracing_subscriber::fmt::layer())
+        .with(otel_layer)
+        .try_init()
+        .map_err(|e| ProglogError::Internal {
+            message: format!("failed to install tracing subscriber: {e}"),
+        })?;
+
+    Ok(TracingGuard { provider })
+}
+
+/// Makes `span` a child of the remote span described by the request's W3C trace-context
+/// metadata. Requests without (valid) trace-context keep `span` as a root.
+pub(crate) fn set_remote_parent(span: &Span, metadata: &MetadataMap) {
+    let parent = TraceContextPropagator::new().extract(&MetadataExtractor(metadata));
+    if !parent.has_active_span() {
+        return;
+    }
+
+    // fails only when no OpenTelemetry layer is installed, which leaves nothing to link
+    let _ = span.set_parent(parent);
+}
+
+struct MetadataExtractor<'a>(&'a MetadataMap);
+
+impl Extractor for MetadataExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0
+            .keys()
+            .filter_map(|key| match key {
+                KeyRef::Ascii(key) => Some(key.as_str()),
+                KeyRef::Binary(_) => None,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extracts_w3c_trace_context() {
+        let mut metadata = MetadataMap::new();
+        metadata.insert(
+            "traceparent",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+                .parse()
+                .unwrap(),
+        );
+
+        let cx = TraceContextPropagator::new().extract(&MetadataExtractor(&metadata));
+        let span_context = cx.span().span_context().clone();
+
+        assert!(span_context.is_remote());
+        assert_eq!(
+            span_context.trace_id().to_string(),
+            "4bf92f3577b34da6a3ce929d0e0e4736"
+        );
+        assert_eq!(span_context.span_id().to_string(), "00f067aa0ba902b7");
+    }
+
+    #[test]
+    fn test_ignores_missing_trace_context() {
+        let cx = TraceContextPropagator::new().extract(&MetadataExtractor(&MetadataMap::new()));
+        assert!(!cx.has_active_span());
+    }
+}
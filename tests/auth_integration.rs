@@ -0,0 +1,141 @@
+use proglog_rs::client::{ClientConfig, LogClient};
+use proglog_rs::errors::{NetworkError, ProglogError};
+use proglog_rs::server::auth::{AuthInterceptor, Authorizer};
+use proglog_rs::server::grpc::LogService;
+use proglog_rs::server::grpc::proto::log_server::LogServer;
+use proglog_rs::server::tls::{ClientTls, ServerTls};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use tonic::Code;
+use tonic::transport::Server;
+
+mod common;
+use common::{generate_pki, open_log, spawn_router};
+
+const POLICY: &str = "
+# subject, object, action
+p, alice, orders, produce
+p, alice, orders, consume
+p, bob, orders, consume
+p, client, orders, produce
+";
+
+const TOKENS: &str = "
+alice alice-token
+bob bob-token
+";
+
+async fn spawn_server(
+    dir: &Path,
+    tls: Option<ServerTls>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let policy_path = dir.join("policy.csv");
+    let tokens_path = dir.join("tokens");
+    fs::write(&policy_path, POLICY)?;
+    fs::write(&tokens_path, TOKENS)?;
+
+    let service = LogService::new(open_log(dir.join("log")))
+        .with_topic("orders")
+        .with_authorizer(Arc::new(Authorizer::from_file(&policy_path)?));
+    let interceptor = AuthInterceptor::load_tokens(&tokens_path)?;
+
+    let mut server = Server::builder();
+    let scheme = match tls {
+        Some(tls) => {
+            server = server.tls_config(tls.load()?)?;
+            "https"
+        }
+        None => "http",
+    };
+
+    let addr =
+        spawn_router(server.add_service(LogServer::with_interceptor(service, interceptor))).await;
+    Ok(format!("{scheme}://{addr}"))
+}
+
+fn client(addr: &str, token: Option<&str>, tls: Option<ClientTls>) -> LogClient {
+    LogClient::new(ClientConfig {
+        servers: vec![addr.to_string()],
+        max_retries: 1,
+        initial_backoff: Duration::from_millis(10),
+        request_timeout: Duration::from_secs(2),
+        tls,
+        token: token.map(str::to_string),
+        ..Default::default()
+    })
+    .unwrap()
+}
+
+fn rpc_code<T>(result: Result<T, ProglogError>) -> Option<Code> {
+    match result {
+        Err(ProglogError::Network(NetworkError::Rpc { code, .. })) => Some(code),
+        _ => None,
+    }
+}
+
+#[tokio::test]
+async fn test_bearer_token_acl() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = TempDir::new()?;
+    let addr = spawn_server(temp_dir.path(), None).await?;
+
+    let mut alice = client(&addr, Some("alice-token"), None);
+    let offset = alice.produce(b"order #1").await?;
+    assert_eq!(alice.consume(offset).await?, b"order #1");
+
+    let mut bob = client(&addr, Some("bob-token"), None);
+    assert_eq!(
+        rpc_code(bob.produce(b"not allowed").await),
+        Some(Code::PermissionDenied)
+    );
+    assert_eq!(bob.consume(offset).await?, b"order #1");
+
+    let mut anonymous = client(&addr, None, None);
+    assert_eq!(
+        rpc_code(anonymous.consume(offset).await),
+        Some(Code::Unauthenticated)
+    );
+
+    let mut impostor = client(&addr, Some("guessed-token"), None);
+    assert_eq!(
+        rpc_code(impostor.consume(offset).await),
+        Some(Code::Unauthenticated)
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_client_certificate_identity() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = TempDir::new()?;
+    let pki = generate_pki(temp_dir.path())?;
+
+    let addr = spawn_server(
+        temp_dir.path(),
+        Some(ServerTls {
+            cert_path: pki.server_cert.clone(),
+            key_path: pki.server_key.clone(),
+            client_ca_path: Some(pki.ca_cert.clone()),
+        }),
+    )
+    .await?;
+
+    // the certificate's common name ("client") is the subject
+    let tls = ClientTls {
+        ca_cert_path: pki.ca_cert.clone(),
+        domain_name: Some("localhost".to_string()),
+        cert_path: Some(pki.client_cert.clone()),
+        key_path: Some(pki.client_key.clone()),
+    };
+    let mut client = client(&addr, None, Some(tls));
+
+    let offset = client.produce(b"signed order").await?;
+    assert_eq!(
+        rpc_code(client.consume(offset).await),
+        Some(Code::PermissionDenied)
+    );
+
+    Ok(())
+}
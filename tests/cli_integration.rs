@@ -0,0 +1,140 @@
+use proglog_rs::server::grpc::LogService;
+use proglog_rs::storage::log::Log;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+use tempfile::TempDir;
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+mod common;
+use common::{log_config, spawn_server};
+
+/// Serves a log with 1 KiB segments and a 256 byte record limit
+async fn spawn_log(temp_dir: &TempDir) -> String {
+    let config = log_config(temp_dir)
+        .max_store_bytes(1024)
+        .max_index_entries(100)
+        .max_record_bytes(256)
+        .build()
+        .unwrap();
+    spawn_server(LogService::new(Log::new(config).unwrap())).await
+}
+
+fn cli(server: &str, args: &[&str]) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_proglog-cli"));
+    command.arg("--server").arg(server).args(args);
+    command
+}
+
+/// Runs the CLI on a blocking thread so the in-process server keeps serving
+async fn run(server: &str, args: &[&str], stdin: &[u8]) -> Output {
+    let mut command = cli(server, args);
+    let stdin = stdin.to_vec();
+
+    tokio::task::spawn_blocking(move || {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child.stdin.take().unwrap().write_all(&stdin).unwrap();
+        child.wait_with_output().unwrap()
+    })
+    .await
+    .unwrap()
+}
+
+async fn stdout(server: &str, args: &[&str], stdin: &[u8]) -> String {
+    let output = run(server, args, stdin).await;
+    assert!(
+        output.status.success(),
+        "{args:?} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_produce_consume_and_offsets() {
+    let temp_dir = TempDir::new().unwrap();
+    let server = spawn_log(&temp_dir).await;
+
+    let offsets = stdout(&server, &["produce"], b"alpha\nbeta\r\ngamma\n").await;
+    assert_eq!(offsets, "0\n1\n2\n");
+
+    let file = temp_dir.path().join("record.bin");
+    std::fs::write(&file, b"multi\nline").unwrap();
+    let offsets = stdout(
+        &server,
+        &["produce", "--whole-file", file.to_str().unwrap()],
+        b"",
+    )
+    .await;
+    assert_eq!(offsets, "3\n");
+
+    assert_eq!(
+        stdout(&server, &["consume"], b"").await,
+        "alpha\nbeta\ngamma\nmulti\nline\n"
+    );
+    assert_eq!(
+        stdout(&server, &["consume", "--from", "1", "--to", "3"], b"").await,
+        "beta\ngamma\n"
+    );
+    assert_eq!(
+        stdout(&server, &["consume", "--to", "3", "--reverse"], b"").await,
+        "gamma\nbeta\nalpha\n"
+    );
+    assert_eq!(
+        stdout(
+            &server,
+            &["--format", "hex", "consume", "--from", "2", "--to", "3"],
+            b""
+        )
+        .await,
+        "67616d6d61\n"
+    );
+    assert_eq!(
+        stdout(&server, &["tail", "-n", "1", "--format", "json"], b"").await,
+        "{\"offset\":3,\"record\":\"bXVsdGkKbGluZQ==\"}\n"
+    );
+    assert_eq!(
+        stdout(&server, &["offsets", "--format", "json"], b"").await,
+        "{\"base_offset\":0,\"next_offset\":4}\n"
+    );
+
+    // records spanning several segments are streamed in order
+    let many: String = (0..50).map(|i| format!("record-{i}\n")).collect();
+    stdout(&server, &["produce"], many.as_bytes()).await;
+    let consumed = stdout(&server, &["consume", "--from", "4"], b"").await;
+    assert_eq!(consumed, many);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_consume_out_of_range_fails() {
+    let temp_dir = TempDir::new().unwrap();
+    let server = spawn_log(&temp_dir).await;
+
+    let output = run(&server, &["consume", "--from", "5", "--to", "6"], b"").await;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Offset 5 not found"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_tail_follow_prints_new_records() {
+    let temp_dir = TempDir::new().unwrap();
+    let server = spawn_log(&temp_dir).await;
+    stdout(&server, &["produce"], b"old\n").await;
+
+    let mut child = tokio::process::Command::from(cli(&server, &["tail", "-f"]))
+        .stdout(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .unwrap();
+    let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+
+    assert_eq!(lines.next_line().await.unwrap().unwrap(), "old");
+    stdout(&server, &["produce"], b"new\n").await;
+    assert_eq!(lines.next_line().await.unwrap().unwrap(), "new");
+
+    child.kill().await.unwrap();
+}
@@ -0,0 +1,396 @@
+use proglog_rs::client::{ClientConfig, LogClient};
+use proglog_rs::discovery::Membership;
+use proglog_rs::errors::{NetworkError, ProglogError};
+use proglog_rs::server::grpc::proto::log_server::{self, LogServer};
+use proglog_rs::server::grpc::proto::{
+    self, ConsumeRequest, ConsumeResponse, ConsumeStreamRequest, GetOffsetsRequest,
+    GetOffsetsResponse, GetServersRequest, GetServersResponse, ProduceRequest, ProduceResponse,
+};
+use proglog_rs::server::grpc::{LEADER_ID_METADATA_KEY, LogService};
+use proglog_rs::server::status::{ERROR_DOMAIN, NOT_LEADER_REASON};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use tokio::net::TcpListener;
+use tonic::transport::Server;
+use tonic::{Code, Request, Response, Status};
+use tonic_types::{ErrorDetails, StatusExt};
+
+mod common;
+use common::{bind, open_log, serve, spawn_server};
+
+/// Stand-in for a follower that rejects every write and points at the leader
+struct Follower {
+    addr: String,
+    leader_addr: String,
+    /// Whether the follower names the leader in `NotLeader`, or only knows there is one
+    knows_leader: bool,
+}
+
+#[tonic::async_trait]
+impl log_server::Log for Follower {
+    async fn produce(
+        &self,
+        _request: Request<ProduceRequest>,
+    ) -> Result<Response<ProduceResponse>, Status> {
+        if !self.knows_leader {
+            return Err(Status::with_error_details(
+                Code::FailedPrecondition,
+                "not the leader",
+                ErrorDetails::with_error_info(NOT_LEADER_REASON, ERROR_DOMAIN, HashMap::new()),
+            ));
+        }
+
+        let mut status = Status::failed_precondition("not the leader");
+        status
+            .metadata_mut()
+            .insert(LEADER_ID_METADATA_KEY, "leader".parse().unwrap());
+        Err(status)
+    }
+
+    async fn consume(
+        &self,
+        _request: Request<ConsumeRequest>,
+    ) -> Result<Response<ConsumeResponse>, Status> {
+        Err(Status::unavailable("follower is catching up"))
+    }
+
+    type ConsumeStreamStream = tokio_stream::Empty<Result<ConsumeResponse, Status>>;
+
+    async fn consume_stream(
+        &self,
+        _request: Request<ConsumeStreamRequest>,
+    ) -> Result<Response<Self::ConsumeStreamStream>, Status> {
+        Err(Status::unavailable("follower is catching up"))
+    }
+
+    async fn get_offsets(
+        &self,
+        _request: Request<GetOffsetsRequest>,
+    ) -> Result<Response<GetOffsetsResponse>, Status> {
+        Err(Status::unavailable("follower is catching up"))
+    }
+
+    async fn get_servers(
+        &self,
+        _request: Request<GetServersRequest>,
+    ) -> Result<Response<GetServersResponse>, Status> {
+        Ok(Response::new(GetServersResponse {
+            servers: vec![
+                proto::Server {
+                    id: "follower".to_string(),
+                    rpc_addr: self.addr.clone(),
+                    is_leader: false,
+                },
+                proto::Server {
+                    id: "leader".to_string(),
+                    rpc_addr: self.leader_addr.clone(),
+                    is_leader: true,
+                },
+            ],
+        }))
+    }
+}
+
+fn spawn_log(temp_dir: &TempDir) -> LogService {
+    LogService::new(open_log(temp_dir))
+}
+
+fn client_config(servers: Vec<String>) -> ClientConfig {
+    ClientConfig {
+        servers,
+        initial_backoff: Duration::from_millis(10),
+        max_backoff: Duration::from_millis(50),
+        request_timeout: Duration::from_secs(2),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_client_produce_and_consume() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = TempDir::new()?;
+    let addr = spawn_server(spawn_log(&temp_dir)).await;
+
+    let mut client = LogClient::new(client_config(vec![addr]))?;
+
+    let records = ["first", "second", "third"];
+    for (i, record) in records.iter().enumerate() {
+        let offset = client.produce(record.as_bytes()).await?;
+        assert_eq!(offset, i as u64);
+    }
+
+    for (i, record) in records.iter().enumerate() {
+        let data = client.consume(i as u64).await?;
+        assert_eq!(data, record.as_bytes());
+    }
+
+    // reading past the end is a client error, reported with the valid offset range
+    match client.consume(99).await {
+        Err(ProglogError::Network(NetworkError::Rpc {
+            code,
+            reason,
+            metadata,
+            ..
+        })) => {
+            assert_eq!(code, Code::OutOfRange);
+            assert_eq!(reason.as_deref(), Some("OFFSET_OUT_OF_RANGE"));
+            assert_eq!(metadata["base_offset"], "0");
+            assert_eq!(metadata["next_offset"], "3");
+        }
+        other => panic!("expected OUT_OF_RANGE, got {other:?}"),
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_client_follows_not_leader_redirect() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = TempDir::new()?;
+    let leader_addr = spawn_server(spawn_log(&temp_dir)).await;
+    let (listener, addr) = bind().await;
+    serve(
+        listener,
+        Server::builder().add_service(LogServer::new(Follower {
+            addr: addr.clone(),
+            leader_addr: leader_addr.clone(),
+            knows_leader: true,
+        })),
+    );
+    let follower_addr = format!("http://{addr}");
+
+    // only the follower is known up front, the leader's address is resolved after the redirect
+    let mut client = LogClient::new(client_config(vec![follower_addr]))?;
+
+    let offset = client.produce(b"redirected").await?;
+    assert_eq!(offset, 0);
+    assert_eq!(client.leader(), leader_addr);
+
+    // the follower keeps failing reads with UNAVAILABLE, so they are retried elsewhere
+    let data = client.consume(0).await?;
+    assert_eq!(data, b"redirected");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_client_discovers_leader_after_anonymous_not_leader()
+-> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = TempDir::new()?;
+    let leader_addr = spawn_server(spawn_log(&temp_dir)).await;
+    let (listener, addr) = bind().await;
+    serve(
+        listener,
+        Server::builder().add_service(LogServer::new(Follower {
+            addr: addr.clone(),
+            leader_addr: leader_addr.clone(),
+            knows_leader: false,
+        })),
+    );
+
+    // NotLeader names no leader, only GetServers does; the client must stay on that leader
+    let mut client = LogClient::new(ClientConfig {
+        max_retries: 1,
+        ..client_config(vec![format!("http://{addr}")])
+    })?;
+    assert_eq!(client.produce(b"discovered").await?, 0);
+    assert_eq!(client.leader(), leader_addr);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_client_skips_unavailable_server() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = TempDir::new()?;
+    let addr = spawn_server(spawn_log(&temp_dir)).await;
+
+    // grab a free port and drop the listener so nothing is serving there
+    let dead_addr = {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        format!("http://{}", listener.local_addr()?)
+    };
+
+    let mut client = LogClient::new(client_config(vec![dead_addr, addr.clone()]))?;
+
+    let offset = client.produce(b"after failover").await?;
+    assert_eq!(offset, 0);
+    assert_eq!(client.leader(), addr);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_client_resolves_leader_via_get_servers() -> Result<(), Box<dyn std::error::Error>> {
+    let leader_dir = TempDir::new()?;
+    let follower_dir = TempDir::new()?;
+    let (leader_listener, leader_addr) = bind().await;
+    let (follower_listener, follower_addr) = bind().await;
+
+    let membership = |local_id: &str, local_addr: &str| {
+        let membership = Membership::new(local_id, local_addr);
+        membership.join("node-0", leader_addr.clone());
+        membership.join("node-1", follower_addr.clone());
+        membership.set_leader(Some("node-0".to_string()));
+        Arc::new(membership)
+    };
+
+    serve(
+        leader_listener,
+        Server::builder().add_service(LogServer::new(
+            LogService::new(open_log(&leader_dir))
+                .with_membership(membership("node-0", &leader_addr)),
+        )),
+    );
+    serve(
+        follower_listener,
+        Server::builder().add_service(LogServer::new(
+            LogService::new(open_log(&follower_dir))
+                .with_membership(membership("node-1", &follower_addr)),
+        )),
+    );
+
+    // the follower is the only configured server, the leader comes from GetServers
+    let mut client =
+        LogClient::connect(client_config(vec![format!("http://{follower_addr}")])).await?;
+    assert_eq!(client.leader(), format!("http://{leader_addr}"));
+
+    let offset = client.produce(b"via resolver").await?;
+    assert_eq!(offset, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_failed_discovery_keeps_known_servers() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = TempDir::new()?;
+    let (listener, addr) = bind().await;
+
+    // the topology lists the server itself first, then an address that cannot be parsed
+    let membership = Membership::new("node-0", addr.clone());
+    membership.join("node-1", "not a valid address");
+    membership.set_leader(Some("node-0".to_string()));
+    serve(
+        listener,
+        Server::builder().add_service(LogServer::new(
+            LogService::new(open_log(&temp_dir)).with_membership(Arc::new(membership)),
+        )),
+    );
+
+    let mut client = LogClient::connect(client_config(vec![format!("http://{addr}")])).await?;
+    assert!(matches!(
+        client.discover().await,
+        Err(ProglogError::Config { .. })
+    ));
+    assert_eq!(client.leader(), format!("http://{addr}"));
+    assert_eq!(client.produce(b"still connected").await?, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_discovery_keeps_endpoint_for_unspecified_address()
+-> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = TempDir::new()?;
+    let (listener, addr) = bind().await;
+
+    // the server advertises the wildcard address it would listen on
+    let membership = Membership::single_node("node-0", "0.0.0.0:50051");
+    serve(
+        listener,
+        Server::builder().add_service(LogServer::new(
+            LogService::new(open_log(&temp_dir)).with_membership(Arc::new(membership)),
+        )),
+    );
+
+    let mut client = LogClient::connect(client_config(vec![format!("http://{addr}")])).await?;
+    client.discover().await?;
+    assert_eq!(client.leader(), format!("http://{addr}"));
+    assert_eq!(client.produce(b"still connected").await?, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_client_streams_ranges_in_both_directions() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = TempDir::new()?;
+    let addr = spawn_server(spawn_log(&temp_dir)).await;
+    let mut client = LogClient::new(client_config(vec![addr]))?;
+
+    // more records than the server sends per batch
+    for i in 0..150 {
+        client.produce(format!("record-{i}").as_bytes()).await?;
+    }
+
+    let mut collect = async |range, reverse| -> Result<Vec<u64>, ProglogError> {
+        let mut records = client.consume_range(range, reverse).await?;
+        let mut offsets = Vec::new();
+        while let Some((offset, record)) = records.next().await? {
+            assert_eq!(record, format!("record-{offset}").as_bytes());
+            offsets.push(offset);
+        }
+        Ok(offsets)
+    };
+
+    assert_eq!(
+        collect(10..140, false).await?,
+        (10..140).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        collect(10..140, true).await?,
+        (10..140).rev().collect::<Vec<_>>()
+    );
+    // the end is clamped to the log
+    assert_eq!(
+        collect(100..1000, true).await?,
+        (100..150).rev().collect::<Vec<_>>()
+    );
+    assert!(collect(20..20, true).await?.is_empty());
+    assert!(matches!(
+        collect(500..600, true).await,
+        Err(ProglogError::Network(NetworkError::Rpc {
+            code: Code::OutOfRange,
+            ..
+        }))
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stream_end_offset_stops_following() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = TempDir::new()?;
+    let service = spawn_log(&temp_dir);
+
+    let stream = |follow, end_offset, reverse| {
+        log_server::Log::consume_stream(
+            &service,
+            Request::new(ConsumeStreamRequest {
+                offset: 0,
+                follow,
+                end_offset,
+                reverse,
+            }),
+        )
+    };
+
+    let status = stream(true, None, true).await.err().unwrap();
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    let mut records = stream(true, Some(2), false).await?.into_inner();
+    for record in ["first", "second", "third"] {
+        log_server::Log::produce(
+            &service,
+            Request::new(ProduceRequest {
+                record: record.as_bytes().to_vec(),
+            }),
+        )
+        .await?;
+    }
+
+    let mut offsets = Vec::new();
+    while let Some(response) = tokio_stream::StreamExt::next(&mut records).await {
+        offsets.push(response?.offset);
+    }
+    assert_eq!(offsets, [0, 1]);
+    Ok(())
+}
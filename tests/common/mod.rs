@@ -0,0 +1,106 @@
+//! Helpers shared by the integration tests
+#![allow(dead_code)] // not every test binary uses every helper
+
+use proglog_rs::server::grpc::proto::log_server::{self, LogServer};
+use proglog_rs::storage::log::{Log, LogConfig, LogConfigBuilder};
+use rcgen::{
+    BasicConstraints, CertificateParams, DnType, ExtendedKeyUsagePurpose, IsCa, Issuer, KeyPair,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio::net::TcpListener;
+use tonic::transport::Server;
+use tonic::transport::server::{Router, TcpIncoming};
+
+/// Log settings the tests share: 1 MiB segments of up to 1000 records in `dir`
+pub fn log_config(dir: impl AsRef<Path>) -> LogConfigBuilder {
+    LogConfig::builder()
+        .max_store_bytes(1024 * 1024)
+        .max_index_entries(1000)
+        .log_dir(dir.as_ref())
+}
+
+pub fn open_log(dir: impl AsRef<Path>) -> Log {
+    Log::new(log_config(dir).build().unwrap()).unwrap()
+}
+
+/// Binds a listener on a free local port and returns it with its `host:port` address
+pub async fn bind() -> (TcpListener, String) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    (listener, addr)
+}
+
+/// Serves `router` on `listener` in the background
+pub fn serve(listener: TcpListener, router: Router) {
+    tokio::spawn(async move {
+        router
+            .serve_with_incoming(TcpIncoming::from(listener))
+            .await
+            .unwrap();
+    });
+}
+
+/// Serves `router` on a free local port, returning its `host:port` address
+pub async fn spawn_router(router: Router) -> String {
+    let (listener, addr) = bind().await;
+    serve(listener, router);
+    addr
+}
+
+/// Serves `service` over plain HTTP/2, returning the endpoint clients connect to
+pub async fn spawn_server(service: impl log_server::Log) -> String {
+    let addr = spawn_router(Server::builder().add_service(LogServer::new(service))).await;
+    format!("http://{addr}")
+}
+
+/// PEM files for a throwaway CA plus a server and a client certificate signed by it
+pub struct TestPki {
+    pub ca_cert: PathBuf,
+    pub server_cert: PathBuf,
+    pub server_key: PathBuf,
+    pub client_cert: PathBuf,
+    pub client_key: PathBuf,
+}
+
+pub fn generate_pki(dir: &Path) -> Result<TestPki, Box<dyn std::error::Error>> {
+    let ca_key = KeyPair::generate()?;
+    let mut ca_params = CertificateParams::new(Vec::new())?;
+    ca_params
+        .distinguished_name
+        .push(DnType::CommonName, "proglog test CA");
+    ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    let ca_cert = ca_params.self_signed(&ca_key)?;
+    let issuer = Issuer::new(ca_params, ca_key);
+
+    let leaf = |name: &str, sans: Vec<String>, usage: ExtendedKeyUsagePurpose| {
+        let key = KeyPair::generate()?;
+        let mut params = CertificateParams::new(sans)?;
+        params.distinguished_name.push(DnType::CommonName, name);
+        params.extended_key_usages = vec![usage];
+        let cert = params.signed_by(&key, &issuer)?;
+        Ok::<_, rcgen::Error>((cert.pem(), key.serialize_pem()))
+    };
+
+    let (server_cert, server_key) = leaf(
+        "server",
+        vec!["localhost".to_string(), "127.0.0.1".to_string()],
+        ExtendedKeyUsagePurpose::ServerAuth,
+    )?;
+    let (client_cert, client_key) =
+        leaf("client", Vec::new(), ExtendedKeyUsagePurpose::ClientAuth)?;
+
+    let write = |name: &str, contents: String| -> std::io::Result<PathBuf> {
+        let path = dir.join(name);
+        fs::write(&path, contents)?;
+        Ok(path)
+    };
+
+    Ok(TestPki {
+        ca_cert: write("ca.pem", ca_cert.pem())?,
+        server_cert: write("server.pem", server_cert)?,
+        server_key: write("server-key.pem", server_key)?,
+        client_cert: write("client.pem", client_cert)?,
+        client_key: write("client-key.pem", client_key)?,
+    })
+}
@@ -0,0 +1,148 @@
+use proglog_rs::server::grpc::LogService;
+use proglog_rs::server::grpc::proto::log_client::LogClient as GrpcLogClient;
+use proglog_rs::server::grpc::proto::log_server::{LogServer, SERVICE_NAME};
+use proglog_rs::server::grpc::proto::{ConsumeRequest, ProduceRequest};
+use proglog_rs::server::health::{LogHealth, reflection_service};
+use proglog_rs::server::shutdown::Shutdown;
+use std::time::Duration;
+use tempfile::TempDir;
+use tonic::transport::{Channel, Server};
+use tonic_health::pb::HealthCheckRequest;
+use tonic_health::pb::health_check_response::ServingStatus;
+use tonic_health::pb::health_client::HealthClient;
+
+mod common;
+use common::{open_log, spawn_router};
+
+async fn spawn_server(temp_dir: &TempDir, shutdown: &Shutdown) -> Channel {
+    let (health, health_service) = LogHealth::new().await;
+    health.watch_shutdown(shutdown.listener());
+    let service = LogService::new(open_log(temp_dir)).with_health(health);
+
+    let addr = spawn_router(
+        Server::builder()
+            .add_service(health_service)
+            .add_service(reflection_service().unwrap())
+            .add_service(LogServer::new(service)),
+    )
+    .await;
+
+    Channel::from_shared(format!("http://{addr}"))
+        .unwrap()
+        .connect()
+        .await
+        .unwrap()
+}
+
+async fn status(health: &mut HealthClient<Channel>, service: &str) -> ServingStatus {
+    let response = health
+        .check(HealthCheckRequest {
+            service: service.to_string(),
+        })
+        .await
+        .unwrap();
+    response.into_inner().status()
+}
+
+/// Polls until the status changes, since health updates are applied asynchronously
+async fn wait_for_status(health: &mut HealthClient<Channel>, expected: ServingStatus) {
+    for _ in 0..100 {
+        if status(health, "").await == expected && status(health, SERVICE_NAME).await == expected {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("health never became {expected:?}");
+}
+
+#[tokio::test]
+async fn test_not_serving_after_shutdown() {
+    let temp_dir = TempDir::new().unwrap();
+    let shutdown = Shutdown::new();
+    let channel = spawn_server(&temp_dir, &shutdown).await;
+    let mut health = HealthClient::new(channel);
+
+    assert_eq!(status(&mut health, "").await, ServingStatus::Serving);
+    assert_eq!(
+        status(&mut health, SERVICE_NAME).await,
+        ServingStatus::Serving
+    );
+
+    shutdown.trigger();
+    wait_for_status(&mut health, ServingStatus::NotServing).await;
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_not_serving_after_storage_corruption() {
+    use std::os::unix::fs::FileExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    let shutdown = Shutdown::new();
+    let channel = spawn_server(&temp_dir, &shutdown).await;
+    let mut health = HealthClient::new(channel.clone());
+    let mut client = GrpcLogClient::new(channel);
+
+    client
+        .produce(ProduceRequest {
+            record: b"intact".to_vec(),
+        })
+        .await
+        .unwrap();
+
+    // a client error must not affect health
+    let status_code = client
+        .consume(ConsumeRequest { offset: 42 })
+        .await
+        .unwrap_err()
+        .code();
+    assert_eq!(status_code, tonic::Code::OutOfRange);
+    assert_eq!(status(&mut health, "").await, ServingStatus::Serving);
+
+    // overwrite the length prefix of the first record, the store is mapped shared
+    let store_path = temp_dir.path().join(format!("{:020}.log", 0));
+    let file = std::fs::OpenOptions::new()
+        .write(true)
+        .open(store_path)
+        .unwrap();
+    file.write_at(&u64::MAX.to_le_bytes(), 0).unwrap();
+
+    let status_code = client
+        .consume(ConsumeRequest { offset: 0 })
+        .await
+        .unwrap_err()
+        .code();
+    assert_eq!(status_code, tonic::Code::DataLoss);
+    wait_for_status(&mut health, ServingStatus::NotServing).await;
+}
+
+#[tokio::test]
+async fn test_reflection_lists_services() {
+    use tonic_reflection::pb::v1::ServerReflectionRequest;
+    use tonic_reflection::pb::v1::server_reflection_client::ServerReflectionClient;
+    use tonic_reflection::pb::v1::server_reflection_request::MessageRequest;
+    use tonic_reflection::pb::v1::server_reflection_response::MessageResponse;
+
+    let temp_dir = TempDir::new().unwrap();
+    let shutdown = Shutdown::new();
+    let channel = spawn_server(&temp_dir, &shutdown).await;
+    let mut reflection = ServerReflectionClient::new(channel);
+
+    let request = ServerReflectionRequest {
+        host: String::new(),
+        message_request: Some(MessageRequest::ListServices(String::new())),
+    };
+    let mut responses = reflection
+        .server_reflection_info(tokio_stream::once(request))
+        .await
+        .unwrap()
+        .into_inner();
+
+    let response = responses.message().await.unwrap().unwrap();
+    let Some(MessageResponse::ListServicesResponse(list)) = response.message_response else {
+        panic!("unexpected reflection response: {response:?}");
+    };
+    let names: Vec<_> = list.service.into_iter().map(|s| s.name).collect();
+    assert!(names.contains(&SERVICE_NAME.to_string()));
+    assert!(names.contains(&"grpc.health.v1.Health".to_string()));
+}
@@ -0,0 +1,198 @@
+use axum::Router;
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use http_body_util::BodyExt;
+use proglog_rs::discovery::Membership;
+use proglog_rs::server::auth::{AuthInterceptor, Authorizer};
+use proglog_rs::server::grpc::LogService;
+use proglog_rs::server::http::HttpGateway;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tempfile::TempDir;
+use tower::ServiceExt;
+
+mod common;
+use common::open_log;
+
+fn gateway(temp_dir: &TempDir, configure: impl FnOnce(LogService) -> LogService) -> HttpGateway {
+    let service = configure(LogService::new(open_log(temp_dir)));
+    HttpGateway::new(Arc::new(service))
+}
+
+async fn send(router: &Router, request: Request<Body>) -> (StatusCode, Vec<u8>) {
+    let response = router.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    (status, body.to_vec())
+}
+
+async fn send_json(router: &Router, request: Request<Body>) -> (StatusCode, Value) {
+    let (status, body) = send(router, request).await;
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+fn get(uri: &str) -> Request<Body> {
+    Request::get(uri).body(Body::empty()).unwrap()
+}
+
+fn post_raw(record: &[u8]) -> Request<Body> {
+    Request::post("/records")
+        .body(Body::from(record.to_vec()))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_produce_and_consume() {
+    let temp_dir = TempDir::new().unwrap();
+    let router = gateway(&temp_dir, |service| service).router();
+
+    let response = router.clone().oneshot(post_raw(b"raw")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert_eq!(response.headers()["location"], "/records/0");
+
+    let request = Request::post("/records")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            json!({ "record": BASE64.encode(b"json") }).to_string(),
+        ))
+        .unwrap();
+    let (status, body) = send_json(&router, request).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(body, json!({ "offset": 1 }));
+
+    let (status, body) = send_json(&router, get("/records/1")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        body,
+        json!({ "offset": 1, "record": BASE64.encode(b"json") })
+    );
+
+    let request = Request::get("/records/0")
+        .header("accept", "application/octet-stream")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(
+        send(&router, request).await,
+        (StatusCode::OK, b"raw".to_vec())
+    );
+}
+
+#[tokio::test]
+async fn test_range_and_tail() {
+    let temp_dir = TempDir::new().unwrap();
+    let router = gateway(&temp_dir, |service| service).router();
+    for i in 0..5 {
+        send(&router, post_raw(format!("record-{i}").as_bytes())).await;
+    }
+
+    let offsets = |body: &Value| -> Vec<u64> {
+        body["records"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|record| record["offset"].as_u64().unwrap())
+            .collect()
+    };
+
+    let (status, body) = send_json(&router, get("/records?from=1&to=3")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(offsets(&body), [1, 2]);
+    assert_eq!(body["next_offset"], 3);
+    assert_eq!(body["records"][0]["record"], BASE64.encode(b"record-1"));
+
+    let (_, body) = send_json(&router, get("/records?from=3&limit=10")).await;
+    assert_eq!(offsets(&body), [3, 4]);
+    assert_eq!(body["next_offset"], 5);
+
+    let (_, body) = send_json(&router, get("/records?limit=2")).await;
+    assert_eq!(offsets(&body), [0, 1]);
+
+    let (_, body) = send_json(&router, get("/records?tail=2")).await;
+    assert_eq!(offsets(&body), [3, 4]);
+
+    // caught up: nothing to read yet
+    let (status, body) = send_json(&router, get("/records?from=5")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!({ "records": [], "next_offset": 5 }));
+}
+
+#[tokio::test]
+async fn test_errors_carry_grpc_details() {
+    let temp_dir = TempDir::new().unwrap();
+    let router = gateway(&temp_dir, |service| service).router();
+    send(&router, post_raw(b"only")).await;
+
+    let (status, body) = send_json(&router, get("/records/7")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "OUT_OF_RANGE");
+    assert_eq!(body["reason"], "OFFSET_OUT_OF_RANGE");
+    assert_eq!(body["metadata"]["next_offset"], "1");
+
+    let (status, body) = send_json(&router, get("/records?from=9")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["reason"], "OFFSET_OUT_OF_RANGE");
+
+    for uri in ["/records/abc", "/records?from=0&tail=1", "/records?limit=x"] {
+        let (status, body) = send_json(&router, get(uri)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}");
+        assert_eq!(body["reason"], "INVALID_REQUEST", "{uri}");
+    }
+
+    let request = Request::post("/records")
+        .header("content-type", "application/json")
+        .body(Body::from(r#"{"record": "not base64!"}"#))
+        .unwrap();
+    let (status, body) = send_json(&router, request).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["reason"], "INVALID_REQUEST");
+}
+
+#[tokio::test]
+async fn test_follower_rejects_writes_with_leader_hint() {
+    let temp_dir = TempDir::new().unwrap();
+    let membership = Arc::new(Membership::new("node-1", "127.0.0.1:2"));
+    membership.join("node-0", "127.0.0.1:1");
+    membership.set_leader(Some("node-0".to_string()));
+    let router = gateway(&temp_dir, |service| service.with_membership(membership)).router();
+
+    let response = router.clone().oneshot(post_raw(b"write")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response.headers()["x-leader-id"], "node-0");
+}
+
+#[tokio::test]
+async fn test_bearer_token_authorization() {
+    let temp_dir = TempDir::new().unwrap();
+    let authorizer = Arc::new(Authorizer::parse("p, alice, default, produce").unwrap());
+    let tokens = HashMap::from([
+        ("alice-token".to_string(), "alice".to_string()),
+        ("bob-token".to_string(), "bob".to_string()),
+    ]);
+    let router = gateway(&temp_dir, |service| service.with_authorizer(authorizer))
+        .with_interceptor(AuthInterceptor::with_tokens(tokens))
+        .router();
+
+    let produce = |token: &str| {
+        Request::post("/records")
+            .header("authorization", format!("Bearer {token}"))
+            .body(Body::from("secret"))
+            .unwrap()
+    };
+
+    let (status, _) = send(&router, produce("alice-token")).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, body) = send_json(&router, produce("bob-token")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["code"], "PERMISSION_DENIED");
+
+    let (status, _) = send(&router, produce("unknown")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // alice may only produce
+    let (status, _) = send(&router, get("/records/0")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,131 @@
+use proglog_rs::storage::log::{Log, LogConfig};
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn inspect(args: &[&str]) -> (bool, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_proglog-inspect"))
+        .args(args)
+        .output()
+        .unwrap();
+    (
+        output.status.success(),
+        String::from_utf8(output.stdout).unwrap(),
+    )
+}
+
+#[test]
+fn test_inspects_live_log_without_touching_it() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path().to_str().unwrap();
+    let config = LogConfig::builder()
+        .max_store_bytes(200)
+        .max_index_entries(10)
+        .max_record_bytes(64)
+        .log_dir(temp_dir.path())
+        .build()
+        .unwrap();
+    let mut log = Log::new(config).unwrap();
+    for i in 0..12 {
+        log.append(format!("record-{i}").as_bytes()).unwrap();
+    }
+
+    let snapshot = || {
+        let mut files: Vec<_> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| {
+                let path = entry.unwrap().path();
+                (path.clone(), fs::read(path).unwrap())
+            })
+            .collect();
+        files.sort();
+        files
+    };
+    let before = snapshot();
+
+    let (ok, segments) = inspect(&["segments", dir, "-o", "json"]);
+    assert!(ok);
+    let segments: Vec<serde_json::Value> = segments
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(segments.len(), log.segment_count());
+    assert_eq!(segments.last().unwrap()["next_offset"], 12);
+
+    let (ok, records) = inspect(&["records", dir, "--from", "9", "--to", "11"]);
+    assert!(ok);
+    let offsets: Vec<_> = records
+        .lines()
+        .map(|line| line.split('\t').next().unwrap())
+        .collect();
+    assert_eq!(offsets, ["9", "10"]);
+    assert!(records.ends_with("record-10\n"));
+
+    let (ok, index) = inspect(&["index", dir, "--segment", "0"]);
+    assert!(ok);
+    assert!(index.starts_with("0\t0\t0\n"));
+
+    assert_eq!(snapshot(), before);
+}
+
+#[test]
+fn test_verify_and_repair() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path().to_str().unwrap();
+    let config = LogConfig::builder()
+        .max_index_entries(5)
+        .log_dir(temp_dir.path())
+        .build()
+        .unwrap();
+    let mut log = Log::new(config.clone()).unwrap();
+    for i in 0..12 {
+        log.append(format!("record-{i}").as_bytes()).unwrap();
+    }
+    log.close().unwrap();
+
+    let (ok, report) = inspect(&["verify", dir]);
+    assert!(ok);
+    assert_eq!(report, "3 segments, 12 records, 0 issues\n");
+
+    fs::remove_file(temp_dir.path().join("00000000000000000005.idx")).unwrap();
+    let (ok, report) = inspect(&["verify", dir, "-o", "json"]);
+    assert!(!ok);
+    assert_eq!(
+        report,
+        "{\"kind\":\"orphan_store\",\"base_offset\":5,\"repaired\":false}\n"
+    );
+
+    let (ok, report) = inspect(&["verify", dir, "--repair"]);
+    assert!(ok);
+    assert!(report.starts_with("segment 5: store file without an index\trepaired\n"));
+    assert!(report.ends_with("3 segments, 12 records, 1 issues\n"));
+
+    let log = Log::new(config).unwrap();
+    assert_eq!(log.read(7).unwrap(), b"record-7");
+}
+
+#[test]
+fn test_repair_refuses_a_log_open_in_another_process() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path().to_str().unwrap();
+    let config = LogConfig::builder()
+        .log_dir(temp_dir.path())
+        .build()
+        .unwrap();
+    let log = Log::new(config).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_proglog-inspect"))
+        .args(["verify", dir, "--repair"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("is locked by another process"));
+
+    // verifying only reads, so it works next to the writer
+    let (ok, _) = inspect(&["verify", dir]);
+    assert!(ok);
+
+    drop(log);
+    let (ok, _) = inspect(&["verify", dir, "--repair"]);
+    assert!(ok);
+}
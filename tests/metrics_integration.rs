@@ -0,0 +1,66 @@
+use proglog_rs::client::{ClientConfig, LogClient};
+use proglog_rs::server::grpc::LogService;
+use proglog_rs::telemetry::metrics;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tempfile::TempDir;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+mod common;
+use common::{open_log, spawn_server};
+
+async fn free_addr() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    listener.local_addr().unwrap()
+}
+
+async fn scrape(addr: SocketAddr) -> String {
+    // the exporter starts listening in the background
+    for _ in 0..50 {
+        if let Ok(mut stream) = TcpStream::connect(addr).await {
+            let request =
+                format!("GET /metrics HTTP/1.1\r\nHost: {addr}\r\nConnection: close\r\n\r\n");
+            stream.write_all(request.as_bytes()).await.unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            return response;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("metrics endpoint never came up on {addr}");
+}
+
+#[tokio::test]
+async fn test_metrics_endpoint_reports_log_and_rpc_stats() -> Result<(), Box<dyn std::error::Error>>
+{
+    let metrics_addr = free_addr().await;
+    metrics::install(metrics_addr)?;
+
+    let temp_dir = TempDir::new()?;
+    let addr = spawn_server(LogService::new(open_log(&temp_dir))).await;
+
+    let mut client = LogClient::new(ClientConfig {
+        servers: vec![addr],
+        max_retries: 0,
+        ..Default::default()
+    })?;
+    for i in 0..3 {
+        client.produce(format!("record-{i}").as_bytes()).await?;
+    }
+    client.consume(1).await?;
+    assert!(client.consume(99).await.is_err());
+
+    let response = scrape(metrics_addr).await;
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+    assert!(response.contains("proglog_records_appended_total 3"));
+    assert!(response.contains("proglog_next_offset 3"));
+    assert!(response.contains("proglog_segments 1"));
+    assert!(response.contains("proglog_read_duration_seconds_bucket"));
+    assert!(response.contains("proglog_rpc_requests_total{method=\"Produce\",code=\"OK\"} 3"));
+    assert!(
+        response.contains("proglog_rpc_requests_total{method=\"Consume\",code=\"OUT_OF_RANGE\"} 1")
+    );
+
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use proglog_rs::client::{ClientConfig, LogClient};
+use proglog_rs::server::grpc::LogService;
+use proglog_rs::server::grpc::proto::log_server::{Log as _, LogServer};
+use proglog_rs::server::grpc::proto::{ConsumeStreamRequest, ProduceRequest};
+use proglog_rs::server::shutdown::{Shutdown, reclaim_log};
+use std::time::Duration;
+use tempfile::TempDir;
+use tokio_stream::StreamExt;
+use tonic::Request;
+use tonic::transport::Server;
+use tonic::transport::server::TcpIncoming;
+
+mod common;
+use common::{bind, open_log};
+
+#[tokio::test]
+async fn test_shutdown_drains_and_closes_log() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = TempDir::new()?;
+    let shutdown = Shutdown::new();
+    let service = LogService::new(open_log(&temp_dir)).with_shutdown(shutdown.listener());
+    let log = service.log();
+
+    let (listener, addr) = bind().await;
+    let server_shutdown = shutdown.listener();
+    let server = tokio::spawn(async move {
+        Server::builder()
+            .add_service(LogServer::new(service))
+            .serve_with_incoming_shutdown(TcpIncoming::from(listener), server_shutdown.wait())
+            .await
+    });
+
+    let mut client = LogClient::new(ClientConfig {
+        servers: vec![format!("http://{addr}")],
+        max_retries: 1,
+        request_timeout: Duration::from_secs(2),
+        ..Default::default()
+    })?;
+    for i in 0..10 {
+        client.produce(format!("record-{i}").as_bytes()).await?;
+    }
+    drop(client);
+
+    shutdown.trigger();
+    tokio::time::timeout(Duration::from_secs(5), server).await???;
+
+    reclaim_log(log, Duration::from_secs(5)).await?.close()?;
+
+    let log = open_log(&temp_dir);
+    assert_eq!(log.next_offset(), 10);
+    assert_eq!(log.read(9)?, b"record-9");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_calls_are_rejected_after_shutdown() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = TempDir::new()?;
+    let shutdown = Shutdown::new();
+    let service = LogService::new(open_log(&temp_dir)).with_shutdown(shutdown.listener());
+
+    shutdown.trigger();
+
+    let status = service
+        .produce(Request::new(ProduceRequest {
+            record: b"late".to_vec(),
+        }))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::Unavailable);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_following_streams_end_on_shutdown() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = TempDir::new()?;
+    let shutdown = Shutdown::new();
+    let service = LogService::new(open_log(&temp_dir)).with_shutdown(shutdown.listener());
+    let log = service.log();
+
+    let mut stream = service
+        .consume_stream(Request::new(ConsumeStreamRequest {
+            offset: 0,
+            follow: true,
+            ..Default::default()
+        }))
+        .await?
+        .into_inner();
+
+    service
+        .produce(Request::new(ProduceRequest {
+            record: b"streamed".to_vec(),
+        }))
+        .await?;
+    let response = stream.next().await.unwrap()?;
+    assert_eq!(
+        (response.offset, response.record),
+        (0, b"streamed".to_vec())
+    );
+
+    shutdown.trigger();
+    let status = stream.next().await.unwrap().unwrap_err();
+    assert_eq!(status.code(), tonic::Code::Unavailable);
+    assert!(stream.next().await.is_none());
+
+    // the stream released its handle on the log
+    drop(service);
+    reclaim_log(log, Duration::from_secs(5)).await?.close()?;
+
+    Ok(())
+}
@@ -29,7 +29,7 @@ fn test_store_index_coordination() -> Result<(), Box<dyn std::error::Error>> {
             positions.push(position);
         }
 
-        assert_eq!(index.len(), records.len().try_into().unwrap());
+        assert_eq!(index.len(), u64::try_from(records.len()).unwrap());
     }
 
     {
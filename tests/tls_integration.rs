@@ -0,0 +1,114 @@
+use proglog_rs::client::{ClientConfig, LogClient};
+use proglog_rs::errors::ProglogError;
+use proglog_rs::server::grpc::LogService;
+use proglog_rs::server::grpc::proto::log_server::LogServer;
+use proglog_rs::server::tls::{ClientTls, ServerTls};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tempfile::TempDir;
+use tonic::transport::Server;
+
+mod common;
+use common::{generate_pki, open_log, spawn_router};
+
+async fn spawn_tls_server(
+    log_dir: &Path,
+    tls: ServerTls,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let service = LogService::new(open_log(log_dir));
+    let mut server = Server::builder().tls_config(tls.load()?)?;
+    let addr = spawn_router(server.add_service(LogServer::new(service))).await;
+    Ok(format!("https://{addr}"))
+}
+
+fn client_config(addr: String, tls: ClientTls) -> ClientConfig {
+    ClientConfig {
+        servers: vec![addr],
+        max_retries: 1,
+        initial_backoff: Duration::from_millis(10),
+        request_timeout: Duration::from_secs(2),
+        tls: Some(tls),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_tls_produce_and_consume() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = TempDir::new()?;
+    let pki = generate_pki(temp_dir.path())?;
+
+    let addr = spawn_tls_server(
+        &temp_dir.path().join("log"),
+        ServerTls {
+            cert_path: pki.server_cert.clone(),
+            key_path: pki.server_key.clone(),
+            client_ca_path: None,
+        },
+    )
+    .await?;
+
+    let tls = ClientTls {
+        ca_cert_path: pki.ca_cert.clone(),
+        domain_name: Some("localhost".to_string()),
+        cert_path: None,
+        key_path: None,
+    };
+    let mut client = LogClient::new(client_config(addr, tls))?;
+
+    let offset = client.produce(b"encrypted").await?;
+    assert_eq!(client.consume(offset).await?, b"encrypted");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_mutual_tls_requires_client_certificate() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = TempDir::new()?;
+    let pki = generate_pki(temp_dir.path())?;
+
+    let addr = spawn_tls_server(
+        &temp_dir.path().join("log"),
+        ServerTls {
+            cert_path: pki.server_cert.clone(),
+            key_path: pki.server_key.clone(),
+            client_ca_path: Some(pki.ca_cert.clone()),
+        },
+    )
+    .await?;
+
+    // without a client certificate the handshake is rejected
+    let anonymous = ClientTls {
+        ca_cert_path: pki.ca_cert.clone(),
+        domain_name: Some("localhost".to_string()),
+        cert_path: None,
+        key_path: None,
+    };
+    let mut client = LogClient::new(client_config(addr.clone(), anonymous))?;
+    assert!(client.produce(b"anonymous").await.is_err());
+
+    let authenticated = ClientTls {
+        ca_cert_path: pki.ca_cert.clone(),
+        domain_name: Some("localhost".to_string()),
+        cert_path: Some(pki.client_cert.clone()),
+        key_path: Some(pki.client_key.clone()),
+    };
+    let mut client = LogClient::new(client_config(addr, authenticated))?;
+    let offset = client.produce(b"authenticated").await?;
+    assert_eq!(client.consume(offset).await?, b"authenticated");
+
+    Ok(())
+}
+
+#[test]
+fn test_client_tls_requires_cert_and_key_together() {
+    let tls = ClientTls {
+        ca_cert_path: PathBuf::from("ca.pem"),
+        domain_name: None,
+        cert_path: Some(PathBuf::from("client.pem")),
+        key_path: None,
+    };
+    assert!(matches!(
+        tls.load(),
+        Err(ProglogError::Config { message }) if message.contains("together")
+    ));
+}
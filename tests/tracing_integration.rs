@@ -0,0 +1,122 @@
+//! Installs the global tracing subscriber, so this binary holds a single test.
+
+use opentelemetry_proto::tonic::collector::trace::v1::trace_service_server::{
+    TraceService, TraceServiceServer,
+};
+use opentelemetry_proto::tonic::collector::trace::v1::{
+    ExportTraceServiceRequest, ExportTraceServiceResponse,
+};
+use opentelemetry_proto::tonic::trace::v1::Span;
+use proglog_rs::server::grpc::LogService;
+use proglog_rs::server::grpc::proto::ProduceRequest;
+use proglog_rs::server::grpc::proto::log_client::LogClient as GrpcLogClient;
+use proglog_rs::telemetry::trace;
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+use tonic::transport::Server;
+use tonic::{Request, Response, Status};
+
+mod common;
+use common::{open_log, spawn_router, spawn_server};
+
+const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+const CLIENT_SPAN_ID: &str = "00f067aa0ba902b7";
+
+/// Stand-in OTLP collector keeping every exported span
+#[derive(Default, Clone)]
+struct Collector {
+    spans: Arc<Mutex<Vec<Span>>>,
+}
+
+#[tonic::async_trait]
+impl TraceService for Collector {
+    async fn export(
+        &self,
+        request: Request<ExportTraceServiceRequest>,
+    ) -> Result<Response<ExportTraceServiceResponse>, Status> {
+        let spans = request
+            .into_inner()
+            .resource_spans
+            .into_iter()
+            .flat_map(|resource| resource.scope_spans)
+            .flat_map(|scope| scope.spans);
+        self.spans.lock().unwrap().extend(spans);
+
+        Ok(Response::new(ExportTraceServiceResponse {
+            partial_success: None,
+        }))
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_trace_context_propagates_into_storage_spans() -> Result<(), Box<dyn std::error::Error>>
+{
+    let collector = Collector::default();
+    let service = TraceServiceServer::new(collector.clone());
+    let collector_addr = format!(
+        "http://{}",
+        spawn_router(Server::builder().add_service(service)).await
+    );
+
+    let provider = trace::otlp_provider(&collector_addr, "test-node")?;
+    let guard = trace::init("info", Some(provider))?;
+
+    let temp_dir = TempDir::new()?;
+    let server_addr = spawn_server(LogService::new(open_log(&temp_dir))).await;
+
+    let mut client = GrpcLogClient::connect(server_addr).await?;
+    let mut request = Request::new(ProduceRequest {
+        record: b"traced".to_vec(),
+    });
+    request.metadata_mut().insert(
+        "traceparent",
+        format!("00-{TRACE_ID}-{CLIENT_SPAN_ID}-01").parse()?,
+    );
+    client.produce(request).await?;
+
+    // force_flush blocks until the exporter has sent the batch
+    let guard = tokio::task::spawn_blocking(move || {
+        guard.flush();
+        guard
+    })
+    .await?;
+
+    let spans = collector.spans.lock().unwrap().clone();
+    let find = |name: &str| {
+        spans
+            .iter()
+            .find(|span| span.name == name && hex(&span.trace_id) == TRACE_ID)
+            .unwrap_or_else(|| panic!("no `{name}` span in trace, got {spans:#?}"))
+    };
+
+    // client span -> Produce -> Log::append -> Segment::append -> Store::append
+    let rpc = find("Produce");
+    assert_eq!(hex(&rpc.parent_span_id), CLIENT_SPAN_ID);
+
+    let appends: Vec<_> = spans
+        .iter()
+        .filter(|span| span.name == "append" && hex(&span.trace_id) == TRACE_ID)
+        .collect();
+    assert_eq!(appends.len(), 3, "log, segment and store append spans");
+
+    let log_append = appends
+        .iter()
+        .find(|span| span.parent_span_id == rpc.span_id)
+        .expect("Log::append is a child of the RPC span");
+    let segment_append = appends
+        .iter()
+        .find(|span| span.parent_span_id == log_append.span_id)
+        .expect("Segment::append is a child of Log::append");
+    assert!(
+        appends
+            .iter()
+            .any(|span| span.parent_span_id == segment_append.span_id)
+    );
+
+    drop(guard);
+    Ok(())
+}